@@ -0,0 +1,97 @@
+//! ChaCha20-Poly1305 layer for the zk chat demo.
+//! Derives the AEAD key from the 32 byte Kyber768 shared secret with HKDF-SHA256.
+//! Output `cipher` is ciphertext || 16 byte tag, the segment the program stores before kem and proof.
+
+use anyhow::{anyhow, ensure, Result};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+/// HKDF info string; differs from the AES-256-GCM path so keys are never shared across ciphers
+pub const KDF_INFO: &[u8] = b"zk-chat:kyber768:chacha20poly1305:v1";
+/// Shared secret length produced by kem-cli (ssB64)
+pub const SS_LEN: usize = 32;
+/// Nonce length, matching ChatMsg.nonce
+pub const NONCE_LEN: usize = 12;
+/// Poly1305 tag appended to the ciphertext
+pub const TAG_LEN: usize = 16;
+/// HKDF info prefix for nonce derivation; followed by slot_le(8) || counter_le(4)
+pub const NONCE_INFO: &[u8] = b"zk-chat:nonce:v1";
+
+/// Derives the 12 byte nonce as HKDF-SHA256(ikm = ss, salt = none, info = NONCE_INFO || slot_le || counter_le)
+/// Unique per (secret, slot, counter), so the recipient can recompute it from the ChatMsg slot
+pub fn derive_nonce(ss: &[u8], slot: u64, counter: u32) -> Result<[u8; NONCE_LEN]> {
+    ensure!(ss.len() == SS_LEN, "shared secret must be {SS_LEN} bytes, got {}", ss.len());
+    let info = [NONCE_INFO, &slot.to_le_bytes(), &counter.to_le_bytes()].concat();
+    let mut nonce = [0u8; NONCE_LEN];
+    Hkdf::<Sha256>::new(None, ss)
+        .expand(&info, &mut nonce)
+        .map_err(|_| anyhow!("hkdf expand"))?;
+    Ok(nonce)
+}
+
+/// Derives the 32 byte AEAD key from the KEM shared secret
+pub fn derive_key(ss: &[u8]) -> Result<[u8; 32]> {
+    ensure!(ss.len() == SS_LEN, "shared secret must be {SS_LEN} bytes, got {}", ss.len());
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, ss)
+        .expand(KDF_INFO, &mut key)
+        .map_err(|_| anyhow!("hkdf expand"))?;
+    Ok(key)
+}
+
+/// Encrypts plaintext and returns cipher = ciphertext || tag
+pub fn seal(ss: &[u8], nonce: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    ensure!(nonce.len() == NONCE_LEN, "nonce must be {NONCE_LEN} bytes, got {}", nonce.len());
+    let key = derive_key(ss)?;
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(nonce), plaintext)
+        .map_err(|_| anyhow!("encrypt"))
+}
+
+/// Decrypts cipher = ciphertext || tag; fails if the tag does not verify
+pub fn open(ss: &[u8], nonce: &[u8], cipher: &[u8]) -> Result<Vec<u8>> {
+    ensure!(nonce.len() == NONCE_LEN, "nonce must be {NONCE_LEN} bytes, got {}", nonce.len());
+    ensure!(cipher.len() >= TAG_LEN, "cipher shorter than the {TAG_LEN} byte tag");
+    let key = derive_key(ss)?;
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(Nonce::from_slice(nonce), cipher)
+        .map_err(|_| anyhow!("authentication failed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pqcrypto_kyber::kyber768 as kem;
+    use pqcrypto_traits::kem::SharedSecret as _;
+
+    /// Same inputs reproduce the nonce; distinct counters and slots give distinct nonces
+    #[test]
+    fn derive_nonce_unique_and_reproducible() {
+        let ss = [0x11u8; SS_LEN];
+        let n = derive_nonce(&ss, 42, 7).unwrap();
+        assert_eq!(n, derive_nonce(&ss, 42, 7).unwrap());
+        assert_eq!(n.to_vec(), [0x13, 0xe5, 0xb2, 0x33, 0x12, 0xf3, 0x57, 0x0b, 0x12, 0x53, 0x5e, 0x6f]);
+        assert_ne!(n, derive_nonce(&ss, 42, 8).unwrap());
+        assert_ne!(n, derive_nonce(&ss, 43, 7).unwrap());
+        assert!(derive_nonce(&ss[..16], 42, 7).is_err());
+    }
+
+    /// Sender seals with the encapsulated secret, recipient opens with the decapsulated one
+    #[test]
+    fn round_trip_with_kem_secret() {
+        let (pk, sk) = kem::keypair();
+        let (ss_send, ct) = kem::encapsulate(&pk);
+        let ss_recv = kem::decapsulate(&ct, &sk);
+        let nonce = [7u8; NONCE_LEN];
+
+        let cipher = seal(ss_send.as_bytes(), &nonce, b"hello pq chat").unwrap();
+        assert_eq!(cipher.len(), b"hello pq chat".len() + TAG_LEN);
+        assert_eq!(open(ss_recv.as_bytes(), &nonce, &cipher).unwrap(), b"hello pq chat");
+
+        let mut tampered = cipher.clone();
+        tampered[0] ^= 1;
+        assert!(open(ss_recv.as_bytes(), &nonce, &tampered).is_err());
+        assert!(open(ss_recv.as_bytes(), &[0u8; NONCE_LEN], &cipher).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! ChaCha20-Poly1305 AEAD CLI for the zk chat demo.
+//! Prints one JSON object to stdout per command.
+//! Keeps JSON field names in the kem-cli style (ssB64 nonceB64 cipherB64 ptB64).
+
+use aead_cli::{derive_nonce, open, seal};
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+
+/// Defines the CLI and the selected subcommand.
+#[derive(Parser, Debug)]
+#[command(name = "aead-cli")]
+#[command(about = "ChaCha20-Poly1305 seal/open keyed by a Kyber768 shared secret", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// Lists available subcommands.
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Encrypts base64 plaintext and prints the cipher segment as JSON.
+    Seal {
+        #[arg(long)]
+        ss: String,
+        #[arg(long)]
+        nonce: String,
+        #[arg(long)]
+        pt: String,
+    },
+    /// Derives the 12 byte nonce for (shared secret, slot, counter) and prints JSON.
+    Nonce {
+        #[arg(long)]
+        ss: String,
+        #[arg(long)]
+        slot: u64,
+        #[arg(long, default_value_t = 0)]
+        counter: u32,
+    },
+    /// Decrypts a base64 cipher segment and prints the plaintext as JSON.
+    Open {
+        #[arg(long)]
+        ss: String,
+        #[arg(long)]
+        nonce: String,
+        #[arg(long)]
+        cipher: String,
+    },
+}
+
+/// Holds JSON output of seal.
+#[derive(Serialize)]
+struct OutSeal {
+    alg: &'static str,
+    #[serde(rename = "cipherB64")]
+    cipher_b64: String,
+    cipher_len: usize,
+}
+
+/// Holds JSON output of nonce.
+#[derive(Serialize)]
+struct OutNonce {
+    #[serde(rename = "nonceB64")]
+    nonce_b64: String,
+    slot: u64,
+    counter: u32,
+}
+
+/// Holds JSON output of open.
+#[derive(Serialize)]
+struct OutOpen {
+    alg: &'static str,
+    #[serde(rename = "ptB64")]
+    pt_b64: String,
+    pt_len: usize,
+}
+
+fn main() -> Result<()> {
+    // Parses flags and dispatches.
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Seal { ss, nonce, pt } => {
+            let cipher = seal(&STANDARD.decode(ss)?, &STANDARD.decode(nonce)?, &STANDARD.decode(pt)?)?;
+            let out = OutSeal {
+                alg: "chacha20poly1305",
+                cipher_b64: STANDARD.encode(&cipher),
+                cipher_len: cipher.len(),
+            };
+            println!("{}", serde_json::to_string(&out)?);
+        }
+        Commands::Nonce { ss, slot, counter } => {
+            let nonce = derive_nonce(&STANDARD.decode(ss)?, slot, counter)?;
+            let out = OutNonce { nonce_b64: STANDARD.encode(nonce), slot, counter };
+            println!("{}", serde_json::to_string(&out)?);
+        }
+        Commands::Open { ss, nonce, cipher } => {
+            let pt = open(&STANDARD.decode(ss)?, &STANDARD.decode(nonce)?, &STANDARD.decode(cipher)?)?;
+            let out = OutOpen {
+                alg: "chacha20poly1305",
+                pt_b64: STANDARD.encode(&pt),
+                pt_len: pt.len(),
+            };
+            println!("{}", serde_json::to_string(&out)?);
+        }
+    }
+
+    Ok(())
+}
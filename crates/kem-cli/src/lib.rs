@@ -0,0 +1,115 @@
+//! Kyber768 KEM helpers shared by kem-cli and Rust clients.
+//! Every input is length-checked before pqcrypto parses it; a bad length is a typed KemError, never a panic.
+
+use core::fmt;
+
+// Uses Kyber768 as the concrete KEM.
+use pqcrypto_kyber::kyber768 as kyber;
+// Brings KEM traits for as_bytes and from_bytes.
+use pqcrypto_traits::kem::{Ciphertext as _, PublicKey as _, SecretKey as _, SharedSecret as _};
+
+/// Algorithm name reported in the CLI output.
+pub const ALG: &str = "kyber768";
+
+/// Why a KEM input was refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KemError {
+    /// Public key is not public_key_len() bytes.
+    InvalidPublicKeyLength,
+    /// Secret key is not secret_key_len() bytes.
+    InvalidSecretKeyLength,
+    /// Ciphertext is not ciphertext_len() bytes.
+    InvalidCiphertextLength,
+}
+
+impl fmt::Display for KemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KemError::InvalidPublicKeyLength => "invalid Kyber768 public key length",
+            KemError::InvalidSecretKeyLength => "invalid Kyber768 secret key length",
+            KemError::InvalidCiphertextLength => "invalid Kyber768 ciphertext length",
+        })
+    }
+}
+
+impl std::error::Error for KemError {}
+
+/// Public key length in bytes (1184).
+pub fn public_key_len() -> usize {
+    kyber::public_key_bytes()
+}
+
+/// Secret key length in bytes (2400).
+pub fn secret_key_len() -> usize {
+    kyber::secret_key_bytes()
+}
+
+/// Ciphertext length in bytes (1088).
+pub fn ciphertext_len() -> usize {
+    kyber::ciphertext_bytes()
+}
+
+/// Shared secret length in bytes (32).
+pub fn shared_secret_len() -> usize {
+    kyber::shared_secret_bytes()
+}
+
+/// Generates a keypair as (pk, sk); pqcrypto draws from the OS RNG.
+pub fn gen() -> (Vec<u8>, Vec<u8>) {
+    let (pk, sk) = kyber::keypair();
+    (pk.as_bytes().to_vec(), sk.as_bytes().to_vec())
+}
+
+/// Encapsulates to a public key and returns (ct, ss).
+pub fn encap(pk: &[u8]) -> Result<(Vec<u8>, Vec<u8>), KemError> {
+    let pk = kyber::PublicKey::from_bytes(pk).map_err(|_| KemError::InvalidPublicKeyLength)?;
+    let (ss, ct) = kyber::encapsulate(&pk);
+    Ok((ct.as_bytes().to_vec(), ss.as_bytes().to_vec()))
+}
+
+/// Decapsulates a ciphertext with a secret key and returns ss.
+/// A well-formed ciphertext for another key yields an unrelated secret (implicit rejection), not an error.
+pub fn decap(sk: &[u8], ct: &[u8]) -> Result<Vec<u8>, KemError> {
+    let sk = kyber::SecretKey::from_bytes(sk).map_err(|_| KemError::InvalidSecretKeyLength)?;
+    let ct = kyber::Ciphertext::from_bytes(ct).map_err(|_| KemError::InvalidCiphertextLength)?;
+    Ok(kyber::decapsulate(&ct, &sk).as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// gen returns keys of the advertised lengths, and two calls differ.
+    #[test]
+    fn gen_lengths() {
+        let (pk, sk) = gen();
+        assert_eq!((pk.len(), sk.len()), (public_key_len(), secret_key_len()));
+        assert_eq!((pk.len(), sk.len()), (1184, 2400));
+        assert_ne!(gen().0, pk);
+    }
+
+    /// encap yields a ciphertext and secret of the advertised lengths; a wrong-length key is a typed error.
+    #[test]
+    fn encap_checks_key_length() {
+        let (pk, _) = gen();
+        let (ct, ss) = encap(&pk).unwrap();
+        assert_eq!((ct.len(), ss.len()), (ciphertext_len(), shared_secret_len()));
+        for len in [0, pk.len() - 1, pk.len() + 1] {
+            assert_eq!(encap(&vec![0u8; len]), Err(KemError::InvalidPublicKeyLength));
+        }
+    }
+
+    /// decap recovers encap's secret; wrong-length keys or ciphertexts are typed errors.
+    #[test]
+    fn decap_round_trip_and_lengths() {
+        let (pk, sk) = gen();
+        let (ct, ss) = encap(&pk).unwrap();
+        assert_eq!(decap(&sk, &ct).unwrap(), ss);
+        assert_ne!(decap(&gen().1, &ct).unwrap(), ss);
+
+        assert_eq!(decap(&sk[1..], &ct), Err(KemError::InvalidSecretKeyLength));
+        assert_eq!(decap(&sk, &ct[1..]), Err(KemError::InvalidCiphertextLength));
+        assert_eq!(decap(&sk, &[]), Err(KemError::InvalidCiphertextLength));
+        assert_eq!(KemError::InvalidCiphertextLength.to_string(), "invalid Kyber768 ciphertext length");
+    }
+}
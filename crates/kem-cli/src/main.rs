@@ -1,16 +1,14 @@
 //! Kyber768 KEM CLI for the zk chat demo.
 //! Prints one JSON object to stdout per command.
-//! Keeps JSON field names pkB64 ctB64 ssB64 via serde rename.
+//! Keeps JSON field names pkB64 ctB64 ssB64 by default; --encoding base58 switches them to pkB58 ctB58 ssB58.
 
-use anyhow::{anyhow, Result};
+use anyhow::{ensure, Result};
 use base64::{engine::general_purpose::STANDARD, Engine};
-use clap::{Parser, Subcommand};
-use serde::Serialize;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{ser::SerializeMap, Serialize, Serializer};
 
-// Uses Kyber768 as the concrete KEM.
-use pqcrypto_kyber::kyber768 as kem;
-// Brings KEM traits for as_bytes and from_bytes.
-use pqcrypto_traits::kem::{Ciphertext as _, PublicKey as _, SecretKey as _, SharedSecret as _};
+// Length-checked Kyber768 operations from this crate's library.
+use kem::ALG;
 
 /// Defines the CLI and the selected subcommand.
 #[derive(Parser, Debug)]
@@ -19,19 +17,61 @@ use pqcrypto_traits::kem::{Ciphertext as _, PublicKey as _, SecretKey as _, Shar
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Encoding of every key, ciphertext and secret, in arguments and output alike.
+    #[arg(long, global = true, value_enum, default_value_t = Encoding::Base64)]
+    encoding: Encoding,
+}
+
+/// Text encoding for byte strings; the JSON key suffix names it so outputs are never ambiguous.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Encoding {
+    /// Standard padded base64 (the default, as the demo scripts expect).
+    Base64,
+    /// Bitcoin-alphabet base58, as Solana tooling uses for keys and addresses.
+    Base58,
+}
+
+impl Encoding {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Base64 => STANDARD.encode(bytes),
+            Encoding::Base58 => bs58::encode(bytes).into_string(),
+        }
+    }
+
+    fn decode(self, text: &str) -> Result<Vec<u8>> {
+        Ok(match self {
+            Encoding::Base64 => STANDARD.decode(text)?,
+            Encoding::Base58 => bs58::decode(text).into_vec()?,
+        })
+    }
+
+    /// JSON key for an encoded field, e.g. pkB64 or pkB58.
+    fn key(self, name: &str) -> String {
+        match self {
+            Encoding::Base64 => format!("{name}B64"),
+            Encoding::Base58 => format!("{name}B58"),
+        }
+    }
 }
 
 /// Lists available subcommands.
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Generates a Kyber768 keypair and prints JSON.
-    Gen,
-    /// Encapsulates to a base64 public key and prints JSON.
+    /// With --count N prints a JSON array of N keypairs (or N lines with --ndjson).
+    Gen {
+        #[arg(long)]
+        count: Option<usize>,
+        #[arg(long, requires = "count")]
+        ndjson: bool,
+    },
+    /// Encapsulates to an encoded public key and prints JSON.
     Encap {
         #[arg(long)]
         pk: String,
     },
-    /// Decapsulates with a base64 secret key and ciphertext and prints JSON.
+    /// Decapsulates with an encoded secret key and ciphertext and prints JSON.
     Decap {
         #[arg(long)]
         sk: String,
@@ -41,84 +81,200 @@ enum Commands {
 }
 
 /// Holds JSON output of gen.
-#[derive(Serialize)]
 struct OutGen {
-    alg: &'static str,
-    #[serde(rename = "pkB64")]
-    pk_b64: String,
-    #[serde(rename = "skB64")]
-    sk_b64: String,
+    encoding: Encoding,
+    pk: String,
+    sk: String,
     pk_len: usize,
     sk_len: usize,
 }
 
 /// Holds JSON output of encap.
-#[derive(Serialize)]
 struct OutEncap {
-    alg: &'static str,
-    #[serde(rename = "ctB64")]
-    ct_b64: String,
-    #[serde(rename = "ssB64")]
-    ss_b64: String,
+    encoding: Encoding,
+    ct: String,
+    ss: String,
     ct_len: usize,
     ss_len: usize,
 }
 
 /// Holds JSON output of decap.
-#[derive(Serialize)]
 struct OutDecap {
-    alg: &'static str,
-    #[serde(rename = "ssB64")]
-    ss_b64: String,
+    encoding: Encoding,
+    ss: String,
     ss_len: usize,
 }
 
+// Serialized by hand because the key suffix follows the encoding; field order matches the former derives.
+impl Serialize for OutGen {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut m = s.serialize_map(Some(5))?;
+        m.serialize_entry("alg", ALG)?;
+        m.serialize_entry(&self.encoding.key("pk"), &self.pk)?;
+        m.serialize_entry(&self.encoding.key("sk"), &self.sk)?;
+        m.serialize_entry("pk_len", &self.pk_len)?;
+        m.serialize_entry("sk_len", &self.sk_len)?;
+        m.end()
+    }
+}
+
+impl Serialize for OutEncap {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut m = s.serialize_map(Some(5))?;
+        m.serialize_entry("alg", ALG)?;
+        m.serialize_entry(&self.encoding.key("ct"), &self.ct)?;
+        m.serialize_entry(&self.encoding.key("ss"), &self.ss)?;
+        m.serialize_entry("ct_len", &self.ct_len)?;
+        m.serialize_entry("ss_len", &self.ss_len)?;
+        m.end()
+    }
+}
+
+impl Serialize for OutDecap {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut m = s.serialize_map(Some(3))?;
+        m.serialize_entry("alg", ALG)?;
+        m.serialize_entry(&self.encoding.key("ss"), &self.ss)?;
+        m.serialize_entry("ss_len", &self.ss_len)?;
+        m.end()
+    }
+}
+
+/// Upper bound for gen --count.
+const MAX_COUNT: usize = 100_000;
+
+/// Generates one keypair as its JSON output.
+fn gen_one(enc: Encoding) -> OutGen {
+    let (pk, sk) = kem::gen();
+    OutGen {
+        encoding: enc,
+        pk: enc.encode(&pk),
+        sk: enc.encode(&sk),
+        pk_len: pk.len(),
+        sk_len: sk.len(),
+    }
+}
+
+/// Generates count keypairs in one process; pqcrypto draws from the OS RNG for each.
+fn gen_many(count: usize, enc: Encoding) -> Result<Vec<OutGen>> {
+    ensure!((1..=MAX_COUNT).contains(&count), "--count must be between 1 and {MAX_COUNT}");
+    Ok((0..count).map(|_| gen_one(enc)).collect())
+}
+
+/// Encapsulates to an encoded public key.
+fn encap(pk: &str, enc: Encoding) -> Result<OutEncap> {
+    let (ct, ss) = kem::encap(&enc.decode(pk)?)?;
+    Ok(OutEncap {
+        encoding: enc,
+        ct: enc.encode(&ct),
+        ss: enc.encode(&ss),
+        ct_len: ct.len(),
+        ss_len: ss.len(),
+    })
+}
+
+/// Decapsulates an encoded ciphertext with an encoded secret key.
+fn decap(sk: &str, ct: &str, enc: Encoding) -> Result<OutDecap> {
+    let ss = kem::decap(&enc.decode(sk)?, &enc.decode(ct)?)?;
+    Ok(OutDecap {
+        encoding: enc,
+        ss: enc.encode(&ss),
+        ss_len: ss.len(),
+    })
+}
+
 fn main() -> Result<()> {
     // Parses flags and dispatches.
     let cli = Cli::parse();
+    let enc = cli.encoding;
 
     match cli.command {
-        Commands::Gen => {
-            let (pk, sk) = kem::keypair();
-            let out = OutGen {
-                alg: "kyber768",
-                pk_b64: STANDARD.encode(pk.as_bytes()),
-                sk_b64: STANDARD.encode(sk.as_bytes()),
-                pk_len: kem::public_key_bytes(),
-                sk_len: kem::secret_key_bytes(),
-            };
-            println!("{}", serde_json::to_string(&out)?);
+        Commands::Gen { count: None, .. } => {
+            println!("{}", serde_json::to_string(&gen_one(enc))?);
+        }
+        Commands::Gen { count: Some(n), ndjson } => {
+            let outs = gen_many(n, enc)?;
+            if ndjson {
+                for out in &outs {
+                    println!("{}", serde_json::to_string(out)?);
+                }
+            } else {
+                println!("{}", serde_json::to_string(&outs)?);
+            }
         }
         Commands::Encap { pk } => {
-            let pk_bytes = STANDARD.decode(pk)?;
-            let pk = kem::PublicKey::from_bytes(&pk_bytes)
-                .map_err(|_| anyhow!("invalid Kyber768 public key length"))?;
-            let (ss, ct) = kem::encapsulate(&pk);
-            let out = OutEncap {
-                alg: "kyber768",
-                ct_b64: STANDARD.encode(ct.as_bytes()),
-                ss_b64: STANDARD.encode(ss.as_bytes()),
-                ct_len: kem::ciphertext_bytes(),
-                ss_len: kem::shared_secret_bytes(),
-            };
-            println!("{}", serde_json::to_string(&out)?);
+            println!("{}", serde_json::to_string(&encap(&pk, enc)?)?);
         }
         Commands::Decap { sk, ct } => {
-            let sk_bytes = STANDARD.decode(sk)?;
-            let ct_bytes = STANDARD.decode(ct)?;
-            let sk = kem::SecretKey::from_bytes(&sk_bytes)
-                .map_err(|_| anyhow!("invalid Kyber768 secret key length"))?;
-            let ct = kem::Ciphertext::from_bytes(&ct_bytes)
-                .map_err(|_| anyhow!("invalid Kyber768 ciphertext length"))?;
-            let ss = kem::decapsulate(&ct, &sk);
-            let out = OutDecap {
-                alg: "kyber768",
-                ss_b64: STANDARD.encode(ss.as_bytes()),
-                ss_len: kem::shared_secret_bytes(),
-            };
-            println!("{}", serde_json::to_string(&out)?);
+            println!("{}", serde_json::to_string(&decap(&sk, &ct, enc)?)?);
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// --count 3 yields three distinct keypairs.
+    #[test]
+    fn gen_count_three_distinct() {
+        let outs = gen_many(3, Encoding::Base64).unwrap();
+        assert_eq!(outs.len(), 3);
+        let pks: HashSet<_> = outs.iter().map(|o| o.pk.clone()).collect();
+        let sks: HashSet<_> = outs.iter().map(|o| o.sk.clone()).collect();
+        assert_eq!((pks.len(), sks.len()), (3, 3));
+    }
+
+    /// Reads one string field from a command's JSON output.
+    fn field(out: &impl Serialize, name: &str) -> String {
+        let json: serde_json::Value = serde_json::from_str(&serde_json::to_string(out).unwrap()).unwrap();
+        json[name].as_str().unwrap().to_string()
+    }
+
+    /// gen → encap → decap through the printed JSON yields the same 32-byte shared secret on both sides.
+    #[test]
+    fn encap_decap_shared_secrets_agree() {
+        let keys = gen_one(Encoding::Base64);
+        let enc = encap(&field(&keys, "pkB64"), Encoding::Base64).unwrap();
+        let dec = decap(&field(&keys, "skB64"), &field(&enc, "ctB64"), Encoding::Base64).unwrap();
+
+        let ss_enc = STANDARD.decode(field(&enc, "ssB64")).unwrap();
+        let ss_dec = STANDARD.decode(field(&dec, "ssB64")).unwrap();
+        assert_eq!(ss_enc.len(), 32);
+        assert_eq!(ss_enc, ss_dec);
+
+        // Another keypair's secret key does not recover the sender's secret.
+        let other = decap(&field(&gen_one(Encoding::Base64), "skB64"), &field(&enc, "ctB64"), Encoding::Base64).unwrap();
+        assert_ne!(STANDARD.decode(field(&other, "ssB64")).unwrap(), ss_enc);
+        assert!(encap("AAAA", Encoding::Base64).is_err());
+    }
+
+    /// --encoding base58 outputs pkB58/skB58/ctB58/ssB58 that decode to the Kyber768 lengths and round-trip.
+    #[test]
+    fn base58_outputs_decode_to_kyber_lengths() {
+        let b58 = Encoding::Base58;
+        let keys = gen_one(b58);
+        let enc = encap(&field(&keys, "pkB58"), b58).unwrap();
+        let dec = decap(&field(&keys, "skB58"), &field(&enc, "ctB58"), b58).unwrap();
+
+        let len = |text: String| bs58::decode(text).into_vec().unwrap().len();
+        assert_eq!((len(field(&keys, "pkB58")), len(field(&keys, "skB58"))), (1184, 2400));
+        assert_eq!((len(field(&enc, "ctB58")), len(field(&enc, "ssB58"))), (1088, 32));
+        assert_eq!(field(&enc, "ssB58"), field(&dec, "ssB58"));
+
+        // No base64 keys leak into base58 output, and base64 input is refused in base58 mode.
+        let json = serde_json::to_string(&keys).unwrap();
+        assert!(!json.contains("B64"));
+        assert!(encap(&gen_one(Encoding::Base64).pk, b58).is_err());
+    }
+
+    /// Counts outside 1..=MAX_COUNT are refused.
+    #[test]
+    fn gen_count_bounds() {
+        assert!(gen_many(0, Encoding::Base64).is_err());
+        assert!(gen_many(MAX_COUNT + 1, Encoding::Base64).is_err());
+    }
+}
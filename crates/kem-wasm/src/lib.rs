@@ -0,0 +1,123 @@
+//! WASM bindings for the Kyber768 KEM
+//! Exposes kem-cli's gen, encap and decap to JavaScript with the same JSON fields
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use kem::{KemError, ALG};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Output of kem_keypair, field for field the JSON of `kem-cli gen`
+#[derive(Serialize, Debug, PartialEq)]
+struct OutGen {
+    alg: &'static str,
+    #[serde(rename = "pkB64")]
+    pk_b64: String,
+    #[serde(rename = "skB64")]
+    sk_b64: String,
+    pk_len: usize,
+    sk_len: usize,
+}
+
+/// Output of kem_encap, the JSON of `kem-cli encap`
+#[derive(Serialize, Debug, PartialEq)]
+struct OutEncap {
+    alg: &'static str,
+    #[serde(rename = "ctB64")]
+    ct_b64: String,
+    #[serde(rename = "ssB64")]
+    ss_b64: String,
+    ct_len: usize,
+    ss_len: usize,
+}
+
+/// Output of kem_decap, the JSON of `kem-cli decap`
+#[derive(Serialize, Debug, PartialEq)]
+struct OutDecap {
+    alg: &'static str,
+    #[serde(rename = "ssB64")]
+    ss_b64: String,
+    ss_len: usize,
+}
+
+/// Maps a length error to the string code thrown to JavaScript
+fn error_code(e: KemError) -> &'static str {
+    match e {
+        KemError::InvalidPublicKeyLength => "invalid_pk_length",
+        KemError::InvalidSecretKeyLength => "invalid_sk_length",
+        KemError::InvalidCiphertextLength => "invalid_ct_length",
+    }
+}
+
+fn gen_out() -> OutGen {
+    let (pk, sk) = kem::gen();
+    OutGen { alg: ALG, pk_b64: STANDARD.encode(&pk), sk_b64: STANDARD.encode(&sk), pk_len: pk.len(), sk_len: sk.len() }
+}
+
+fn encap_out(pk: &[u8]) -> Result<OutEncap, &'static str> {
+    let (ct, ss) = kem::encap(pk).map_err(error_code)?;
+    Ok(OutEncap { alg: ALG, ct_b64: STANDARD.encode(&ct), ss_b64: STANDARD.encode(&ss), ct_len: ct.len(), ss_len: ss.len() })
+}
+
+fn decap_out(sk: &[u8], ct: &[u8]) -> Result<OutDecap, &'static str> {
+    let ss = kem::decap(sk, ct).map_err(error_code)?;
+    Ok(OutDecap { alg: ALG, ss_b64: STANDARD.encode(&ss), ss_len: ss.len() })
+}
+
+fn to_js<T: Serialize>(out: &T) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(out).map_err(|_| JsValue::from_str("serialize"))
+}
+
+/// Generates a keypair -> { alg, pkB64, skB64, pk_len, sk_len }
+#[wasm_bindgen]
+pub fn kem_keypair() -> Result<JsValue, JsValue> {
+    to_js(&gen_out())
+}
+
+/// Encapsulates to a raw 1,184-byte public key -> { alg, ctB64, ssB64, ct_len, ss_len }
+/// Throws invalid_pk_length for any other length
+#[wasm_bindgen]
+pub fn kem_encap(pk: &[u8]) -> Result<JsValue, JsValue> {
+    to_js(&encap_out(pk).map_err(JsValue::from_str)?)
+}
+
+/// Decapsulates a raw 1,088-byte ciphertext with a raw 2,400-byte secret key -> { alg, ssB64, ss_len }
+/// Throws invalid_sk_length or invalid_ct_length; a ciphertext for another key gives an unrelated secret, not an error
+#[wasm_bindgen]
+pub fn kem_decap(sk: &[u8], ct: &[u8]) -> Result<JsValue, JsValue> {
+    to_js(&decap_out(sk, ct).map_err(JsValue::from_str)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Outputs use kem-cli's field names, and encap/decap agree on the secret
+    #[test]
+    fn outputs_mirror_cli_json() {
+        let keys = gen_out();
+        let json = serde_json::to_value(&keys).unwrap();
+        assert_eq!(json["alg"], "kyber768");
+        assert_eq!((json["pk_len"].as_u64(), json["sk_len"].as_u64()), (Some(1184), Some(2400)));
+
+        let pk = STANDARD.decode(&keys.pk_b64).unwrap();
+        let sk = STANDARD.decode(&keys.sk_b64).unwrap();
+        let enc = encap_out(&pk).unwrap();
+        let json = serde_json::to_value(&enc).unwrap();
+        assert!(json["ctB64"].is_string() && json["ssB64"].is_string());
+        assert_eq!((enc.ct_len, enc.ss_len), (1088, 32));
+
+        let dec = decap_out(&sk, &STANDARD.decode(&enc.ct_b64).unwrap()).unwrap();
+        assert_eq!(dec.ss_b64, enc.ss_b64);
+        assert_eq!(serde_json::to_value(&dec).unwrap()["ss_len"], 32);
+    }
+
+    /// Wrong lengths come back as string codes
+    #[test]
+    fn length_errors_are_codes() {
+        let keys = gen_out();
+        let sk = STANDARD.decode(&keys.sk_b64).unwrap();
+        assert_eq!(encap_out(&[0u8; 1183]), Err("invalid_pk_length"));
+        assert_eq!(decap_out(&sk[1..], &[0u8; 1088]), Err("invalid_sk_length"));
+        assert_eq!(decap_out(&sk, &[0u8; 1087]), Err("invalid_ct_length"));
+    }
+}
@@ -0,0 +1,57 @@
+//! JS-side round trips through the wasm-bindgen exports
+//! Run with `wasm-pack test --node crates/kem-wasm`
+
+#![cfg(target_arch = "wasm32")]
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use kem_wasm::{kem_decap, kem_encap, kem_keypair};
+use serde::Deserialize;
+use wasm_bindgen_test::*;
+
+#[derive(Deserialize)]
+struct Keys {
+    #[serde(rename = "pkB64")]
+    pk_b64: String,
+    #[serde(rename = "skB64")]
+    sk_b64: String,
+}
+
+#[derive(Deserialize)]
+struct Encap {
+    #[serde(rename = "ctB64")]
+    ct_b64: String,
+    #[serde(rename = "ssB64")]
+    ss_b64: String,
+}
+
+#[derive(Deserialize)]
+struct Decap {
+    #[serde(rename = "ssB64")]
+    ss_b64: String,
+}
+
+fn keypair() -> (Vec<u8>, Vec<u8>) {
+    let keys: Keys = serde_wasm_bindgen::from_value(kem_keypair().unwrap()).unwrap();
+    (STANDARD.decode(keys.pk_b64).unwrap(), STANDARD.decode(keys.sk_b64).unwrap())
+}
+
+/// keypair -> encap -> decap recovers the same shared secret
+#[wasm_bindgen_test]
+fn keypair_encap_decap_round_trip() {
+    let (pk, sk) = keypair();
+    let enc: Encap = serde_wasm_bindgen::from_value(kem_encap(&pk).unwrap()).unwrap();
+    let ct = STANDARD.decode(&enc.ct_b64).unwrap();
+    let dec: Decap = serde_wasm_bindgen::from_value(kem_decap(&sk, &ct).unwrap()).unwrap();
+    assert_eq!(dec.ss_b64, enc.ss_b64);
+}
+
+/// Bad lengths throw a catchable string code instead of aborting the instance
+#[wasm_bindgen_test]
+fn bad_lengths_throw_codes() {
+    let (pk, sk) = keypair();
+    assert_eq!(kem_encap(&pk[1..]).unwrap_err().as_string().as_deref(), Some("invalid_pk_length"));
+    assert_eq!(kem_decap(&sk[1..], &[0u8; 1088]).unwrap_err().as_string().as_deref(), Some("invalid_sk_length"));
+    assert_eq!(kem_decap(&sk, &[]).unwrap_err().as_string().as_deref(), Some("invalid_ct_length"));
+    // The instance is still usable afterwards.
+    assert!(kem_encap(&pk).is_ok());
+}
@@ -0,0 +1,29 @@
+// Regenerates node_sha2_128s.json with Node's native SLH-DSA (crypto.sign, Node >= 24.8)
+// Usage: node fixtures/gen_node_fixture.mjs > fixtures/node_sha2_128s.json
+import crypto from "node:crypto";
+
+const [major, minor] = process.versions.node.split(".").map(Number);
+if (major < 24 || (major === 24 && minor < 8)) {
+  console.error(`Node ${process.versions.node} has no SLH-DSA; Node >= 24.8 is required`);
+  process.exit(1);
+}
+
+const msg = Buffer.from("zk-chat node interop");
+const ctx = "zk-chat";
+const { publicKey, privateKey } = crypto.generateKeyPairSync("slh-dsa-sha2-128s");
+
+// Pure FIPS 205 signing: the algorithm argument is null, and no context means the empty context
+const sig = crypto.sign(null, msg, privateKey);
+const sigCtx = crypto.sign(null, msg, { key: privateKey, context: Buffer.from(ctx) });
+if (!crypto.verify(null, msg, publicKey, sig)) throw new Error("self-check failed");
+
+const fixture = {
+  generator: `node ${process.versions.node} crypto.sign (OpenSSL ${process.versions.openssl})`,
+  alg: "SLH-DSA-SHA2-128s",
+  msg_hex: msg.toString("hex"),
+  spki_der_hex: publicKey.export({ type: "spki", format: "der" }).toString("hex"),
+  sig_hex: sig.toString("hex"),
+  ctx,
+  sig_ctx_hex: sigCtx.toString("hex"),
+};
+console.log(JSON.stringify(fixture, null, 2));
@@ -0,0 +1,99 @@
+//! Flat batch verification: one Uint8Array in, one Uint8Array out
+//! Frames are [u32 msg_len LE][msg][u32 sig_len LE][sig][u32 pk_len LE][pk], repeated back to back.
+//! The batch crosses the JS/WASM boundary once and is split in Rust without copying, so large batches
+//! create no per-item JsValue on either side
+
+use wasm_bindgen::prelude::*;
+
+/// Most triples one verify_batch_flat call accepts; larger batches are refused whole so the caller can split them
+pub const MAX_BATCH_ITEMS: usize = 1024;
+
+/// Splits a framed batch into (msg, sig, pk) slices; fails on a truncated frame or more than MAX_BATCH_ITEMS triples
+pub(crate) fn decode_batch(frames: &[u8]) -> Result<Vec<[&[u8]; 3]>, &'static str> {
+    let mut items = Vec::new();
+    let mut rest = frames;
+    while !rest.is_empty() {
+        if items.len() == MAX_BATCH_ITEMS { return Err("batch_too_large"); }
+        let mut item: [&[u8]; 3] = [&[]; 3];
+        for part in item.iter_mut() {
+            let (len, tail) = rest.split_first_chunk::<4>().ok_or("invalid_batch_framing")?;
+            let len = u32::from_le_bytes(*len) as usize;
+            if tail.len() < len { return Err("invalid_batch_framing"); }
+            (*part, rest) = tail.split_at(len);
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Frames (msg, sig, pk) triples for verify_batch_flat; Rust hosts and tests build batches with it
+pub fn encode_batch(items: &[[&[u8]; 3]]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(items.iter().flatten().map(|p| 4 + p.len()).sum());
+    for part in items.iter().flatten() {
+        out.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        out.extend_from_slice(part);
+    }
+    out
+}
+
+/// One byte per triple, in order: 1 if the signature verifies under the key (as verify would say), else 0
+pub(crate) fn batch_results(frames: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let items = decode_batch(frames)?;
+    Ok(items.iter().map(|[msg, sig, pk]| u8::from(crate::verify(msg, sig, pk))).collect())
+}
+
+/// Verifies a framed batch of (msg, sig, pk) triples and returns one result byte per triple (1 = verifies)
+/// Throws invalid_batch_framing for a truncated frame and batch_too_large past MAX_BATCH_ITEMS, before verifying anything
+#[wasm_bindgen]
+pub fn verify_batch_flat(frames: &[u8]) -> Result<Vec<u8>, JsValue> {
+    batch_results(frames).map_err(JsValue::from_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slh_dsa::{signature::{Keypair, Signer}, Sha2_128s};
+
+    /// 100 mixed triples give the same verdicts through the flat encoding as item by item
+    #[test]
+    fn flat_batch_matches_per_item_verify() {
+        let sk = crate::test_key::<Sha2_128s>(0);
+        let other = crate::test_key::<Sha2_128s>(1);
+        let (pk, other_pk) = (sk.verifying_key().to_bytes(), other.verifying_key().to_bytes());
+        let msgs: [&[u8]; 3] = [b"first", b"", &[0x5au8; 600]];
+        let sigs: Vec<Vec<u8>> = msgs.iter().map(|m| sk.sign(m).to_bytes().to_vec()).collect();
+
+        let items: Vec<[&[u8]; 3]> = (0..100)
+            .map(|i| {
+                let (msg, sig) = (msgs[i % 3], &sigs[i % 3][..]);
+                match i % 5 {
+                    0 | 1 => [msg, sig, &pk[..]],
+                    2 => [&b"tampered"[..], sig, &pk[..]],
+                    3 => [msg, sig, &other_pk[..]],
+                    _ => [msg, &sig[..sig.len() - 1], &pk[..]],
+                }
+            })
+            .collect();
+        let results = batch_results(&encode_batch(&items)).unwrap();
+        let expected: Vec<u8> = items.iter().map(|[m, s, p]| u8::from(crate::verify(m, s, p))).collect();
+        assert_eq!(results, expected);
+        assert_eq!(results.iter().filter(|&&r| r == 1).count(), 40);
+    }
+
+    /// Truncated frames and oversized batches are refused whole; an empty batch yields no results
+    #[test]
+    fn malformed_batch_rejected() {
+        let frames = encode_batch(&[[&b"m"[..], &[0u8; 8][..], &[0u8; 4][..]]]);
+        assert_eq!(decode_batch(&frames).unwrap(), vec![[&b"m"[..], &[0u8; 8][..], &[0u8; 4][..]]]);
+        assert_eq!(batch_results(&frames).unwrap(), vec![0]);
+        assert_eq!(batch_results(&frames[..frames.len() - 1]), Err("invalid_batch_framing"));
+        assert_eq!(batch_results(&frames[..2]), Err("invalid_batch_framing"));
+        assert_eq!(batch_results(&[]).unwrap(), Vec::<u8>::new());
+
+        let empty: [&[u8]; 3] = [&[], &[], &[]];
+        let max = encode_batch(&vec![empty; MAX_BATCH_ITEMS]);
+        assert_eq!(batch_results(&max).unwrap().len(), MAX_BATCH_ITEMS);
+        let over = encode_batch(&vec![empty; MAX_BATCH_ITEMS + 1]);
+        assert_eq!(batch_results(&over), Err("batch_too_large"));
+    }
+}
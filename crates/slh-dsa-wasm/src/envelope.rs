@@ -0,0 +1,120 @@
+//! Self describing signature envelope [u16 alg_id LE][u32 len LE][sig bytes]
+//! Lets stored signatures name their parameter set; raw signatures stay supported everywhere
+
+use wasm_bindgen::prelude::*;
+
+/// Envelope header length (alg_id + len)
+pub const HEADER_LEN: usize = 2 + 4;
+
+/// SLH-DSA parameter sets an envelope can name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alg {
+    Sha2_128s,
+    Sha2_128f,
+}
+
+impl Alg {
+    /// Wire id stored in the envelope header
+    pub const fn id(self) -> u16 {
+        match self {
+            Alg::Sha2_128s => 0x0001,
+            Alg::Sha2_128f => 0x0002,
+        }
+    }
+
+    /// Raw signature length of this parameter set
+    pub const fn sig_len(self) -> usize {
+        match self {
+            Alg::Sha2_128s => 7_856,
+            Alg::Sha2_128f => 17_088,
+        }
+    }
+
+    /// Looks up a parameter set by wire id
+    pub fn from_id(id: u16) -> Option<Self> {
+        [Alg::Sha2_128s, Alg::Sha2_128f].into_iter().find(|a| a.id() == id)
+    }
+}
+
+/// Wraps a raw signature; the length must match the parameter set
+pub fn encode(alg: Alg, sig: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if sig.len() != alg.sig_len() { return Err("invalid_sig_length"); }
+    let mut out = Vec::with_capacity(HEADER_LEN + sig.len());
+    out.extend_from_slice(&alg.id().to_le_bytes());
+    out.extend_from_slice(&(sig.len() as u32).to_le_bytes());
+    out.extend_from_slice(sig);
+    Ok(out)
+}
+
+/// Splits an envelope into its parameter set and raw signature
+pub fn decode(env: &[u8]) -> Result<(Alg, &[u8]), &'static str> {
+    if env.len() < HEADER_LEN { return Err("short_envelope"); }
+    let alg = Alg::from_id(u16::from_le_bytes([env[0], env[1]])).ok_or("unknown_alg")?;
+    let len = u32::from_le_bytes([env[2], env[3], env[4], env[5]]) as usize;
+    let sig = &env[HEADER_LEN..];
+    if len != sig.len() || len != alg.sig_len() { return Err("invalid_sig_length"); }
+    Ok((alg, sig))
+}
+
+/// Returns the raw signature for alg, accepting either raw bytes or an envelope naming alg
+pub fn unwrap_for(alg: Alg, bytes: &[u8]) -> Option<&[u8]> {
+    if bytes.len() == alg.sig_len() { return Some(bytes); }
+    match decode(bytes) {
+        Ok((a, sig)) if a == alg => Some(sig),
+        _ => None,
+    }
+}
+
+/// Wraps a raw signature in an envelope; alg_id 1 = SHA2-128s, 2 = SHA2-128f
+#[wasm_bindgen]
+pub fn encode_sig_envelope(alg_id: u16, sig: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let alg = Alg::from_id(alg_id).ok_or_else(|| JsValue::from_str("unknown_alg"))?;
+    encode(alg, sig).map_err(JsValue::from_str)
+}
+
+/// Returns the alg_id named by an envelope
+#[wasm_bindgen]
+pub fn envelope_alg_id(env: &[u8]) -> Result<u16, JsValue> {
+    decode(env).map(|(a, _)| a.id()).map_err(JsValue::from_str)
+}
+
+/// Returns the raw signature carried by an envelope
+#[wasm_bindgen]
+pub fn decode_sig_envelope(env: &[u8]) -> Result<Vec<u8>, JsValue> {
+    decode(env).map(|(_, s)| s.to_vec()).map_err(JsValue::from_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slh_dsa::{signature::Signer, Sha2_128f, Sha2_128s};
+
+    /// Real signatures of every supported set survive an encode/decode round trip
+    #[test]
+    fn round_trip_each_alg() {
+        let s = crate::test_key::<Sha2_128s>(0)
+            .sign(b"env").to_bytes().to_vec();
+        let f = crate::test_key::<Sha2_128f>(0)
+            .sign(b"env").to_bytes().to_vec();
+        for (alg, sig) in [(Alg::Sha2_128s, &s), (Alg::Sha2_128f, &f)] {
+            let env = encode(alg, sig).unwrap();
+            assert_eq!(env.len(), HEADER_LEN + alg.sig_len());
+            assert_eq!(decode(&env).unwrap(), (alg, &sig[..]));
+            assert_eq!(unwrap_for(alg, &env).unwrap(), &sig[..]);
+            assert_eq!(unwrap_for(alg, sig).unwrap(), &sig[..]);
+        }
+        assert!(unwrap_for(Alg::Sha2_128s, &encode(Alg::Sha2_128f, &f).unwrap()).is_none());
+    }
+
+    /// Malformed envelopes are rejected
+    #[test]
+    fn malformed_rejected() {
+        let env = encode(Alg::Sha2_128s, &[0u8; 7_856]).unwrap();
+        assert_eq!(decode(&env[..5]), Err("short_envelope"));
+        assert_eq!(decode(&env[..env.len() - 1]), Err("invalid_sig_length"));
+        let mut bad = env.clone();
+        bad[0] = 9;
+        assert_eq!(decode(&bad), Err("unknown_alg"));
+        assert_eq!(encode(Alg::Sha2_128f, &[0u8; 7_856]), Err("invalid_sig_length"));
+    }
+}
@@ -0,0 +1,79 @@
+//! Signing key helpers shared by the WASM bindings and Rust hosts (slh_dsa_wasm::keys)
+//! Return a typed KeyError instead of panicking; the bindings throw its code() string
+
+use core::fmt;
+use slh_dsa::{signature::Keypair, Sha2_128s, SigningKey};
+
+use crate::{PK_LEN, SK_LEN};
+
+/// Why a signing key was refused
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyError {
+    /// Not SK_LEN (64) bytes
+    InvalidLength,
+    /// slh_dsa could not decode the bytes as sk_seed || sk_prf || pk_seed || pk_root
+    Decode,
+    /// pk_root is not the root sk_seed and pk_seed generate (corrupted or spliced key)
+    Inconsistent,
+}
+
+impl KeyError {
+    /// Error string thrown to JavaScript
+    pub const fn code(self) -> &'static str {
+        match self {
+            KeyError::InvalidLength => "invalid_sk_length",
+            KeyError::Decode => "decode_sk",
+            KeyError::Inconsistent => "sk_mismatch",
+        }
+    }
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+/// Checks the length and decodes a 64 byte signing key without checking pk_root
+pub fn decode_sk(sk_bytes: &[u8]) -> Result<SigningKey<Sha2_128s>, KeyError> {
+    if sk_bytes.len() != SK_LEN { return Err(KeyError::InvalidLength); }
+    SigningKey::<Sha2_128s>::try_from(sk_bytes).map_err(|_| KeyError::Decode)
+}
+
+/// Returns the 32 byte verifying key (pk_seed || pk_root) of a signing key
+/// Regenerates pk_root from sk_seed and pk_seed (one top-layer XMSS tree, a few ms) so a key with
+/// 64 bytes of garbage is refused instead of yielding a verifying key nothing it signs will match
+pub fn try_verifying_key_bytes(sk_bytes: &[u8]) -> Result<[u8; PK_LEN], KeyError> {
+    let sk = decode_sk(sk_bytes)?;
+    let n = SK_LEN / 4;
+    let regenerated = SigningKey::<Sha2_128s>::slh_keygen_internal(&sk_bytes[..n], &sk_bytes[n..2 * n], &sk_bytes[2 * n..3 * n]);
+    let vk = sk.verifying_key().to_bytes();
+    if regenerated.verifying_key().to_bytes() != vk { return Err(KeyError::Inconsistent); }
+    vk.as_slice().try_into().map_err(|_| KeyError::Decode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A generated key yields its verifying key; garbage of the right length and wrong lengths are typed errors
+    #[test]
+    fn vk_from_good_garbage_and_short_keys() {
+        let sk = crate::test_key::<Sha2_128s>(0);
+        let sk_bytes = sk.to_bytes();
+        assert_eq!(try_verifying_key_bytes(&sk_bytes).unwrap()[..], sk.verifying_key().to_bytes()[..]);
+
+        let garbage: Vec<u8> = (0..SK_LEN as u8).map(|i| i.wrapping_mul(37)).collect();
+        assert_eq!(try_verifying_key_bytes(&garbage), Err(KeyError::Inconsistent));
+        let mut flipped = sk_bytes.to_vec();
+        flipped[SK_LEN - 1] ^= 1;
+        assert_eq!(try_verifying_key_bytes(&flipped), Err(KeyError::Inconsistent));
+
+        for len in [0, PK_LEN, SK_LEN - 1, SK_LEN + 1] {
+            assert_eq!(try_verifying_key_bytes(&vec![0u8; len]), Err(KeyError::InvalidLength));
+        }
+        assert_eq!(KeyError::InvalidLength.to_string(), "invalid_sk_length");
+    }
+}
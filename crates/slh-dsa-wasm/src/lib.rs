@@ -4,57 +4,509 @@
 #![allow(clippy::missing_safety_doc)]
 
 use wasm_bindgen::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use slh_dsa::{
     Sha2_128s, SigningKey, VerifyingKey, Signature,
     signature::{Keypair, Signer, Verifier},
 };
+use slh_dsa::onchain_sha2::{h_msg_sha2_128s, verify_sha2_128s};
 use rand_core::OsRng;
 use core::convert::TryFrom;
 
+mod upload;
+pub use upload::{estimate_tx_count, fold_chain, fold_upload_chain, plan_message_upload, plan_upload};
+mod nonce;
+pub use nonce::derive_nonce;
+mod stark;
+pub use stark::cipher_digest;
+#[cfg(feature = "stark")]
+pub use stark::verify_stark_wasm;
+#[cfg(feature = "stark")]
+mod worker;
+#[cfg(feature = "stark")]
+pub use worker::{verify_job, JOB_SIG_OK, JOB_STARK_OK};
+mod batch;
+pub use batch::{encode_batch, verify_batch_flat, MAX_BATCH_ITEMS};
+mod node_compat;
+pub use node_compat::vk_bytes_from_spki;
+mod sha256;
+pub use sha256::Sha256Ctx;
+pub mod envelope;
+pub use envelope::{decode_sig_envelope, encode_sig_envelope, envelope_alg_id};
+pub mod keys;
+pub use keys::{try_verifying_key_bytes, KeyError};
+
 const SK_LEN : usize = 64; // Defines private key length in bytes
 const PK_LEN : usize = 32; // Defines public key length in bytes
 const SIG_LEN: usize = 7_856; // Defines signature length in bytes
+const RAND_LEN: usize = 16; // Defines randomizer R length in bytes
+const NONCE_LEN: usize = 12; // Defines finalize_sig nonce length in bytes (ChaCha20-Poly1305)
+const XNONCE_LEN: usize = 24; // Defines the extended nonce length in bytes (XChaCha20-Poly1305)
 
 #[derive(Serialize)]
 struct KeyPair { public_key: Vec<u8>, private_key: Vec<u8> }
 
+/// Hex encoded parts of a 64 byte signing key (4 x 16 bytes)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SkComponents { sk_seed: String, sk_prf: String, pk_seed: String, pk_root: String }
+
+const PART_LEN: usize = SK_LEN / 4; // Defines n = 16 bytes per component
+
+fn split_sk(sk_bytes: &[u8]) -> Result<SkComponents, &'static str> {
+    if sk_bytes.len() != SK_LEN { return Err("invalid_sk_length"); }
+    let part = |i: usize| hex::encode(&sk_bytes[i * PART_LEN..(i + 1) * PART_LEN]);
+    Ok(SkComponents { sk_seed: part(0), sk_prf: part(1), pk_seed: part(2), pk_root: part(3) })
+}
+
+fn join_sk(c: &SkComponents) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::with_capacity(SK_LEN);
+    for part in [&c.sk_seed, &c.sk_prf, &c.pk_seed, &c.pk_root] {
+        let bytes = hex::decode(part).map_err(|_| "invalid_hex")?;
+        if bytes.len() != PART_LEN { return Err("invalid_component_length"); }
+        out.extend_from_slice(&bytes);
+    }
+    SigningKey::<Sha2_128s>::try_from(&out[..]).map_err(|_| "decode_sk")?;
+    Ok(out)
+}
+
+/// Checks the length and decodes a 64 byte signing key
+fn decode_sk(sk_bytes: &[u8]) -> Result<SigningKey<Sha2_128s>, &'static str> {
+    keys::decode_sk(sk_bytes).map_err(KeyError::code)
+}
+
+fn vk_from_sk(sk_bytes: &[u8]) -> Result<Vec<u8>, &'static str> {
+    keys::try_verifying_key_bytes(sk_bytes).map(|vk| vk.to_vec()).map_err(KeyError::code)
+}
+
+/// Encodes a signing key as a JSON byte array, the layout of a Solana keypair file
+fn sk_to_json(sk_bytes: &[u8]) -> Result<String, &'static str> {
+    if sk_bytes.len() != SK_LEN { return Err("invalid_sk_length"); }
+    serde_json::to_string(sk_bytes).map_err(|_| "serialize")
+}
+
+/// Parses a JSON byte array back into a 64 byte signing key
+fn sk_from_json(json: &str) -> Result<Vec<u8>, &'static str> {
+    let bytes: Vec<u8> = serde_json::from_str(json).map_err(|_| "invalid_json")?;
+    if bytes.len() != SK_LEN { return Err("invalid_sk_length"); }
+    SigningKey::<Sha2_128s>::try_from(&bytes[..]).map_err(|_| "decode_sk")?;
+    Ok(bytes)
+}
+
+/// Build metadata reported by version_info
+#[derive(Serialize, Debug)]
+struct VersionInfo {
+    crate_version: &'static str,
+    param_set: &'static str,
+    sig_len: usize,
+    pk_len: usize,
+    sk_len: usize,
+    git_sha: Option<&'static str>,
+}
+
+fn build_info() -> VersionInfo {
+    VersionInfo {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        param_set: "SLH-DSA-SHA2-128s",
+        sig_len: SIG_LEN,
+        pk_len: PK_LEN,
+        sk_len: SK_LEN,
+        git_sha: option_env!("GIT_SHA"), // Set GIT_SHA at build time to embed the commit
+    }
+}
+
+/// Returns crate version and parameter set so front ends can detect stale bundles
+#[wasm_bindgen]
+pub fn version_info() -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&build_info()).map_err(|_| JsValue::from_str("serialize"))
+}
+
 /// Generates a new keypair and returns a JSON value
 #[wasm_bindgen]
-pub fn generate_keypair() -> JsValue {
+pub fn generate_keypair() -> Result<JsValue, JsValue> {
     let sk = SigningKey::<Sha2_128s>::new(&mut OsRng);
     let pk = sk.verifying_key();
     serde_wasm_bindgen::to_value(&KeyPair {
         public_key : pk.to_bytes().to_vec(),
         private_key: sk.to_bytes().to_vec(),
-    }).unwrap()
+    }).map_err(|_| JsValue::from_str("serialize"))
+}
+
+/// Derives verifying key bytes from a signing key; throws invalid_sk_length, decode_sk or sk_mismatch
+#[wasm_bindgen]
+pub fn vk_bytes_from_sk(sk_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    vk_from_sk(sk_bytes).map_err(JsValue::from_str)
+}
+
+/// Splits a signing key into { sk_seed, sk_prf, pk_seed, pk_root } hex strings for inspection
+#[wasm_bindgen]
+pub fn sk_components(sk_bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let c = split_sk(sk_bytes).map_err(JsValue::from_str)?;
+    serde_wasm_bindgen::to_value(&c).map_err(|_| JsValue::from_str("serialize"))
+}
+
+/// Reassembles a signing key from { sk_seed, sk_prf, pk_seed, pk_root } hex strings
+#[wasm_bindgen]
+pub fn assemble_sk(components: JsValue) -> Result<Vec<u8>, JsValue> {
+    let c: SkComponents = serde_wasm_bindgen::from_value(components)
+        .map_err(|_| JsValue::from_str("invalid_components"))?;
+    join_sk(&c).map_err(JsValue::from_str)
 }
 
-/// Derives verifying key bytes from a signing key
+/// Writes the signing key as a Solana-style JSON byte array for storage next to keypair files
+/// The file holds an SLH-DSA key; it is not an ed25519 keypair and solana-keygen cannot sign with it
 #[wasm_bindgen]
-pub fn vk_bytes_from_sk(sk_bytes: &[u8]) -> Vec<u8> {
-    if sk_bytes.len() != SK_LEN { return vec![]; }
-    let sk = SigningKey::<Sha2_128s>::try_from(sk_bytes).unwrap();
-    sk.verifying_key().to_bytes().to_vec()
+pub fn to_solana_json(sk_bytes: &[u8]) -> Result<String, JsValue> {
+    sk_to_json(sk_bytes).map_err(JsValue::from_str)
+}
+
+/// Reads a signing key written by to_solana_json, checking it is 64 bytes and decodes
+#[wasm_bindgen]
+pub fn from_solana_json(json: &str) -> Result<Vec<u8>, JsValue> {
+    sk_from_json(json).map_err(JsValue::from_str)
 }
 
 /// Signs the message with SLH DSA SHA2 128s
 #[wasm_bindgen]
 pub fn sign(msg: &[u8], sk_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
-    if sk_bytes.len() != SK_LEN {
-        return Err(JsValue::from_str("invalid_sk_length"));
-    }
-    let sk = SigningKey::<Sha2_128s>::try_from(sk_bytes)
-        .map_err(|_| JsValue::from_str("decode_sk"))?;
+    let sk = decode_sk(sk_bytes).map_err(JsValue::from_str)?;
     let sig: Signature<Sha2_128s> = sk.sign(msg);
     Ok(sig.to_bytes().to_vec())
 }
 
+/// Signs with the deterministic FIPS 205 variant for test vectors and interop
+/// opt_rand is set to pk_seed instead of fresh randomness, so the same (msg, sk) always yields the same bytes
+/// Uses pure mode with an empty context, matching the on-chain verifier
+#[wasm_bindgen]
+pub fn sign_deterministic(msg: &[u8], sk_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let sk = decode_sk(sk_bytes).map_err(JsValue::from_str)?;
+    let sig = sk.try_sign_with_context(msg, &[], None)
+        .map_err(|_| JsValue::from_str("sign_failed"))?;
+    Ok(sig.to_bytes().to_vec())
+}
+
 /// Verifies the signature with the given verifying key
+/// sig_bytes may be a raw signature or an envelope naming SHA2-128s
 #[wasm_bindgen]
 pub fn verify(msg: &[u8], sig_bytes: &[u8], pk_bytes: &[u8]) -> bool {
+    let Some(sig_bytes) = envelope::unwrap_for(envelope::Alg::Sha2_128s, sig_bytes) else { return false };
     if pk_bytes.len() != PK_LEN || sig_bytes.len() != SIG_LEN { return false; }
     let pk  = match VerifyingKey::<Sha2_128s>::try_from(pk_bytes) { Ok(v)=>v, Err(_)=>return false };
     let sig = match Signature::<Sha2_128s>::try_from(sig_bytes)   { Ok(s)=>s, Err(_)=>return false };
     pk.verify(msg, &sig).is_ok()
 }
+
+/// Index of the first candidate key the signature verifies under, or None
+/// Rust hosts pass the keys as slices; a malformed key simply does not match
+pub fn verify_any_key<K: AsRef<[u8]>>(msg: &[u8], sig_bytes: &[u8], pks: &[K]) -> Option<usize> {
+    pks.iter().position(|pk| verify(msg, sig_bytes, pk.as_ref()))
+}
+
+/// Verifies against several candidate keys packed back to back (32 bytes each), e.g. new_vk || old_vk during a rotation
+/// Returns the index of the first key that verifies; undefined if none does or pks is not a multiple of 32 bytes
+#[wasm_bindgen]
+pub fn verify_any(msg: &[u8], sig_bytes: &[u8], pks: &[u8]) -> Option<u32> {
+    if !pks.len().is_multiple_of(PK_LEN) { return None; }
+    let keys: Vec<&[u8]> = pks.chunks(PK_LEN).collect();
+    verify_any_key(msg, sig_bytes, &keys).map(|i| i as u32)
+}
+
+/// Pre-upload check of a raw signature, mirroring the program's 7,856 byte requirement
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct SignatureCheck { pub len_ok: bool, pub structure_ok: bool, pub expected_len: usize }
+
+/// Checks length and FORS/hypertree layout with Signature::try_from, without a key or message
+/// Catches truncated uploads and 128f signatures (17,088 bytes) before any chunk is sent
+pub fn check_signature(sig: &[u8]) -> SignatureCheck {
+    SignatureCheck {
+        len_ok: sig.len() == SIG_LEN,
+        structure_ok: Signature::<Sha2_128s>::try_from(sig).is_ok(),
+        expected_len: SIG_LEN,
+    }
+}
+
+/// Returns { len_ok, structure_ok, expected_len } for a raw signature about to be uploaded
+#[wasm_bindgen]
+pub fn prevalidate_signature(sig: &[u8]) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&check_signature(sig)).map_err(|_| JsValue::from_str("serialize"))
+}
+
+/// Builds the finalize_sig message cipher || kem || sender || recipient || nonce || nonce_len || slot_le || msg_type_le || sig_alg,
+/// as the program's signed_message does
+fn finalize_message(
+    cipher_kem: &[u8],
+    sender: &[u8; 32],
+    recipient: &[u8; 32],
+    nonce: &[u8],
+    slot: u64,
+    msg_type: u16,
+    sig_alg: u8,
+) -> Vec<u8> {
+    [cipher_kem, sender, recipient, nonce, &[nonce.len() as u8], &slot.to_le_bytes(), &msg_type.to_le_bytes(), &[sig_alg]].concat()
+}
+
+/// Preflights finalize_sig against a registered verifying key fetched by the caller
+/// Frames the message and runs the streaming verifier the program uses, so the verdict matches on-chain
+/// sender and recipient are the 32-byte payer and recipient keys the finalize transaction will pass
+/// nonce may be 12 or 24 bytes, like the program's finalize_sig; sig_alg must be 1 (SHA2-128s, the envelope alg_id)
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn verify_registered(
+    cipher_kem: &[u8],
+    sender: &[u8],
+    recipient: &[u8],
+    nonce: &[u8],
+    slot: u64,
+    msg_type: u16,
+    sig_alg: u8,
+    sig_bytes: &[u8],
+    registered_vk: &[u8],
+) -> bool {
+    if !(nonce.len() == NONCE_LEN || nonce.len() == XNONCE_LEN) || registered_vk.len() != PK_LEN { return false; }
+    let (Ok(sender), Ok(recipient)) = (<&[u8; 32]>::try_from(sender), <&[u8; 32]>::try_from(recipient)) else { return false };
+    if u16::from(sig_alg) != envelope::Alg::Sha2_128s.id() { return false; }
+    let Some(sig_bytes) = envelope::unwrap_for(envelope::Alg::Sha2_128s, sig_bytes) else { return false };
+    verify_sha2_128s(&finalize_message(cipher_kem, sender, recipient, nonce, slot, msg_type, sig_alg), sig_bytes, registered_vk).is_ok()
+}
+
+/// Computes the FIPS 205 H_msg digest (30 bytes) for debugging signature framing
+/// Uses the pure mode empty context prefix, exactly as the on-chain verifier does
+/// rand is the 16 byte randomizer R taken from the first bytes of the signature
+#[wasm_bindgen]
+pub fn h_msg_digest(msg: &[u8], rand: &[u8], pk_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+    if rand.len() != RAND_LEN {
+        return Err(JsValue::from_str("invalid_rand_length"));
+    }
+    if pk_bytes.len() != PK_LEN {
+        return Err(JsValue::from_str("invalid_pk_length"));
+    }
+    h_msg_sha2_128s(msg, rand, pk_bytes)
+        .map(|d| d.to_vec())
+        .map_err(|_| JsValue::from_str("decode_pk"))
+}
+
+/// Deterministic key `n` of parameter set `P` for tests, seeded with bytes 3n+1, 3n+2 and 3n+3.
+#[cfg(test)]
+pub(crate) fn test_key<P: slh_dsa::ParameterSet>(n: u8) -> SigningKey<P> {
+    let seed = |i: u8| [3 * n + i; 16];
+    SigningKey::slh_keygen_internal(&seed(1), &seed(2), &seed(3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build info is populated and matches the size constants
+    #[test]
+    fn version_info_fields() {
+        let info = build_info();
+        assert_eq!(info.crate_version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.crate_version.is_empty());
+        assert_eq!(info.param_set, "SLH-DSA-SHA2-128s");
+        assert_eq!((info.sig_len, info.pk_len, info.sk_len), (SIG_LEN, PK_LEN, SK_LEN));
+        assert_eq!(info.sig_len, slh_dsa::onchain_sha2::SIG_LEN_128S);
+    }
+
+    /// SK_LEN, PK_LEN and SIG_LEN gate every length check; pin them to what slh_dsa actually encodes
+    #[test]
+    fn size_constants_match_slh_dsa() {
+        let sk = SigningKey::<Sha2_128s>::new(&mut OsRng);
+        let sig = sk.sign(b"size check");
+        assert_eq!(sk.to_bytes().len(), SK_LEN, "SK_LEN drifted from slh_dsa");
+        assert_eq!(sk.verifying_key().to_bytes().len(), PK_LEN, "PK_LEN drifted from slh_dsa");
+        assert_eq!(sig.to_bytes().len(), SIG_LEN, "SIG_LEN drifted from slh_dsa");
+        assert_eq!(PART_LEN * 4, SK_LEN);
+    }
+
+    /// Deterministic signing is reproducible and verifies under the derived key
+    #[test]
+    fn sign_deterministic_is_reproducible() {
+        let sk = test_key::<Sha2_128s>(0);
+        let sk_bytes = sk.to_bytes().to_vec();
+        let msg = b"kat vector";
+        let a = sign_deterministic(msg, &sk_bytes).unwrap();
+        let b = sign_deterministic(msg, &sk_bytes).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.len(), SIG_LEN);
+        assert!(verify(msg, &a, &vk_from_sk(&sk_bytes).unwrap()));
+    }
+
+    /// split then assemble returns the original key, and the parts are in FIPS 205 order
+    #[test]
+    fn sk_components_round_trip() {
+        let sk = test_key::<Sha2_128s>(0);
+        let sk_bytes = sk.to_bytes().to_vec();
+        let c = split_sk(&sk_bytes).unwrap();
+        assert_eq!(c.sk_seed, hex::encode([1u8; 16]));
+        assert_eq!(c.sk_prf, hex::encode([2u8; 16]));
+        assert_eq!(c.pk_seed, hex::encode([3u8; 16]));
+        assert_eq!(hex::decode(&c.pk_root).unwrap(), vk_from_sk(&sk_bytes).unwrap()[PART_LEN..]);
+        assert_eq!(join_sk(&c).unwrap(), sk_bytes);
+
+        assert_eq!(split_sk(&sk_bytes[1..]).unwrap_err(), "invalid_sk_length");
+        let short = SkComponents { sk_seed: "00".into(), ..c };
+        assert_eq!(join_sk(&short).unwrap_err(), "invalid_component_length");
+    }
+
+    /// A keypair survives the JSON byte array form and still signs for the same verifying key
+    #[test]
+    fn solana_json_round_trip() {
+        let sk = test_key::<Sha2_128s>(0);
+        let sk_bytes = sk.to_bytes().to_vec();
+        let json = sk_to_json(&sk_bytes).unwrap();
+        assert!(json.starts_with('[') && json.ends_with(']'));
+        assert_eq!(json.split(',').count(), SK_LEN);
+        let back = sk_from_json(&json).unwrap();
+        assert_eq!(back, sk_bytes);
+        assert_eq!(vk_from_sk(&back).unwrap(), vk_from_sk(&sk_bytes).unwrap());
+
+        assert_eq!(sk_to_json(&sk_bytes[1..]).unwrap_err(), "invalid_sk_length");
+        assert_eq!(sk_from_json(&sk_to_json(&sk_bytes).unwrap().replacen("[", "[0,", 1)).unwrap_err(), "invalid_sk_length");
+        assert_eq!(sk_from_json("[1,2,300]").unwrap_err(), "invalid_json");
+    }
+
+    /// Empty messages are valid FIPS 205 input and verify like any other
+    #[test]
+    fn empty_message_round_trip() {
+        let sk = test_key::<Sha2_128s>(2);
+        let sk_bytes = sk.to_bytes().to_vec();
+        let sig = sign(&[], &sk_bytes).unwrap();
+        assert!(verify(&[], &sig, &vk_from_sk(&sk_bytes).unwrap()));
+        assert!(!verify(&[0], &sig, &vk_from_sk(&sk_bytes).unwrap()));
+    }
+
+    /// verify accepts the same signature wrapped in an envelope
+    #[test]
+    fn verify_accepts_envelope() {
+        let sk = test_key::<Sha2_128s>(1);
+        let sk_bytes = sk.to_bytes().to_vec();
+        let sig = sign_deterministic(b"wrapped", &sk_bytes).unwrap();
+        let env = envelope::encode(envelope::Alg::Sha2_128s, &sig).unwrap();
+        assert!(verify(b"wrapped", &env, &vk_from_sk(&sk_bytes).unwrap()));
+        assert!(!verify(b"other", &env, &vk_from_sk(&sk_bytes).unwrap()));
+    }
+
+    /// Payer and recipient keys the tests finalize between; the program's tests use the same bytes
+    pub(crate) const SENDER: [u8; 32] = [0xa1; 32];
+    pub(crate) const RECIPIENT: [u8; 32] = [0xb2; 32];
+
+    /// Preflight accepts the registered signer, rejects another key, and agrees with the host verifier
+    #[test]
+    fn verify_registered_matches_program_framing() {
+        let sk = test_key::<Sha2_128s>(0);
+        let other = test_key::<Sha2_128s>(1);
+        let (cipher_kem, nonce, slot, ty) = (vec![0x42u8; 1_100], [7u8; NONCE_LEN], 99u64, 2u16);
+        let msg = finalize_message(&cipher_kem, &SENDER, &RECIPIENT, &nonce, slot, ty, 1);
+        let sig = sign_deterministic(&msg, &sk.to_bytes()).unwrap();
+        let vk = sk.verifying_key().to_bytes().to_vec();
+        let other_vk = other.verifying_key().to_bytes().to_vec();
+        let (s, r) = (&SENDER[..], &RECIPIENT[..]);
+
+        assert!(verify_registered(&cipher_kem, s, r, &nonce, slot, ty, 1, &sig, &vk));
+        assert!(verify(&msg, &sig, &vk));
+        assert!(!verify_registered(&cipher_kem, s, r, &nonce, slot, ty, 1, &sig, &other_vk));
+        assert!(!verify_registered(&cipher_kem, s, r, &nonce, slot + 1, ty, 1, &sig, &vk));
+        assert!(!verify_registered(&cipher_kem, s, r, &nonce, slot, ty + 1, 1, &sig, &vk));
+        assert!(!verify_registered(&cipher_kem, s, r, &nonce[1..], slot, ty, 1, &sig, &vk));
+        // sig_alg is signed, and only SHA2-128s (1) is accepted
+        assert!(!verify_registered(&cipher_kem, s, r, &nonce, slot, ty, 2, &sig, &vk));
+        assert!(!verify_registered(&cipher_kem, s, r, &nonce, slot, ty, 0, &sig, &vk));
+        // Both parties are signed: another recipient, swapped roles or a short key fail
+        assert!(!verify_registered(&cipher_kem, s, &[0xb3; 32], &nonce, slot, ty, 1, &sig, &vk));
+        assert!(!verify_registered(&cipher_kem, r, s, &nonce, slot, ty, 1, &sig, &vk));
+        assert!(!verify_registered(&cipher_kem, s, &r[1..], &nonce, slot, ty, 1, &sig, &vk));
+    }
+
+    #[derive(serde::Deserialize)]
+    struct MessageVector {
+        cipher_kem_hex: String,
+        sender_hex: String,
+        recipient_hex: String,
+        nonce_hex: String,
+        slot: u64,
+        msg_type: u16,
+        sig_alg: u8,
+        message_hex: String,
+    }
+
+    /// finalize_message is byte-identical to the program's signed_message; the program checks the same vector
+    #[test]
+    fn finalize_message_matches_shared_vector() {
+        let v: MessageVector = serde_json::from_str(include_str!("../fixtures/finalize_message.json")).unwrap();
+        let h = |s: &str| hex::decode(s).unwrap();
+        let key = |s: &str| <[u8; 32]>::try_from(h(s)).unwrap();
+        let msg = finalize_message(
+            &h(&v.cipher_kem_hex), &key(&v.sender_hex), &key(&v.recipient_hex), &h(&v.nonce_hex), v.slot, v.msg_type, v.sig_alg,
+        );
+        assert_eq!(hex::encode(msg), v.message_hex);
+    }
+
+    /// A 24-byte nonce is framed with its length and verifies; its 12-byte tail does not
+    #[test]
+    fn verify_registered_extended_nonce() {
+        let sk = test_key::<Sha2_128s>(0);
+        let (cipher_kem, nonce, slot) = (vec![0x42u8; 64], [9u8; XNONCE_LEN], 5u64);
+        let msg = finalize_message(&cipher_kem, &SENDER, &RECIPIENT, &nonce, slot, 0, 1);
+        assert_eq!(msg.len(), cipher_kem.len() + 64 + XNONCE_LEN + 1 + 8 + 2 + 1);
+        let sig = sign_deterministic(&msg, &sk.to_bytes()).unwrap();
+        let vk = sk.verifying_key().to_bytes().to_vec();
+        let (s, r) = (&SENDER[..], &RECIPIENT[..]);
+
+        assert!(verify_registered(&cipher_kem, s, r, &nonce, slot, 0, 1, &sig, &vk));
+        let shifted = [&cipher_kem[..], &nonce[..NONCE_LEN]].concat();
+        assert!(!verify_registered(&shifted, s, r, &nonce[NONCE_LEN..], slot, 0, 1, &sig, &vk));
+        assert!(!verify_registered(&cipher_kem, s, r, &[9u8; XNONCE_LEN + 1], slot, 0, 1, &sig, &vk));
+    }
+
+    /// During a rotation the signature is matched to the key that made it; unrelated keys give None
+    #[test]
+    fn verify_any_finds_signing_key() {
+        let old = test_key::<Sha2_128s>(0);
+        let new = test_key::<Sha2_128s>(1);
+        let other = test_key::<Sha2_128s>(2);
+        let [old_vk, new_vk, other_vk] = [&old, &new, &other].map(|k| k.verifying_key().to_bytes().to_vec());
+        let msg = b"in flight before rotation";
+        let sig = old.sign(msg).to_bytes().to_vec();
+
+        assert_eq!(verify_any_key(msg, &sig, &[&new_vk, &old_vk]), Some(1));
+        assert_eq!(verify_any_key(msg, &sig, &[&old_vk, &new_vk]), Some(0));
+        assert_eq!(verify_any_key(msg, &sig, &[&new_vk, &other_vk]), None);
+        assert_eq!(verify_any_key::<&[u8]>(msg, &sig, &[]), None);
+        assert_eq!(verify_any_key(msg, &sig, &[&old_vk[1..], &old_vk[..]]), Some(1));
+
+        let packed = [&other_vk[..], &new_vk, &old_vk].concat();
+        assert_eq!(verify_any(msg, &sig, &packed), Some(2));
+        assert_eq!(verify_any(b"other message", &sig, &packed), None);
+        assert_eq!(verify_any(msg, &sig, &packed[1..]), None);
+    }
+
+    /// Malformed keys come back as error codes the bindings throw, never a panic
+    #[test]
+    fn malformed_sk_is_an_error() {
+        let sk = test_key::<Sha2_128s>(0);
+        let sk_bytes = sk.to_bytes().to_vec();
+        let long = [&sk_bytes[..], &[0u8]].concat();
+        for bad in [&[][..], &sk_bytes[..PK_LEN], &sk_bytes[1..], &long[..]] {
+            assert_eq!(vk_from_sk(bad).unwrap_err(), "invalid_sk_length");
+            assert_eq!(decode_sk(bad).err(), Some("invalid_sk_length"));
+        }
+        assert_eq!(vk_from_sk(&sk_bytes).unwrap(), sk.verifying_key().to_bytes().to_vec());
+        assert!(sign(b"ok", &sk_bytes).is_ok());
+    }
+
+    /// A 128s signature passes prevalidation; a 128f signature and a truncated one do not
+    #[test]
+    fn prevalidate_signature_catches_wrong_shape() {
+        let sk = test_key::<Sha2_128s>(0);
+        let sig = sign_deterministic(b"upload me", &sk.to_bytes()).unwrap();
+        let ok = SignatureCheck { len_ok: true, structure_ok: true, expected_len: SIG_LEN };
+        assert_eq!(check_signature(&sig), ok);
+
+        let bad = SignatureCheck { len_ok: false, structure_ok: false, expected_len: SIG_LEN };
+        let fast = test_key::<slh_dsa::Sha2_128f>(0);
+        let fast_sig = fast.sign(b"upload me").to_bytes().to_vec();
+        assert_eq!(fast_sig.len(), 17_088);
+        assert_eq!(check_signature(&fast_sig), bad);
+        assert_eq!(check_signature(&sig[..SIG_LEN - 1]), bad);
+        assert_eq!(check_signature(&[]), bad);
+    }
+}
@@ -0,0 +1,69 @@
+//! Interop with Node's native SLH-DSA (crypto.sign / crypto.verify, Node >= 24.8)
+//! Node signs through OpenSSL 3.5 in FIPS 205 pure mode with an empty context unless a context is passed,
+//! so its 7,856 byte SHA2-128s signatures are the same bytes verify and the on-chain verifier expect
+//! The only difference is the key encoding: Node exports public keys as SPKI DER, not the raw 32 bytes
+
+use wasm_bindgen::prelude::*;
+
+/// SPKI DER header for SLH-DSA-SHA2-128s: SEQUENCE { AlgorithmIdentifier { id-slh-dsa-sha2-128s }, BIT STRING (33) }
+const SPKI_PREFIX_SHA2_128S: [u8; 18] = [
+    0x30, 0x30, 0x30, 0x0b, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x03, 0x14, 0x03, 0x21, 0x00,
+];
+
+pub(crate) fn raw_vk_from_spki(der: &[u8]) -> Result<&[u8], &'static str> {
+    match der.strip_prefix(&SPKI_PREFIX_SHA2_128S[..]) {
+        Some(vk) if vk.len() == crate::PK_LEN => Ok(vk),
+        _ => Err("invalid_spki"),
+    }
+}
+
+/// Extracts the raw 32 byte verifying key from a Node/OpenSSL SPKI DER export
+#[wasm_bindgen]
+pub fn vk_bytes_from_spki(der: &[u8]) -> Result<Vec<u8>, JsValue> {
+    raw_vk_from_spki(der).map(<[u8]>::to_vec).map_err(JsValue::from_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slh_dsa::{Sha2_128s, Signature, VerifyingKey};
+
+    #[derive(serde::Deserialize)]
+    struct Fixture { msg_hex: String, spki_der_hex: String, sig_hex: String, ctx: String, sig_ctx_hex: String }
+
+    fn fixture() -> (Vec<u8>, Vec<u8>, Vec<u8>, String, Vec<u8>) {
+        let f: Fixture = serde_json::from_str(include_str!("../fixtures/node_sha2_128s.json")).unwrap();
+        let h = |s: &str| hex::decode(s).unwrap();
+        (h(&f.msg_hex), h(&f.spki_der_hex), h(&f.sig_hex), f.ctx, h(&f.sig_ctx_hex))
+    }
+
+    /// A Node crypto.sign signature (regenerated by CI; OpenSSL 3.5 in the committed copy) verifies with verify and the on-chain streaming verifier
+    #[test]
+    fn node_signature_cross_verifies() {
+        let (msg, der, sig, _, _) = fixture();
+        let vk = vk_bytes_from_spki(&der).unwrap();
+        assert!(crate::verify(&msg, &sig, &vk));
+        assert!(slh_dsa::onchain_sha2::verify_sha2_128s(&msg, &sig, &vk).is_ok());
+        assert!(!crate::verify(b"other", &sig, &vk));
+    }
+
+    /// A signature made with a non-empty context only verifies under that context
+    #[test]
+    fn node_context_signature_needs_context() {
+        let (msg, der, _, ctx, sig_ctx) = fixture();
+        let vk = vk_bytes_from_spki(&der).unwrap();
+        assert!(!crate::verify(&msg, &sig_ctx, &vk));
+        let pk = VerifyingKey::<Sha2_128s>::try_from(&vk[..]).unwrap();
+        let sig = Signature::<Sha2_128s>::try_from(&sig_ctx[..]).unwrap();
+        assert!(pk.try_verify_with_context(&msg, ctx.as_bytes(), &sig).is_ok());
+    }
+
+    #[test]
+    fn spki_prefix_checked() {
+        let (_, der, _, _, _) = fixture();
+        assert_eq!(raw_vk_from_spki(&der[..der.len() - 1]).unwrap_err(), "invalid_spki");
+        let mut other = der.clone();
+        other[14] = 0x15; // id-slh-dsa-sha2-128f
+        assert_eq!(raw_vk_from_spki(&other).unwrap_err(), "invalid_spki");
+    }
+}
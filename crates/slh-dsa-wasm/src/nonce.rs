@@ -0,0 +1,40 @@
+//! Deterministic AEAD nonce derivation shared with aead-cli
+//! nonce = HKDF-SHA256(ikm = ss, salt = none, info = "zk-chat:nonce:v1" || slot_le || counter_le)[..12]
+
+use wasm_bindgen::prelude::*;
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+const SS_LEN: usize = 32; // Kyber768 shared secret length
+const NONCE_LEN: usize = 12; // ChaCha20-Poly1305 nonce; ChatMsg also stores 24-byte XChaCha20 nonces
+const NONCE_INFO: &[u8] = b"zk-chat:nonce:v1"; // Mirrors aead_cli::NONCE_INFO
+
+pub(crate) fn nonce_for(ss: &[u8], slot: u64, counter: u32) -> Result<[u8; NONCE_LEN], &'static str> {
+    if ss.len() != SS_LEN { return Err("invalid_ss_length"); }
+    let info = [NONCE_INFO, &slot.to_le_bytes(), &counter.to_le_bytes()].concat();
+    let mut nonce = [0u8; NONCE_LEN];
+    Hkdf::<Sha256>::new(None, ss).expand(&info, &mut nonce).map_err(|_| "hkdf_expand")?;
+    Ok(nonce)
+}
+
+/// Derives the 12 byte finalize_sig nonce from the KEM shared secret, slot and a per slot counter
+#[wasm_bindgen]
+pub fn derive_nonce(shared_secret: &[u8], slot: u64, counter: u32) -> Result<Vec<u8>, JsValue> {
+    nonce_for(shared_secret, slot, counter).map(|n| n.to_vec()).map_err(JsValue::from_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Matches the aead-cli vector; distinct counters give distinct nonces
+    #[test]
+    fn nonce_matches_host_vector() {
+        let ss = [0x11u8; SS_LEN];
+        let n = nonce_for(&ss, 42, 7).unwrap();
+        assert_eq!(n, nonce_for(&ss, 42, 7).unwrap());
+        assert_eq!(hex::encode(n), "13e5b23312f3570b12535e6f");
+        assert_ne!(n, nonce_for(&ss, 42, 8).unwrap());
+        assert_eq!(nonce_for(&ss[..31], 42, 7), Err("invalid_ss_length"));
+    }
+}
@@ -0,0 +1,61 @@
+//! Incremental SHA-256 for content hashed client side before encryption
+//! Sha256Ctx streams large inputs in pieces; the digest equals cipher_digest (and the program's hashv) over the whole input
+//! Upload chains still use next_chain per chunk; this is not a chain value
+
+use wasm_bindgen::prelude::*;
+use sha2::{Digest, Sha256};
+
+/// Streaming SHA-256 context: new, update any number of times, then finalize once
+#[wasm_bindgen]
+pub struct Sha256Ctx {
+    inner: Sha256,
+}
+
+#[wasm_bindgen]
+impl Sha256Ctx {
+    /// Starts an empty digest
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Sha256Ctx {
+        Sha256Ctx { inner: Sha256::new() }
+    }
+
+    /// Absorbs the next piece of input; piece boundaries do not affect the digest
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// Returns the 32 byte digest and consumes the context (the JS object is freed)
+    pub fn finalize(self) -> Vec<u8> {
+        self.inner.finalize().to_vec()
+    }
+}
+
+impl Default for Sha256Ctx {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Streaming in uneven pieces matches the one-shot digest and cipher_digest
+    #[test]
+    fn streamed_matches_one_shot() {
+        let input: Vec<u8> = (0..100_000u32).map(|i| (i * 31) as u8).collect();
+        for piece in [1usize, 63, 64, 900, 65_536] {
+            let mut ctx = Sha256Ctx::new();
+            for chunk in input.chunks(piece) {
+                ctx.update(chunk);
+            }
+            assert_eq!(ctx.finalize(), crate::cipher_digest(&input), "piece = {piece}");
+        }
+        let mut empty = Sha256Ctx::new();
+        empty.update(&[]);
+        assert_eq!(
+            hex::encode(empty.finalize()),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! STARK preflight for browsers
+//! Verifies a detached (proof, params) pair with the same AIR and MinConjecturedSecurity(127) as verify_stark
+//! (stark feature only, since it links winterfell); cipher_digest recomputes the digest the program derives (seed, inc) from
+
+use wasm_bindgen::prelude::*;
+use sha2::{Digest, Sha256};
+
+/// Returns SHA-256(cipher), the 32 bytes finalize_sig and verify_stark get from hashv(&[cipher])
+/// hashv over a single part is plain SHA-256 of that part; it only differs from hashing a
+/// concatenation when it is given several parts, which the program never does here
+/// Works for the KEM ciphertext too when verify_stark uses InputSource::Kem
+#[wasm_bindgen]
+pub fn cipher_digest(cipher: &[u8]) -> Vec<u8> {
+    Sha256::digest(cipher).to_vec()
+}
+
+/// Parses decimal seed and inc and runs the host verifier from stark-prover
+#[cfg(feature = "stark")]
+pub(crate) fn verify_detached(proof: &[u8], params: &[u8], seed: &str, inc: &str) -> Result<(), String> {
+    let seed: u64 = seed.parse().map_err(|_| "invalid_seed".to_string())?;
+    let inc: u64 = inc.parse().map_err(|_| "invalid_inc".to_string())?;
+    stark_prover::verify_proof_with_params(proof, params, seed, inc).map_err(|e| e.to_string())
+}
+
+/// Returns true if the proof verifies for (seed, inc); pass seed and inc as decimal strings since they are u64
+#[cfg(feature = "stark")]
+#[wasm_bindgen]
+pub fn verify_stark_wasm(proof: &[u8], params: &[u8], seed: &str, inc: &str) -> bool {
+    verify_detached(proof, params, seed, inc).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Single-part digest is plain SHA-256 (FIPS 180-2 "abc" vector) and feeds the prover's public inputs
+    #[test]
+    fn cipher_digest_is_plain_sha256() {
+        assert_eq!(
+            hex::encode(cipher_digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let cipher = b"ciphertext bytes";
+        let digest = cipher_digest(cipher);
+        assert_eq!(digest.len(), 32);
+        let (params, proof) = stark_prover::generate_proof_for_segment(cipher).unwrap();
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        assert!(stark_prover::verify_proof_with_params(&proof, &params, seed, inc).is_ok());
+    }
+
+    /// A real proof verifies; a flipped byte, wrong inputs or malformed strings do not
+    #[cfg(feature = "stark")]
+    #[test]
+    fn real_proof_and_corrupted_proof() {
+        let digest = [5u8; 32];
+        let (params, proof) = stark_prover::generate_proof(&digest).unwrap();
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        let (seed, inc) = (seed.to_string(), inc.to_string());
+        assert!(verify_stark_wasm(&proof, &params, &seed, &inc));
+
+        let mut bad = proof.clone();
+        let mid = bad.len() / 2;
+        bad[mid] ^= 1;
+        assert!(!verify_stark_wasm(&bad, &params, &seed, &inc));
+        assert!(!verify_stark_wasm(&proof, &params, &inc, &seed));
+        assert_eq!(verify_detached(&proof, &params, "-1", &inc).unwrap_err(), "invalid_seed");
+    }
+}
@@ -0,0 +1,271 @@
+//! Upload planning helpers mirroring the program's write_chunk
+//! Splits a body into offset data hash triples with the exact hash chaining the program checks
+//! plan_message_upload lays out the whole flow (both buffers, finalize and verify) in send order
+
+use wasm_bindgen::prelude::*;
+use serde::Serialize;
+#[cfg(not(feature = "blake3-chain"))]
+use sha2::{Digest, Sha256};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use stark_air::limits::{self, FIXED_TXS, MAX_CHAT_PAYLOAD, MAX_CHUNK, MAX_PROOF_BYTES, MAX_SIG_PAYLOAD};
+
+const VERIFY_HEAP_BYTES: u32 = 256 * 1024; // verify_stark heap_bytes, also the requestHeapFrame size
+
+/// One upload_body call worth of arguments
+pub(crate) struct Chunk {
+    pub offset: u32,
+    pub data: Vec<u8>,
+    pub next_hash: [u8; 32],
+}
+
+#[derive(Serialize)]
+pub(crate) struct ChunkJs { offset: u32, data_b64: String, next_hash_b64: String }
+
+impl From<Chunk> for ChunkJs {
+    fn from(c: Chunk) -> Self {
+        ChunkJs { offset: c.offset, data_b64: STANDARD.encode(&c.data), next_hash_b64: STANDARD.encode(c.next_hash) }
+    }
+}
+
+/// One instruction of the full message flow, tagged by its name as { ix: "upload_body", ... }
+#[derive(Serialize)]
+#[serde(tag = "ix", rename_all = "snake_case")]
+pub(crate) enum Step {
+    InitBuffer,
+    UploadBody(ChunkJs),
+    InitSignature,
+    UploadSignature(ChunkJs),
+    FinalizeSig { cipher_len: u32, kem_len: u32 },
+    VerifyStark { source: &'static str, heap_bytes: u32 },
+}
+
+/// Computes H(prev || data), the chain value write_chunk expects for the next chunk
+/// SHA-256 by default, BLAKE3 with the blake3-chain feature (must match the program build)
+pub(crate) fn next_chain(prev: &[u8; 32], data: &[u8]) -> [u8; 32] {
+    #[cfg(not(feature = "blake3-chain"))]
+    let mut h = Sha256::new();
+    #[cfg(feature = "blake3-chain")]
+    let mut h = blake3::Hasher::new();
+    h.update(prev);
+    h.update(data);
+    h.finalize().into()
+}
+
+/// Returns the chain value for the chunk data uploaded after prev (32 bytes), in this build's chain hash
+#[wasm_bindgen]
+pub fn next_upload_chain(prev: &[u8], data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let prev: &[u8; 32] = prev.try_into().map_err(|_| JsValue::from_str("invalid_prev_len"))?;
+    Ok(next_chain(prev, data).to_vec())
+}
+
+/// Folds next_chain over chunks from the zero seed, the sha_chain write_chunk leaves after them
+pub fn fold_chain(chunks: &[&[u8]]) -> [u8; 32] {
+    chunks.iter().fold([0u8; 32], |prev, chunk| next_chain(&prev, chunk))
+}
+
+/// Returns the 32 byte sha_chain after uploading body in chunk_len sized chunks (plan_upload uses 900)
+/// Compare it with the buffer account's sha_chain to find where an upload diverged
+#[wasm_bindgen]
+pub fn fold_upload_chain(body: &[u8], chunk_len: u32) -> Result<Vec<u8>, JsValue> {
+    if chunk_len == 0 || chunk_len as usize > MAX_CHUNK { return Err(JsValue::from_str("invalid_chunk_len")); }
+    let chunks: Vec<&[u8]> = body.chunks(chunk_len as usize).collect();
+    Ok(fold_chain(&chunks).to_vec())
+}
+
+/// Splits raw into chunks of at most MAX_CHUNK bytes chained from the zero seed
+pub(crate) fn plan_chunks(raw: &[u8], hard_max: usize) -> Result<Vec<Chunk>, &'static str> {
+    if raw.is_empty() { return Err("empty_body"); }
+    if raw.len() > hard_max { return Err("body_too_large"); }
+    let mut prev = [0u8; 32];
+    let mut out = Vec::with_capacity(raw.len().div_ceil(MAX_CHUNK));
+    for (i, data) in raw.chunks(MAX_CHUNK).enumerate() {
+        prev = next_chain(&prev, data);
+        out.push(Chunk { offset: (i * MAX_CHUNK) as u32, data: data.to_vec(), next_hash: prev });
+    }
+    Ok(out)
+}
+
+/// Returns the upload_body plan as [{ offset, data_b64, next_hash_b64 }]
+#[wasm_bindgen]
+pub fn plan_upload(body: &[u8]) -> Result<JsValue, JsValue> {
+    let plan: Vec<ChunkJs> = plan_chunks(body, MAX_CHAT_PAYLOAD)
+        .map_err(JsValue::from_str)?
+        .into_iter()
+        .map(ChunkJs::from)
+        .collect();
+    serde_wasm_bindgen::to_value(&plan).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Plans every instruction for one message in send order, with offsets and chain hashes precomputed
+/// Checks the limits the program enforces: body <= MAX_CHAT_PAYLOAD, proof <= MAX_PROOF_BYTES, signature of exactly SIG_LEN
+pub(crate) fn plan_message(cipher: &[u8], kem: &[u8], proof: &[u8], sig: &[u8]) -> Result<Vec<Step>, &'static str> {
+    if proof.len() > MAX_PROOF_BYTES { return Err("proof_too_large"); }
+    if sig.len() > MAX_SIG_PAYLOAD { return Err("sig_too_large"); }
+    if sig.len() != crate::SIG_LEN { return Err("invalid_sig_length"); }
+    let body = [cipher, kem, proof].concat();
+    let body_chunks = plan_chunks(&body, MAX_CHAT_PAYLOAD)?;
+    let sig_chunks = plan_chunks(sig, MAX_SIG_PAYLOAD)?;
+
+    let mut steps = Vec::with_capacity(body_chunks.len() + sig_chunks.len() + FIXED_TXS);
+    steps.push(Step::InitBuffer);
+    steps.extend(body_chunks.into_iter().map(|c| Step::UploadBody(c.into())));
+    steps.push(Step::InitSignature);
+    steps.extend(sig_chunks.into_iter().map(|c| Step::UploadSignature(c.into())));
+    // Lengths fit u32: the body is capped at MAX_CHAT_PAYLOAD above
+    steps.push(Step::FinalizeSig { cipher_len: cipher.len() as u32, kem_len: kem.len() as u32 });
+    steps.push(Step::VerifyStark { source: "cipher", heap_bytes: VERIFY_HEAP_BYTES });
+    Ok(steps)
+}
+
+/// Returns the whole upload → finalize → verify sequence as [{ ix, ...args }]
+/// ix is init_buffer, upload_body, init_signature, upload_signature, finalize_sig or verify_stark;
+/// upload steps carry { offset, data_b64, next_hash_b64 }, finalize_sig carries { cipher_len, kem_len }
+/// and verify_stark { source, heap_bytes }. Accounts, recipient, slot, nonce, slh_pub, msg_type and sig_alg are the
+/// caller's: they were fixed when the signature was made
+#[wasm_bindgen]
+pub fn plan_message_upload(cipher: &[u8], kem: &[u8], proof: &[u8], signature: &[u8]) -> Result<JsValue, JsValue> {
+    let steps = plan_message(cipher, kem, proof, signature).map_err(JsValue::from_str)?;
+    serde_wasm_bindgen::to_value(&steps).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Returns { body_txs, sig_txs, total } so UIs can show cost and progress before uploading
+/// Serializes stark_air's TxEstimate, the same counts stark-pqc-client reports
+#[wasm_bindgen]
+pub fn estimate_tx_count(body_len: usize, sig_len: usize) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&limits::estimate_tx_count(body_len, sig_len)).map_err(|_| JsValue::from_str("serialize"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replays a plan through the same checks write_chunk applies
+    fn simulate_write_chunk(chunks: &[Chunk]) -> (Vec<u8>, [u8; 32]) {
+        let mut buf = Vec::new();
+        let mut chain = [0u8; 32];
+        for c in chunks {
+            assert!(c.data.len() <= MAX_CHUNK);
+            assert_eq!(c.offset as usize, buf.len());
+            assert_eq!(next_chain(&chain, &c.data), c.next_hash);
+            buf.extend_from_slice(&c.data);
+            chain = c.next_hash;
+        }
+        (buf, chain)
+    }
+
+    #[test]
+    fn plan_replays_to_final_chain() {
+        let body: Vec<u8> = (0..2_500u32).map(|i| i as u8).collect();
+        let plan = plan_chunks(&body, MAX_CHAT_PAYLOAD).unwrap();
+        assert_eq!(plan.len(), 3);
+        let (buf, chain) = simulate_write_chunk(&plan);
+        assert_eq!(buf, body);
+        assert_eq!(chain, plan.last().unwrap().next_hash);
+    }
+
+    /// Pins the zero seed and chunk order against a fixed SHA-256 value, and agrees with plan_chunks
+    #[cfg(not(feature = "blake3-chain"))]
+    #[test]
+    fn fold_chain_known_value() {
+        let tail = b"world".repeat(100);
+        let expected = [
+            0x42, 0x9e, 0x97, 0x63, 0xd9, 0x67, 0x6b, 0xf0, 0x13, 0x1a, 0xd7, 0xd3, 0x87, 0x0b, 0xc0, 0x93,
+            0x99, 0x03, 0x7c, 0x51, 0x33, 0xa9, 0x0d, 0x28, 0xd3, 0x75, 0xd8, 0x1a, 0x76, 0x23, 0xe3, 0x41,
+        ];
+        assert_eq!(fold_chain(&[b"hello", &tail]), expected);
+        assert_eq!(fold_chain(&[]), [0u8; 32]);
+        assert_ne!(fold_chain(&[&tail, b"hello"]), expected);
+
+        let body: Vec<u8> = (0..2_500u32).map(|i| i as u8).collect();
+        let plan = plan_chunks(&body, MAX_CHAT_PAYLOAD).unwrap();
+        assert_eq!(fold_chain(&body.chunks(MAX_CHUNK).collect::<Vec<_>>()), plan.last().unwrap().next_hash);
+    }
+
+    /// With blake3-chain the chain is plain BLAKE3 over prev || data, as the program's blake3 hashv computes it
+    #[cfg(feature = "blake3-chain")]
+    #[test]
+    fn chain_is_blake3_with_feature() {
+        let prev = [7u8; 32];
+        assert_eq!(next_chain(&prev, b"abc"), *blake3::hash(&[&prev[..], b"abc"].concat()).as_bytes());
+    }
+
+    #[test]
+    fn plan_rejects_empty_and_oversized() {
+        assert!(plan_chunks(&[], MAX_CHAT_PAYLOAD).is_err());
+        assert!(plan_chunks(&vec![0u8; MAX_CHAT_PAYLOAD + 1], MAX_CHAT_PAYLOAD).is_err());
+    }
+
+    #[test]
+    fn tx_estimate_matches_plan() {
+        assert_eq!(limits::estimate_tx_count(0, crate::SIG_LEN).sig_txs, 9);
+        for len in [1, MAX_CHUNK, MAX_CHUNK + 1, 2_500] {
+            let plan = plan_chunks(&vec![0u8; len], MAX_CHAT_PAYLOAD).unwrap();
+            assert_eq!(limits::estimate_tx_count(len, 0).body_txs, plan.len());
+        }
+    }
+
+    fn decode(c: &ChunkJs) -> Chunk {
+        Chunk {
+            offset: c.offset,
+            data: STANDARD.decode(&c.data_b64).unwrap(),
+            next_hash: STANDARD.decode(&c.next_hash_b64).unwrap().try_into().unwrap(),
+        }
+    }
+
+    /// Replaying the plan in order fills both buffers, passes finalize_sig's signature check and verify_stark
+    #[test]
+    fn message_plan_replays_to_finalize() {
+        use slh_dsa::{signature::Keypair, Sha2_128s};
+        use crate::tests::{RECIPIENT, SENDER};
+        let sk = crate::test_key::<Sha2_128s>(0);
+        let vk = sk.verifying_key().to_bytes().to_vec();
+        let (cipher, kem, nonce, slot, ty) = (vec![0x11u8; 1_500], vec![0x42u8; 1_088], [7u8; 12], 99u64, 1u16);
+        let (_params, proof) = stark_prover::generate_proof_for_segment(&cipher).unwrap();
+        let cipher_kem = [&cipher[..], &kem[..]].concat();
+        let msg = crate::finalize_message(&cipher_kem, &SENDER, &RECIPIENT, &nonce, slot, ty, 1);
+        let sig = crate::sign_deterministic(&msg, &sk.to_bytes()).unwrap();
+
+        let steps = plan_message(&cipher, &kem, &proof, &sig).unwrap();
+        let body_len = cipher_kem.len() + proof.len();
+        let expected = limits::estimate_tx_count(body_len, sig.len());
+        assert_eq!(steps.len(), expected.total);
+
+        let (mut body, mut sigbuf) = (None::<Vec<Chunk>>, None::<Vec<Chunk>>);
+        let (mut finalized, mut verified) = (false, false);
+        for step in &steps {
+            match step {
+                Step::InitBuffer => body = Some(vec![]),
+                Step::UploadBody(c) => body.as_mut().expect("init_buffer first").push(decode(c)),
+                Step::InitSignature => sigbuf = Some(vec![]),
+                Step::UploadSignature(c) => sigbuf.as_mut().expect("init_signature first").push(decode(c)),
+                Step::FinalizeSig { cipher_len, kem_len } => {
+                    let (b, _) = simulate_write_chunk(body.as_ref().unwrap());
+                    let (s, _) = simulate_write_chunk(sigbuf.as_ref().unwrap());
+                    let signed = &b[..(*cipher_len + *kem_len) as usize];
+                    assert!(crate::verify_registered(signed, &SENDER, &RECIPIENT, &nonce, slot, ty, 1, &s, &vk));
+                    finalized = true;
+                }
+                Step::VerifyStark { source, heap_bytes } => {
+                    assert!(finalized);
+                    assert_eq!((*source, *heap_bytes), ("cipher", 256 * 1024));
+                    let (b, _) = simulate_write_chunk(body.as_ref().unwrap());
+                    let (seed, inc) = stark_prover::public_inputs_from_digest(&crate::cipher_digest(&b[..cipher.len()])).unwrap();
+                    stark_prover::verify_proof(&b[cipher_kem.len()..], seed, inc).unwrap();
+                    verified = true;
+                }
+            }
+        }
+        assert!(verified);
+    }
+
+    /// Oversized bodies and proofs, and signatures of the wrong size, are refused before any step is planned
+    #[test]
+    fn message_plan_checks_limits() {
+        let sig = vec![0u8; crate::SIG_LEN];
+        assert!(plan_message(b"c", b"k", &[1], &sig).is_ok());
+        assert_eq!(plan_message(&vec![0u8; MAX_CHAT_PAYLOAD], b"k", &[1], &sig).err(), Some("body_too_large"));
+        assert_eq!(plan_message(b"c", b"k", &vec![0u8; MAX_PROOF_BYTES + 1], &sig).err(), Some("proof_too_large"));
+        assert_eq!(plan_message(b"c", b"k", &[1], &sig[1..]).err(), Some("invalid_sig_length"));
+        assert_eq!(plan_message(b"c", b"k", &[1], &vec![0u8; 17_088]).err(), Some("sig_too_large"));
+    }
+}
@@ -0,0 +1,56 @@
+//! Worker-friendly verification entry for browsers
+//! Nothing in this crate touches window or the DOM: randomness comes from globalThis.crypto, so every binding
+//! also runs inside a Web Worker. verify_job bundles signature and STARK checks into one call that takes
+//! Uint8Arrays (whose buffers the page can transfer to the worker) and returns a plain number, which
+//! postMessage clones without any main-thread object
+
+use wasm_bindgen::prelude::*;
+
+/// verify_job bit: the signature verifies under the key
+pub const JOB_SIG_OK: u32 = 1;
+/// verify_job bit: the STARK proof verifies for (seed, inc) derived from the digest
+pub const JOB_STARK_OK: u32 = 2;
+
+/// Runs both checks; an empty proof skips the STARK check and leaves JOB_STARK_OK clear
+pub(crate) fn job_flags(msg: &[u8], sig: &[u8], pk: &[u8], proof: &[u8], params: &[u8], digest: &[u8]) -> u32 {
+    let mut flags = 0;
+    if crate::verify(msg, sig, pk) { flags |= JOB_SIG_OK; }
+    if !proof.is_empty() {
+        let stark_ok = stark_prover::public_inputs_from_digest(digest)
+            .and_then(|(seed, inc)| stark_prover::verify_proof_with_params(proof, params, seed, inc))
+            .is_ok();
+        if stark_ok { flags |= JOB_STARK_OK; }
+    }
+    flags
+}
+
+/// Verifies a signed message and, when proof is non-empty, its STARK proof against digest (cipher_digest(cipher))
+/// Returns JOB_SIG_OK (1) | JOB_STARK_OK (2); 3 means both passed
+#[wasm_bindgen]
+pub fn verify_job(msg: Vec<u8>, sig: Vec<u8>, pk: Vec<u8>, proof: Vec<u8>, params: Vec<u8>, digest: Vec<u8>) -> u32 {
+    job_flags(&msg, &sig, &pk, &proof, &params, &digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cipher_digest;
+    use slh_dsa::{signature::{Keypair, Signer}, Sha2_128s};
+
+    /// Each flag tracks its own check; a missing proof only clears the STARK bit
+    #[test]
+    fn job_flags_report_each_check() {
+        let sk = crate::test_key::<Sha2_128s>(0);
+        let pk = sk.verifying_key().to_bytes();
+        let cipher = b"worker job cipher";
+        let sig = sk.sign(cipher).to_bytes();
+        let digest = cipher_digest(cipher);
+        let (params, proof) = stark_prover::generate_proof_for_segment(cipher).unwrap();
+
+        assert_eq!(job_flags(cipher, &sig, &pk, &proof, &params, &digest), JOB_SIG_OK | JOB_STARK_OK);
+        assert_eq!(job_flags(cipher, &sig, &pk, &[], &[], &digest), JOB_SIG_OK);
+        assert_eq!(job_flags(b"other", &sig, &pk, &proof, &params, &digest), JOB_STARK_OK);
+        assert_eq!(job_flags(cipher, &sig, &pk, &proof, &params, &cipher_digest(b"other")), JOB_SIG_OK);
+        assert_eq!(job_flags(cipher, &sig, &pk, &proof, &params, &digest[..8]), JOB_SIG_OK);
+    }
+}
@@ -0,0 +1,100 @@
+//! SLH-DSA-SHA2-128s signature inspector for teaching and debugging.
+//! Splits a 7,856-byte signature into R, the FORS signature and the seven XMSS layers,
+//! and prints each component's byte range and hex, using the layout constants of slh_dsa::onchain_sha2.
+
+use std::ops::Range;
+
+use anyhow::{ensure, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{ArgGroup, Parser};
+use slh_dsa::onchain_sha2::{FORS_SIG_LEN, HT_LAYERS, RAND_LEN, SIG_LEN_128S, XMSS_SIG_LEN};
+
+/// Defines the CLI; the signature comes from --sig (base64) or --file (raw bytes).
+#[derive(Parser, Debug)]
+#[command(name = "slh-inspect")]
+#[command(about = "Prints the R, FORS and XMSS layer breakdown of an SLH-DSA-SHA2-128s signature", long_about = None)]
+#[command(group(ArgGroup::new("input").required(true).args(["sig", "file"])))]
+struct Cli {
+    /// Base64 signature.
+    #[arg(long)]
+    sig: Option<String>,
+    /// Path to a raw signature file.
+    #[arg(long)]
+    file: Option<std::path::PathBuf>,
+}
+
+/// One named component of the signature and its byte range.
+#[derive(Debug, PartialEq, Eq)]
+struct Section {
+    name: String,
+    range: Range<usize>,
+}
+
+/// Lists the components in signature order: R, FORS, then XMSS layers 0 (bottom) to HT_LAYERS - 1.
+fn sections() -> Vec<Section> {
+    let mut out = vec![
+        Section { name: "R".into(), range: 0..RAND_LEN },
+        Section { name: "FORS".into(), range: RAND_LEN..RAND_LEN + FORS_SIG_LEN },
+    ];
+    let mut off = RAND_LEN + FORS_SIG_LEN;
+    for layer in 0..HT_LAYERS {
+        out.push(Section { name: format!("XMSS layer {layer}"), range: off..off + XMSS_SIG_LEN });
+        off += XMSS_SIG_LEN;
+    }
+    out
+}
+
+/// Renders one line per component: name, [start, end), length and hex.
+fn describe(sig: &[u8]) -> Result<Vec<String>> {
+    ensure!(sig.len() == SIG_LEN_128S, "expected a {SIG_LEN_128S}-byte SHA2-128s signature, got {} bytes", sig.len());
+    Ok(sections()
+        .into_iter()
+        .map(|s| {
+            let (start, end, len) = (s.range.start, s.range.end, s.range.len());
+            format!("{:<13} [{start:>4}, {end:>4})  {len:>4} bytes  {}", s.name, hex::encode(&sig[s.range]))
+        })
+        .collect())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let sig = match (cli.sig, cli.file) {
+        (Some(b64), _) => STANDARD.decode(b64.trim())?,
+        (None, Some(path)) => std::fs::read(path)?,
+        (None, None) => unreachable!("clap requires --sig or --file"),
+    };
+    for line in describe(&sig)? {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The components tile the signature exactly: contiguous from 0, no overlap, ending at SIG_LEN_128S.
+    #[test]
+    fn sections_cover_signature() {
+        let all = sections();
+        assert_eq!(all.len(), 2 + HT_LAYERS);
+        assert_eq!(all.iter().map(|s| s.range.len()).sum::<usize>(), SIG_LEN_128S);
+        assert_eq!(all[0].range.start, 0);
+        assert_eq!(all.last().unwrap().range.end, SIG_LEN_128S);
+        for pair in all.windows(2) {
+            assert_eq!(pair[0].range.end, pair[1].range.start);
+        }
+    }
+
+    /// Each line carries its component's bytes; other lengths are refused.
+    #[test]
+    fn describe_prints_each_component() {
+        let sig: Vec<u8> = (0..SIG_LEN_128S).map(|i| (i % 251) as u8).collect();
+        let lines = describe(&sig).unwrap();
+        assert_eq!(lines.len(), 2 + HT_LAYERS);
+        assert!(lines[0].starts_with("R "));
+        assert!(lines[0].ends_with(&hex::encode(&sig[..RAND_LEN])));
+        assert!(lines[HT_LAYERS + 1].ends_with(&hex::encode(&sig[SIG_LEN_128S - XMSS_SIG_LEN..])));
+        assert!(describe(&sig[1..]).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! Shared AIR for the demo STARK, used by crates/stark-prover and programs/stark-pqc-verifier.
+//! Affine counter x_{t+1} = x_t + inc with x_0 = seed and x_last = seed + inc * (n - 1).
+//! Public inputs (seed, inc) come from SHA-256(cipher) (or SHA-256(kem)).
+//! no_std so the program can link it on SBF; the std feature only forwards to winterfell.
+//! limits holds the upload and payload caps the program, the client and the WASM crate share, and the
+//! transaction estimate both off-chain planners report.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod limits;
+
+use alloc::{vec, vec::Vec};
+use winterfell::{
+    crypto::{hashers::Sha2_256, DefaultRandomCoin, MerkleTree},
+    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
+};
+
+/// Default hasher (hash-id 0x01), Merkle vector commitment and random coin.
+pub type H = Sha2_256<BaseElement>;
+pub type VC = MerkleTree<H>;
+pub type RC = DefaultRandomCoin<H>;
+
+/// Main trace width: a single counter column.
+pub const TRACE_WIDTH: usize = 1;
+
+/// Boundary assertions (x_0 and x_last).
+pub const NUM_ASSERTIONS: usize = 2;
+
+/// Public inputs for the AIR: (seed, inc) as base field elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicInputs {
+    pub seed: BaseElement,
+    pub inc: BaseElement,
+}
+
+impl PublicInputs {
+    /// Lifts the u64 values derived from the digest into the field.
+    pub fn new(seed: u64, inc: u64) -> Self {
+        Self { seed: BaseElement::from(seed), inc: BaseElement::from(inc) }
+    }
+}
+
+impl ToElements<BaseElement> for PublicInputs {
+    fn to_elements(&self) -> Vec<BaseElement> {
+        vec![self.seed, self.inc]
+    }
+}
+
+/// Affine-counter AIR: one degree-1 transition constraint and NUM_ASSERTIONS boundary assertions.
+pub struct MessageAir {
+    ctx: AirContext<BaseElement>,
+    pi: PublicInputs,
+}
+
+impl Air for MessageAir {
+    type BaseField = BaseElement;
+    type PublicInputs = PublicInputs;
+
+    fn new(info: TraceInfo, pi: PublicInputs, opts: ProofOptions) -> Self {
+        let degrees = vec![TransitionConstraintDegree::new(1)];
+        let ctx = AirContext::new(info, degrees, NUM_ASSERTIONS, opts);
+        Self { ctx, pi }
+    }
+
+    fn context(&self) -> &AirContext<Self::BaseField> {
+        &self.ctx
+    }
+
+    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        frame: &EvaluationFrame<E>,
+        _periodic: &[E],
+        result: &mut [E],
+    ) {
+        let inc = E::from(self.pi.inc);
+        result[0] = frame.next()[0] - frame.current()[0] - inc;
+    }
+
+    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
+        let last = self.trace_length() - 1;
+        vec![
+            Assertion::single(0, 0, self.pi.seed),
+            Assertion::single(0, last, self.pi.seed + self.pi.inc * BaseElement::from(last as u64)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winterfell::{math::StarkField, AcceptableOptions, BatchingMethod, FieldExtension, Proof};
+
+    fn verify(proof: Proof, seed: u64, inc: u64) -> Result<(), winterfell::VerifierError> {
+        let opts = AcceptableOptions::MinConjecturedSecurity(127);
+        winterfell::verify::<MessageAir, H, RC, VC>(proof, PublicInputs::new(seed, inc), &opts)
+    }
+
+    /// A proof from stark-prover (which proves with this AIR) verifies against this AIR directly.
+    #[test]
+    fn prover_proof_verifies_with_shared_air() {
+        let digest = [7u8; 32];
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        let (_params, blob) = stark_prover::generate_proof(&digest).unwrap();
+        let proof = Proof::from_bytes(&blob[1..]).unwrap();
+        assert_eq!(proof.trace_info().main_trace_width(), TRACE_WIDTH);
+
+        let opts = AcceptableOptions::MinConjecturedSecurity(127);
+        let ok = winterfell::verify::<MessageAir, H, RC, VC>(proof.clone(), PublicInputs::new(seed, inc), &opts);
+        assert!(ok.is_ok());
+        let bad = winterfell::verify::<MessageAir, H, RC, VC>(proof, PublicInputs::new(seed, inc ^ 1), &opts);
+        assert!(bad.is_err());
+    }
+
+    /// Edge inputs proven directly, without SHA-256, verify against this AIR and only for their own (seed, inc).
+    #[test]
+    fn edge_inputs_round_trip() {
+        for (seed, inc) in [(0, 0), (0, 1), (u64::MAX, 0), (0, u64::MAX), (u64::MAX, u64::MAX)] {
+            for trace_len in [8, 64] {
+                let (_params, blob) = stark_prover::prove_with_inputs(seed, inc, trace_len).unwrap();
+                let proof = Proof::from_bytes(&blob[1..]).unwrap();
+                assert!(verify(proof.clone(), seed, inc).is_ok(), "seed={seed} inc={inc} n={trace_len}");
+                assert!(verify(proof.clone(), seed, inc.wrapping_add(1)).is_err());
+                assert!(verify(proof, seed.wrapping_sub(1), inc).is_err());
+            }
+        }
+    }
+
+    /// x_last = seed + inc * (n - 1) is taken in the 128-bit field: with u64 inputs it grows past u64::MAX
+    /// instead of wrapping, and stays below the modulus for any trace length under 2^63.
+    #[test]
+    fn last_assertion_does_not_wrap() {
+        let opts = ProofOptions::new(30, 16, 8, FieldExtension::None, 4, 31, BatchingMethod::Linear, BatchingMethod::Linear);
+        for trace_len in [8usize, 64, 1 << 20] {
+            let air = MessageAir::new(TraceInfo::new(TRACE_WIDTH, trace_len), PublicInputs::new(u64::MAX, u64::MAX), opts.clone());
+            let assertions = air.get_assertions();
+            assert_eq!(assertions.len(), NUM_ASSERTIONS);
+            assert_eq!((assertions[1].column(), assertions[1].first_step()), (0, trace_len - 1));
+
+            let want = u64::MAX as u128 * trace_len as u128;
+            assert_eq!(assertions[1].values()[0].as_int(), want);
+            assert!(want > u64::MAX as u128 && want < BaseElement::MODULUS);
+            assert_ne!(want, u64::MAX.wrapping_mul(trace_len as u64) as u128);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Upload and payload limits shared by programs/stark-pqc-verifier, crates/stark-pqc-client and crates/slh-dsa-wasm.
+//! The program derives the payload caps from its account layout and checks them against these values at compile time.
+//! estimate_tx_count is the one transaction-count rule both off-chain planners report.
+
+/// Per-instruction upload cap (fits one transaction).
+pub const MAX_CHUNK: usize = 900;
+
+/// Largest cipher || kem || proof a ChatMsg holds: 10,240 - 8 (discriminator) - 215 (fixed fields).
+pub const MAX_CHAT_PAYLOAD: usize = 10_017;
+
+/// Largest signature buffer payload: 10,232 (buffer account) - 76 (header).
+pub const MAX_SIG_PAYLOAD: usize = 10_156;
+
+/// Canonical (length 8) proof plus margin; checked before Proof::read_from.
+pub const MAX_PROOF_BYTES: usize = 8 * 1024;
+
+/// Transactions outside the chunk uploads: init_buffer, init_signature, finalize_sig, verify_stark.
+pub const FIXED_TXS: usize = 4;
+
+/// Transaction count for one message upload, one upload call per ≤ MAX_CHUNK chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TxEstimate {
+    pub body_txs: usize,
+    pub sig_txs: usize,
+    pub total: usize,
+}
+
+/// Estimates how many transactions the upload → finalize → verify flow sends.
+pub fn estimate_tx_count(body_len: usize, sig_len: usize) -> TxEstimate {
+    let body_txs = body_len.div_ceil(MAX_CHUNK);
+    let sig_txs = sig_len.div_ceil(MAX_CHUNK);
+    TxEstimate { body_txs, sig_txs, total: body_txs + sig_txs + FIXED_TXS }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chunk counts round up at MAX_CHUNK; a full body and a 7,856-byte signature take 12 + 9 uploads.
+    #[test]
+    fn tx_estimate_representative_sizes() {
+        assert_eq!(estimate_tx_count(0, 7_856), TxEstimate { body_txs: 0, sig_txs: 9, total: 9 + FIXED_TXS });
+        assert_eq!(estimate_tx_count(MAX_CHUNK, 0).body_txs, 1);
+        assert_eq!(estimate_tx_count(MAX_CHUNK + 1, 0).body_txs, 2);
+        assert_eq!(estimate_tx_count(MAX_CHAT_PAYLOAD, 7_856).total, 12 + 9 + FIXED_TXS);
+    }
+}
@@ -0,0 +1,882 @@
+//! Typed Rust client for the stark-pqc-verifier program.
+//! Derives the PDAs and builds ready-to-sign instructions for the upload → finalize → verify flow.
+//! Upload builders chunk the input at MAX_CHUNK and precompute the hash chain write_chunk checks.
+//! estimate_tx_count sizes the flow upfront for progress and fee displays.
+//! Sharded builders split payloads over MAX_CHAT_PAYLOAD across ChatShard accounts.
+//! verify_chat_account re-checks a fetched ChatMsg off-chain (SLH-DSA + STARK) for indexers.
+
+use anchor_lang::{
+    prelude::{ProgramData, Pubkey},
+    require,
+    solana_program::{
+        hash::hashv,
+        instruction::{AccountMeta, Instruction},
+        rent::Rent,
+        sysvar,
+    },
+    system_program, AccountDeserialize, InstructionData, Owner, Result,
+};
+use stark_pqc_verifier::{
+    crypto, derive_public_inputs, instruction as ix, signed_message,
+    state::{chat_account_space, check_sig_alg, ChatMsg, ErrorCode, MAX_CHUNK, MAX_PROOF_BYTES},
+    InputSource,
+};
+
+pub use stark_pqc_verifier::ID as PROGRAM_ID;
+/// Return data of upload_body / upload_signature (borsh): the buffer's new length and sha_chain.
+pub use stark_pqc_verifier::UploadProgress;
+/// Return data of audit_chatmsg, and the same check for fetched account data.
+pub use stark_pqc_verifier::{audit_chat_msg, ChatMsgAudit, ChatMsgIssue};
+/// Merkle helpers for sharded payloads (finalize_sig_sharded / store_shard).
+pub use stark_pqc_verifier::{SHARD_COMMITMENT_TAG, shard_commitment, shard_leaf, shard_path, shards_root};
+
+/// Derives the body buffer PDA: ["buf", sender].
+pub fn buffer_pda(program_id: &Pubkey, sender: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"buf", sender.as_ref()], program_id).0
+}
+
+/// Derives the signature buffer PDA: ["sig", sender, recipient, slot_le].
+pub fn sig_pda(program_id: &Pubkey, sender: &Pubkey, recipient: &Pubkey, slot: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"sig", sender.as_ref(), recipient.as_ref(), &slot.to_le_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// Derives the ChatMsg PDA: ["msg", sender, recipient, slot_le].
+pub fn msg_pda(program_id: &Pubkey, sender: &Pubkey, recipient: &Pubkey, slot: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"msg", sender.as_ref(), recipient.as_ref(), &slot.to_le_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// Derives the ChatMsg PDAs of one conversation, one per slot and in the same order.
+/// Pass them to getMultipleAccounts instead of scanning with getProgramAccounts; unfinalized slots come back empty.
+pub fn conversation_pdas(program_id: &Pubkey, sender: &Pubkey, recipient: &Pubkey, slots: &[u64]) -> Vec<Pubkey> {
+    slots.iter().map(|&slot| msg_pda(program_id, sender, recipient, slot)).collect()
+}
+
+/// Derives a ChatShard PDA: ["shard", chat_msg, index_le].
+pub fn shard_pda(program_id: &Pubkey, chat_msg: &Pubkey, index: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"shard", chat_msg.as_ref(), &index.to_le_bytes()], program_id).0
+}
+
+/// Derives the sender's NonceRegistry PDA: ["nonce", sender].
+pub fn nonce_pda(program_id: &Pubkey, sender: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"nonce", sender.as_ref()], program_id).0
+}
+
+/// Derives the trusted-relayer StarkConfig PDA: ["config"].
+pub fn config_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], program_id).0
+}
+
+/// Derives the CoSigners PDA finalize_sig_multi writes for a ChatMsg: ["cosig", chat_msg].
+pub fn cosigners_pda(program_id: &Pubkey, chat_msg: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"cosig", chat_msg.as_ref()], program_id).0
+}
+
+/// Derives the owner's PqIdentity PDA: ["pqid", owner].
+pub fn identity_pda(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pqid", owner.as_ref()], program_id).0
+}
+
+/// Computes H(prev || data), the next sha_chain value write_chunk expects (same hash as the program build).
+pub fn next_chain(prev: &[u8; 32], data: &[u8]) -> [u8; 32] {
+    stark_pqc_verifier::next_chain(prev, data)
+}
+
+/// Folds next_chain over chunks from the zero seed; compare with the buffer's sha_chain to debug HashMismatch.
+pub fn fold_chain(chunks: &[&[u8]]) -> [u8; 32] {
+    stark_pqc_verifier::fold_chain(chunks)
+}
+
+// Splits raw into (offset, chunk, next_hash) triples chained from the zero seed.
+fn chained_chunks(raw: &[u8]) -> Vec<(u32, Vec<u8>, [u8; 32])> {
+    let mut prev = [0u8; 32];
+    raw.chunks(MAX_CHUNK)
+        .enumerate()
+        .map(|(i, data)| {
+            prev = next_chain(&prev, data);
+            ((i * MAX_CHUNK) as u32, data.to_vec(), prev)
+        })
+        .collect()
+}
+
+/// Transactions outside the chunk uploads: init_buffer, init_signature, finalize_sig, verify_stark.
+/// estimate_tx_count / TxEstimate live in stark-air so slh-dsa-wasm reports the same counts.
+pub use stark_air::limits::{estimate_tx_count, TxEstimate, FIXED_TXS};
+
+/// Lamports finalize_sig moves from the payer to make a ChatMsg with payload_len bytes
+/// (cipher || kem || proof) rent-exempt. Pass the cluster's Rent sysvar, or Rent::default() for mainnet values.
+pub fn chat_rent_lamports(payload_len: usize, rent: &Rent) -> u64 {
+    rent.minimum_balance(chat_account_space(payload_len))
+}
+
+/// Builds init_buffer (create/reset the body buffer).
+pub fn build_init_buffer_ix(program_id: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(buffer_pda(program_id, payer), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: ix::InitBuffer {}.data(),
+    }
+}
+
+/// Builds reset_message (empty the sender's existing body buffer before re-uploading a changed body).
+pub fn build_reset_message_ix(program_id: &Pubkey, sender: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(buffer_pda(program_id, sender), false),
+            AccountMeta::new_readonly(*sender, true),
+        ],
+        data: ix::ResetMessage {}.data(),
+    }
+}
+
+/// Builds init_signature (create/reset the signature buffer for (recipient, slot)).
+pub fn build_init_signature_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    slot: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(sig_pda(program_id, payer, recipient, slot), false),
+            AccountMeta::new_readonly(*recipient, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: ix::InitSignature { recipient: *recipient, slot }.data(),
+    }
+}
+
+/// Builds one upload_body call per ≤ MAX_CHUNK chunk of body (cipher || kem || proof).
+pub fn build_upload_body_ixs(program_id: &Pubkey, sender: &Pubkey, body: &[u8]) -> Vec<Instruction> {
+    let buffer = buffer_pda(program_id, sender);
+    chained_chunks(body)
+        .into_iter()
+        .map(|(off, data, hash)| Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(buffer, false),
+                AccountMeta::new_readonly(*sender, true),
+            ],
+            data: ix::UploadBody { off, data, hash }.data(),
+        })
+        .collect()
+}
+
+/// Builds one upload_signature call per ≤ MAX_CHUNK chunk of the signature.
+pub fn build_upload_signature_ixs(
+    program_id: &Pubkey,
+    sender: &Pubkey,
+    recipient: &Pubkey,
+    slot: u64,
+    sig: &[u8],
+) -> Vec<Instruction> {
+    let buffer = sig_pda(program_id, sender, recipient, slot);
+    let chat = msg_pda(program_id, sender, recipient, slot);
+    chained_chunks(sig)
+        .into_iter()
+        .map(|(off, data, hash)| Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(buffer, false),
+                AccountMeta::new_readonly(*sender, true),
+                AccountMeta::new_readonly(*recipient, false),
+                AccountMeta::new_readonly(chat, false),
+            ],
+            data: ix::UploadSignature { recipient: *recipient, slot, off, data, hash }.data(),
+        })
+        .collect()
+}
+
+/// Builds finalize_sig (verify SLH-DSA and persist ChatMsg). Request ~128 KiB heap alongside it.
+/// `nonce` is the 12- or 24-byte AEAD nonce; other lengths fail with NonceLengthInvalid.
+/// `sig_alg` is the signing parameter set; only SIG_ALG_SHA2_128S is accepted (SigAlgUnsupported otherwise).
+#[allow(clippy::too_many_arguments)]
+pub fn build_finalize_sig_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    cipher_len: u32,
+    kem_len: u32,
+    nonce: &[u8],
+    slot: u64,
+    slh_pub: [u8; 32],
+    msg_type: u16,
+    sig_alg: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: finalize_sig_accounts(program_id, payer, recipient, slot, None),
+        data: ix::FinalizeSig { cipher_len, kem_len, nonce: nonce.to_vec(), slot, slh_pub, msg_type, sig_alg }.data(),
+    }
+}
+
+/// Builds finalize_sig co-signed by a trusted relayer: the ChatMsg records relayer_attested = true
+/// and verify_stark is not needed. Fails with RelayerNotAuthorized unless StarkConfig enables skip_stark for `relayer`.
+#[allow(clippy::too_many_arguments)]
+pub fn build_finalize_sig_attested_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    cipher_len: u32,
+    kem_len: u32,
+    nonce: &[u8],
+    slot: u64,
+    slh_pub: [u8; 32],
+    msg_type: u16,
+    sig_alg: u8,
+    relayer: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: finalize_sig_accounts(program_id, payer, recipient, slot, Some(relayer)),
+        data: ix::FinalizeSig { cipher_len, kem_len, nonce: nonce.to_vec(), slot, slh_pub, msg_type, sig_alg }.data(),
+    }
+}
+
+/// Builds finalize_sig_prehash: same accounts and arguments as finalize_sig, for a HashSLH-DSA signature
+/// over SHA-256(signed_message(..)) with M' = 1 || 0 || SHA-256 OID || digest.
+#[allow(clippy::too_many_arguments)]
+pub fn build_finalize_sig_prehash_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    cipher_len: u32,
+    kem_len: u32,
+    nonce: &[u8],
+    slot: u64,
+    slh_pub: [u8; 32],
+    msg_type: u16,
+    sig_alg: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: finalize_sig_accounts(program_id, payer, recipient, slot, None),
+        data: ix::FinalizeSigPrehash { cipher_len, kem_len, nonce: nonce.to_vec(), slot, slh_pub, msg_type, sig_alg }.data(),
+    }
+}
+
+// FinalizeSig account list; identity is always the sender's PqIdentity address, registered or not.
+// Anchor reads the program id in an optional slot (config, relayer) as None.
+fn finalize_sig_accounts(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    slot: u64,
+    relayer: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let (config, relayer) = match relayer {
+        Some(r) => (AccountMeta::new_readonly(config_pda(program_id), false), AccountMeta::new_readonly(*r, true)),
+        None => (AccountMeta::new_readonly(*program_id, false), AccountMeta::new_readonly(*program_id, false)),
+    };
+    vec![
+        AccountMeta::new(buffer_pda(program_id, payer), false),
+        AccountMeta::new(sig_pda(program_id, payer, recipient, slot), false),
+        AccountMeta::new(msg_pda(program_id, payer, recipient, slot), false),
+        AccountMeta::new(nonce_pda(program_id, payer), false),
+        AccountMeta::new_readonly(*recipient, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(identity_pda(program_id, payer), false),
+        config,
+        relayer,
+    ]
+}
+
+/// Builds finalize_sig_multi. `slh_pubs[0]` signs the payer's sig buffer and must be the payer's registered key;
+/// `cosigners[i]` is the wallet whose registered key is `slh_pubs[i + 1]` and whose sig PDA for this recipient and
+/// slot holds that signature. The signers are recorded at cosigners_pda(msg_pda(..)).
+/// Each signature costs ~500k CU, so request the 1.4M CU maximum.
+#[allow(clippy::too_many_arguments)]
+pub fn build_finalize_sig_multi_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    cipher_len: u32,
+    kem_len: u32,
+    nonce: &[u8],
+    slot: u64,
+    slh_pubs: Vec<[u8; 32]>,
+    msg_type: u16,
+    sig_alg: u8,
+    cosigners: &[Pubkey],
+) -> Instruction {
+    let chat_msg = msg_pda(program_id, payer, recipient, slot);
+    let mut accounts = vec![
+        AccountMeta::new(buffer_pda(program_id, payer), false),
+        AccountMeta::new(sig_pda(program_id, payer, recipient, slot), false),
+        AccountMeta::new(chat_msg, false),
+        AccountMeta::new(nonce_pda(program_id, payer), false),
+        AccountMeta::new_readonly(*recipient, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(identity_pda(program_id, payer), false),
+        AccountMeta::new(cosigners_pda(program_id, &chat_msg), false),
+    ];
+    for owner in cosigners {
+        accounts.push(AccountMeta::new_readonly(identity_pda(program_id, owner), false));
+        accounts.push(AccountMeta::new_readonly(sig_pda(program_id, owner, recipient, slot), false));
+    }
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: ix::FinalizeSigMulti { cipher_len, kem_len, nonce: nonce.to_vec(), slot, slh_pubs, msg_type, sig_alg }.data(),
+    }
+}
+
+/// Builds finalize_sig_sharded: commits `shard_count` shards under `shard_root` (see shards_root) instead of a body.
+/// Sign signed_message(&shard_commitment(..), payer, recipient, nonce, slot, msg_type, sig_alg); then upload and store_shard each shard.
+#[allow(clippy::too_many_arguments)]
+pub fn build_finalize_sig_sharded_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    cipher_len: u32,
+    kem_len: u32,
+    nonce: &[u8],
+    slot: u64,
+    slh_pub: [u8; 32],
+    msg_type: u16,
+    sig_alg: u8,
+    shard_count: u16,
+    shard_root: [u8; 32],
+) -> Instruction {
+    // finalize_sig's list from sigbuf through identity: no body buffer, config or relayer.
+    let accounts = finalize_sig_accounts(program_id, payer, recipient, slot, None)[1..8].to_vec();
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: ix::FinalizeSigSharded {
+            cipher_len, kem_len, nonce: nonce.to_vec(), slot, slh_pub, msg_type, sig_alg, shard_count, shard_root,
+        }
+        .data(),
+    }
+}
+
+/// Builds store_shard: moves the uploaded body buffer into shard `index`. `path` is shard_path(leaves, index).
+pub fn build_store_shard_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    slot: u64,
+    index: u16,
+    path: Vec<[u8; 32]>,
+) -> Instruction {
+    let chat = msg_pda(program_id, payer, recipient, slot);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(buffer_pda(program_id, payer), false),
+            AccountMeta::new_readonly(chat, false),
+            AccountMeta::new(shard_pda(program_id, &chat, index), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: ix::StoreShard { recipient: *recipient, slot, index, path }.data(),
+    }
+}
+
+/// Builds set_stark_config. `admin` must be the program's upgrade authority (checked against ProgramData).
+pub fn build_set_stark_config_ix(program_id: &Pubkey, admin: &Pubkey, relayer: Pubkey, skip_stark: bool) -> Instruction {
+    let program_data = Pubkey::find_program_address(&[program_id.as_ref()], &ProgramData::owner()).0;
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(config_pda(program_id), false),
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new_readonly(program_data, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: ix::SetStarkConfig { relayer, skip_stark }.data(),
+    }
+}
+
+/// Builds register_pq_key (create the owner's PqIdentity, or update it to vk).
+pub fn build_register_pq_key_ix(program_id: &Pubkey, owner: &Pubkey, vk: [u8; 32]) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(identity_pda(program_id, owner), false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: ix::RegisterPqKey { vk }.data(),
+    }
+}
+
+/// Builds rotate_pq_key (owner-signed key replacement).
+pub fn build_rotate_pq_key_ix(program_id: &Pubkey, owner: &Pubkey, new_vk: [u8; 32]) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(identity_pda(program_id, owner), false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: ix::RotatePqKey { new_vk }.data(),
+    }
+}
+
+/// Builds verify_stark for a ChatMsg. heap_bytes must equal the requestHeapFrame sent alongside it;
+/// the program reads that frame from the instructions sysvar and rejects a larger heap_bytes.
+pub fn build_verify_stark_ix(
+    program_id: &Pubkey,
+    chat_msg: &Pubkey,
+    source: InputSource,
+    heap_bytes: u32,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*chat_msg, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+        ],
+        data: ix::VerifyStark { source, heap_bytes }.data(),
+    }
+}
+
+/// Builds verify_stark for a sharded ChatMsg, appending its shard_count ChatShards in index order.
+pub fn build_verify_stark_sharded_ix(
+    program_id: &Pubkey,
+    chat_msg: &Pubkey,
+    shard_count: u16,
+    source: InputSource,
+    heap_bytes: u32,
+) -> Instruction {
+    let mut verify = build_verify_stark_ix(program_id, chat_msg, source, heap_bytes);
+    verify.accounts.extend((0..shard_count).map(|i| AccountMeta::new_readonly(shard_pda(program_id, chat_msg, i), false)));
+    verify
+}
+
+/// Builds audit_sig: re-checks the signature buffer at sig_pda against the ChatMsg's sig_hash.
+pub fn build_audit_sig_ix(
+    program_id: &Pubkey,
+    chat_msg: &Pubkey,
+    sig_pda: &Pubkey,
+    expected_sig_hash: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*chat_msg, false),
+            AccountMeta::new_readonly(*sig_pda, false),
+        ],
+        data: ix::AuditSig { expected_sig_hash }.data(),
+    }
+}
+
+/// Builds audit_chatmsg for a ChatMsg. Simulate it and borsh-decode the return data as ChatMsgAudit.
+pub fn build_audit_chatmsg_ix(program_id: &Pubkey, chat_msg: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*chat_msg, false)],
+        data: ix::AuditChatmsg {}.data(),
+    }
+}
+
+/// Builds message_exists for (sender, recipient, slot). Simulate it: the return data is one byte, 1 if finalized.
+pub fn build_message_exists_ix(program_id: &Pubkey, sender: &Pubkey, recipient: &Pubkey, slot: u64) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(msg_pda(program_id, sender, recipient, slot), false)],
+        data: ix::MessageExists { sender: *sender, recipient: *recipient, slot }.data(),
+    }
+}
+
+/// True when `sig` hashes to a ChatMsg's sig_hash. finalize_sig stores hashv(&[sig]), and hashv over a single
+/// part is plain SHA-256 of it, so a signature fetched from the sig buffer (or kept by the sender) can be checked
+/// without a transaction.
+pub fn verify_sig_hash(sig: &[u8], expected: [u8; 32]) -> bool {
+    hashv(&[sig]).to_bytes() == expected
+}
+
+/// Verifies a raw ChatMsg account (as returned by RPC) against the sender's SLH-DSA key and signature.
+/// Returns Ok(false) when the signature, its recorded hash, or the STARK proof does not check out,
+/// and Err when the account does not decode, is sharded (its payload lives in ChatShards),
+/// or records a sig_alg this crate cannot verify (SigAlgUnsupported).
+/// The proof may bind either segment, as verify_stark allows.
+/// The signature may come from finalize_sig (pure) or finalize_sig_prehash; ChatMsg does not record which,
+/// and the two M' framings are domain-separated, so either verdict is sound.
+pub fn verify_chat_account(account_data: &[u8], vk: &[u8], sig: &[u8]) -> Result<bool> {
+    let chat = ChatMsg::try_deserialize(&mut &account_data[..])?;
+    require!(vk.len() == 32, ErrorCode::LenMismatch);
+    require!(!chat.is_sharded(), ErrorCode::ShardInvalid);
+    check_sig_alg(chat.sig_alg)?;
+    let (cipher, kem, proof) = (chat.cipher()?, chat.kem()?, chat.proof()?);
+
+    if sig.len() != chat.sig_len as usize || !verify_sig_hash(sig, chat.sig_hash) {
+        return Ok(false);
+    }
+    let cipher_kem = &chat.payload[..cipher.len() + kem.len()];
+    let blob = signed_message(cipher_kem, &chat.sender, &chat.recipient, chat.aead_nonce(), chat.slot, chat.msg_type, chat.sig_alg);
+    let signed = crypto::verify(&blob, sig, vk).is_ok()
+        || crypto::verify_prehash(&hashv(&[&blob]).to_bytes(), sig, vk).is_ok();
+    if !signed || proof.len() > MAX_PROOF_BYTES {
+        return Ok(false);
+    }
+
+    Ok([cipher, kem].iter().any(|segment| {
+        let (seed, inc) = derive_public_inputs(segment);
+        crypto::verify_stark_with_len(proof, seed, inc, crypto::CANONICAL_TRACE_LEN).is_ok()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+    use stark_pqc_verifier::state::SIG_ALG_SHA2_128S;
+    use slh_dsa::{onchain_sha2::SHA256_OID_DER, signature::{Keypair, Signer}, Sha2_128s, SigningKey};
+
+    /// Builds the whole message flow and checks PDAs, account metas and chaining.
+    #[test]
+    fn full_sequence_accounts_and_pdas() {
+        let (pid, sender, recipient, slot) = (PROGRAM_ID, Pubkey::new_unique(), Pubkey::new_unique(), 42u64);
+        let body = vec![7u8; 2 * MAX_CHUNK + 10];
+        let sig = vec![9u8; 7_856];
+
+        let (buf, _) = Pubkey::find_program_address(&[b"buf", sender.as_ref()], &pid);
+        let (sigbuf, _) = Pubkey::find_program_address(
+            &[b"sig", sender.as_ref(), recipient.as_ref(), &slot.to_le_bytes()], &pid);
+        let (chat, _) = Pubkey::find_program_address(
+            &[b"msg", sender.as_ref(), recipient.as_ref(), &slot.to_le_bytes()], &pid);
+
+        let init = build_init_buffer_ix(&pid, &sender);
+        assert_eq!(init.accounts[0].pubkey, buf);
+        assert!(init.accounts[1].is_signer && init.accounts[1].is_writable);
+        let reset = build_reset_message_ix(&pid, &sender);
+        assert!(reset.accounts[0].pubkey == buf && reset.accounts[0].is_writable);
+        assert!(reset.accounts[1].is_signer && !reset.accounts[1].is_writable);
+        assert_ne!(reset.data, init.data);
+
+        let bodies = build_upload_body_ixs(&pid, &sender, &body);
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies.iter().all(|i| i.accounts[0].pubkey == buf && i.accounts[1].is_signer));
+
+        let sigs = build_upload_signature_ixs(&pid, &sender, &recipient, slot, &sig);
+        assert_eq!(sigs.len(), 7_856usize.div_ceil(MAX_CHUNK));
+        assert_eq!(sigs[0].accounts[0].pubkey, sigbuf);
+        assert_eq!(sigs[0].accounts[3].pubkey, chat);
+        assert!(!sigs[0].accounts[3].is_writable);
+
+        let fin = build_finalize_sig_ix(&pid, &sender, &recipient, 10, 1088, &[1u8; 12], slot, [2u8; 32], 0, SIG_ALG_SHA2_128S);
+        let keys: Vec<Pubkey> = fin.accounts.iter().map(|m| m.pubkey).collect();
+        assert_eq!(keys[..3], [buf, sigbuf, chat]);
+        assert_eq!(keys[3], nonce_pda(&pid, &sender));
+        assert_eq!(keys[6], system_program::ID);
+        let (pqid, _) = Pubkey::find_program_address(&[b"pqid", sender.as_ref()], &pid);
+        assert_eq!(keys[7], pqid);
+        assert_eq!((fin.accounts[8].pubkey, fin.accounts[9].pubkey), (pid, pid));
+        let relayer = Pubkey::new_unique();
+        let fin_rel = build_finalize_sig_attested_ix(&pid, &sender, &recipient, 10, 1088, &[1u8; 12], slot, [2u8; 32], 0, SIG_ALG_SHA2_128S, &relayer);
+        let (config, _) = Pubkey::find_program_address(&[b"config"], &pid);
+        assert_eq!(fin_rel.accounts[8].pubkey, config);
+        assert!(fin_rel.accounts[9].pubkey == relayer && fin_rel.accounts[9].is_signer);
+        assert_eq!(fin_rel.data, fin.data);
+
+        let rot = build_rotate_pq_key_ix(&pid, &sender, [3u8; 32]);
+        assert_eq!(rot.accounts[0].pubkey, pqid);
+        assert!(rot.accounts[1].is_signer && !rot.accounts[1].is_writable);
+
+        let exists = build_message_exists_ix(&pid, &sender, &recipient, slot);
+        assert_eq!(exists.accounts.len(), 1);
+        assert_eq!(exists.accounts[0].pubkey, chat);
+        assert!(!exists.accounts[0].is_writable && !exists.accounts[0].is_signer);
+
+        let aud = build_audit_chatmsg_ix(&pid, &chat);
+        assert_eq!(aud.accounts, vec![AccountMeta::new_readonly(chat, false)]);
+
+        let ver = build_verify_stark_ix(&pid, &chat, InputSource::Cipher, 256 * 1024);
+        assert_eq!(ver.accounts, vec![
+            AccountMeta::new_readonly(chat, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+        ]);
+    }
+
+    /// conversation_pdas lists the ChatMsg addresses finalize_sig creates for each slot, in slot order.
+    #[test]
+    fn conversation_pdas_match_finalize() {
+        let (pid, sender, recipient) = (PROGRAM_ID, Pubkey::new_unique(), Pubkey::new_unique());
+        let slots = [42u64, 7, u64::MAX];
+        let pdas = conversation_pdas(&pid, &sender, &recipient, &slots);
+        assert_eq!(pdas.len(), slots.len());
+        for (slot, pda) in slots.iter().zip(&pdas) {
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"msg", sender.as_ref(), recipient.as_ref(), &slot.to_le_bytes()], &pid);
+            assert_eq!(*pda, expected);
+            let fin = build_finalize_sig_ix(&pid, &sender, &recipient, 10, 1088, &[1u8; 12], *slot, [2u8; 32], 0, SIG_ALG_SHA2_128S);
+            assert_eq!(fin.accounts[2].pubkey, *pda);
+            assert_eq!(build_message_exists_ix(&pid, &sender, &recipient, *slot).accounts[0].pubkey, *pda);
+        }
+
+        // The direction of the conversation is part of the seeds.
+        assert_ne!(conversation_pdas(&pid, &recipient, &sender, &slots), pdas);
+        assert!(conversation_pdas(&pid, &sender, &recipient, &[]).is_empty());
+    }
+
+    /// The shared estimate's body count matches the builders' output.
+    #[test]
+    fn tx_estimate_matches_builders() {
+        let (pid, sender) = (PROGRAM_ID, Pubkey::new_unique());
+        for len in [1, 2_500, 10_017] {
+            let body = vec![0u8; len];
+            assert_eq!(estimate_tx_count(len, 0).body_txs, build_upload_body_ixs(&pid, &sender, &body).len());
+        }
+    }
+
+    /// The last chunk's hash equals the chain folded over the whole body.
+    #[test]
+    fn upload_chain_matches_fold() {
+        let body: Vec<u8> = (0..2_000u32).map(|i| i as u8).collect();
+        let folded = fold_chain(&body.chunks(MAX_CHUNK).collect::<Vec<_>>());
+        let last = chained_chunks(&body).pop().unwrap();
+        assert_eq!(last.0 as usize, 2 * MAX_CHUNK);
+        assert_eq!(last.2, folded);
+    }
+
+    /// Rent is priced on the same space Anchor serializes a ChatMsg into.
+    #[test]
+    fn chat_rent_matches_serialized_space() {
+        let (data, _vk, _sig) = signed_account();
+        let payload_len = ChatMsg::try_deserialize(&mut &data[..]).unwrap().payload.len();
+        assert_eq!(chat_account_space(payload_len), data.len());
+
+        let rent = Rent::default();
+        assert_eq!(chat_rent_lamports(payload_len, &rent), rent.minimum_balance(data.len()));
+        assert!(rent.is_exempt(chat_rent_lamports(payload_len, &rent), data.len()));
+        assert!(chat_rent_lamports(payload_len + 1, &rent) > chat_rent_lamports(payload_len, &rent));
+        assert_eq!(chat_account_space(stark_pqc_verifier::state::MAX_CHAT_PAYLOAD), 10_240);
+    }
+
+    /// Builds ChatMsg account bytes the way finalize_sig would, plus the key and signature.
+    fn signed_account() -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        signed_account_with_type(0)
+    }
+
+    fn signed_account_with_type(msg_type: u16) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        signed_account_with(msg_type, &[5u8; 12], false)
+    }
+
+    fn signed_account_with(msg_type: u16, nonce: &[u8], prehash: bool) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let sk = SigningKey::<Sha2_128s>::slh_keygen_internal(&[1u8; 16], &[2u8; 16], &[3u8; 16]);
+        let (cipher, kem, slot) = (b"hello pq".to_vec(), vec![0x42u8; 1088], 77u64);
+        let (_params, proof) = stark_prover::generate_proof_for_segment(&cipher).unwrap();
+        let cipher_kem = [&cipher[..], &kem[..]].concat();
+        let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let blob = signed_message(&cipher_kem, &sender, &recipient, nonce, slot, msg_type, SIG_ALG_SHA2_128S);
+        let sig = if prehash {
+            let digest = hashv(&[&blob]).to_bytes();
+            sk.slh_sign_internal(&[&[1u8, 0][..], &SHA256_OID_DER, &digest], None).to_vec()
+        } else {
+            sk.sign(&blob).to_vec()
+        };
+        let mut chat = ChatMsg {
+            sender,
+            recipient,
+            cipher_len: cipher.len() as u32,
+            kem_len: kem.len() as u32,
+            nonce_len: 0,
+            nonce: [0u8; 24],
+            slot,
+            sig_pda: Pubkey::new_unique(),
+            sig_len: sig.len() as u32,
+            sig_hash: hashv(&[&sig]).to_bytes(),
+            msg_type,
+            shard_count: 0,
+            shard_root: [0u8; 32],
+            relayer_attested: false,
+            sig_alg: SIG_ALG_SHA2_128S,
+            payload: [cipher_kem, proof].concat(),
+        };
+        chat.set_nonce(nonce);
+        let mut data = Vec::new();
+        chat.try_serialize(&mut data).unwrap();
+        (data, sk.verifying_key().to_vec(), sig)
+    }
+
+    /// A finalized account verifies; tampering with the cipher or the proof makes it fail.
+    #[test]
+    fn verify_chat_account_good_and_tampered() {
+        let (data, vk, sig) = signed_account();
+        assert!(verify_chat_account(&data, &vk, &sig).unwrap());
+        let audit = audit_chat_msg(&ChatMsg::try_deserialize(&mut &data[..]).unwrap());
+        assert_eq!(audit.issue, None);
+
+        // Layout: disc(8) + sender(32) + recipient(32) + cipher_len(4) + kem_len(4) + nonce_len(1) + nonce(24)
+        // + slot(8) + sig_pda(32) + sig_len(4) + sig_hash(32) + msg_type(2) + shard_count(2) + shard_root(32)
+        // + relayer_attested(1) + sig_alg(1) + vec_len(4) = 223 bytes before the payload.
+        let mut cipher_flip = data.clone();
+        cipher_flip[223] ^= 1;
+        assert!(!verify_chat_account(&cipher_flip, &vk, &sig).unwrap());
+
+        // The recipient (bytes 40..72) is signed, so re-addressing the account breaks the signature.
+        let mut readdressed = data.clone();
+        readdressed[40] ^= 1;
+        assert!(!verify_chat_account(&readdressed, &vk, &sig).unwrap());
+
+        let mut proof_flip = data.clone();
+        let last = proof_flip.len() - 1;
+        proof_flip[last] ^= 1;
+        assert!(!verify_chat_account(&proof_flip, &vk, &sig).unwrap());
+
+        assert!(!verify_chat_account(&data, &vk, &sig[1..]).unwrap());
+        assert!(verify_chat_account(&data[8..], &vk, &sig).is_err());
+    }
+
+    /// verify_sig_hash is plain SHA-256 of the signature, and accepts exactly the signature a ChatMsg recorded.
+    #[test]
+    fn sig_hash_recomputed_off_chain() {
+        // SHA-256 of bytes 0, 1, ..., 255, 0, ... (7,856 bytes), computed independently.
+        let pattern: Vec<u8> = (0..7_856u32).map(|i| i as u8).collect();
+        let known = [
+            0xf5, 0xea, 0x03, 0xcb, 0x57, 0xb0, 0x06, 0x4f, 0xf8, 0x24, 0x32, 0x98, 0x28, 0xf0, 0x5a, 0xe8,
+            0x0c, 0x98, 0x19, 0xb3, 0x80, 0xd1, 0xc6, 0xd2, 0xb9, 0x7d, 0x15, 0x24, 0xe8, 0x0f, 0xbe, 0x20,
+        ];
+        assert!(verify_sig_hash(&pattern, known));
+
+        let (data, _vk, sig) = signed_account();
+        let stored = ChatMsg::try_deserialize(&mut &data[..]).unwrap().sig_hash;
+        assert!(verify_sig_hash(&sig, stored));
+
+        let mut flipped = sig.clone();
+        flipped[0] ^= 1;
+        assert!(!verify_sig_hash(&flipped, stored));
+        assert!(!verify_sig_hash(&sig[..sig.len() - 1], stored));
+        assert!(!verify_sig_hash(&pattern, stored));
+    }
+
+    /// msg_type is stored in the account and covered by the signature: rewriting it fails verification.
+    #[test]
+    fn msg_type_stored_and_signed() {
+        let (data, vk, sig) = signed_account_with_type(7);
+        assert_eq!(ChatMsg::try_deserialize(&mut &data[..]).unwrap().msg_type, 7);
+        assert!(verify_chat_account(&data, &vk, &sig).unwrap());
+
+        // msg_type sits at 181..183, right before shard_count and shard_root.
+        let mut retyped = data.clone();
+        retyped[181..183].copy_from_slice(&8u16.to_le_bytes());
+        assert_eq!(ChatMsg::try_deserialize(&mut &retyped[..]).unwrap().msg_type, 8);
+        assert!(!verify_chat_account(&retyped, &vk, &sig).unwrap());
+    }
+
+    /// sig_alg is stored in the account and covered by the signature; a set the reader cannot verify is an error.
+    #[test]
+    fn sig_alg_stored_and_signed() {
+        let (data, vk, sig) = signed_account();
+        assert_eq!(ChatMsg::try_deserialize(&mut &data[..]).unwrap().sig_alg, SIG_ALG_SHA2_128S);
+        // sig_alg sits at 218, between relayer_attested and the payload's length prefix.
+        assert_eq!(data[218], SIG_ALG_SHA2_128S);
+
+        let mut relabeled = data.clone();
+        relabeled[218] = 2;
+        assert_eq!(ChatMsg::try_deserialize(&mut &relabeled[..]).unwrap().sig_alg, 2);
+        assert_eq!(verify_chat_account(&relabeled, &vk, &sig).unwrap_err(), ErrorCode::SigAlgUnsupported.into());
+
+        // The signed blob ends with sig_alg, so the same signature does not cover another set id.
+        let chat = ChatMsg::try_deserialize(&mut &data[..]).unwrap();
+        let cipher_kem = &chat.payload[..chat.cipher_len as usize + chat.kem_len as usize];
+        let blob = signed_message(cipher_kem, &chat.sender, &chat.recipient, chat.aead_nonce(), chat.slot, chat.msg_type, chat.sig_alg);
+        assert!(crypto::verify(&blob, &sig, &vk).is_ok());
+        let other = signed_message(cipher_kem, &chat.sender, &chat.recipient, chat.aead_nonce(), chat.slot, chat.msg_type, 2);
+        assert!(crypto::verify(&other, &sig, &vk).is_err());
+    }
+
+    /// A 24-byte nonce round-trips through the account and every byte of it is authenticated.
+    #[test]
+    fn extended_nonce_stored_and_signed() {
+        let nonce: Vec<u8> = (1..=24).collect();
+        let (data, vk, sig) = signed_account_with(0, &nonce, false);
+        let chat = ChatMsg::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!((chat.nonce_len, chat.aead_nonce()), (24, &nonce[..]));
+        assert_eq!(data[80], 24);
+        assert!(verify_chat_account(&data, &vk, &sig).unwrap());
+
+        // nonce occupies 81..105; its last byte and the length byte are both covered.
+        let mut renonced = data.clone();
+        renonced[104] ^= 1;
+        assert!(!verify_chat_account(&renonced, &vk, &sig).unwrap());
+        let mut shortened = data.clone();
+        shortened[80] = 12;
+        assert!(!verify_chat_account(&shortened, &vk, &sig).unwrap());
+    }
+
+    /// An account finalized with finalize_sig_prehash verifies; its signature does not pass as pure.
+    #[test]
+    fn prehash_account_verifies() {
+        let (data, vk, sig) = signed_account_with(0, &[5u8; 12], true);
+        assert!(verify_chat_account(&data, &vk, &sig).unwrap());
+        let chat = ChatMsg::try_deserialize(&mut &data[..]).unwrap();
+        let cipher_kem = &chat.payload[..chat.cipher_len as usize + chat.kem_len as usize];
+        let blob = signed_message(cipher_kem, &chat.sender, &chat.recipient, chat.aead_nonce(), chat.slot, 0, SIG_ALG_SHA2_128S);
+        assert!(crypto::verify(&blob, &sig, &vk).is_err());
+
+        let mut cipher_flip = data.clone();
+        cipher_flip[223] ^= 1;
+        assert!(!verify_chat_account(&cipher_flip, &vk, &sig).unwrap());
+
+        let pid = PROGRAM_ID;
+        let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pre = build_finalize_sig_prehash_ix(&pid, &sender, &recipient, 8, 1088, &[5u8; 12], 77, [2u8; 32], 0, SIG_ALG_SHA2_128S);
+        let pure = build_finalize_sig_ix(&pid, &sender, &recipient, 8, 1088, &[5u8; 12], 77, [2u8; 32], 0, SIG_ALG_SHA2_128S);
+        assert_eq!(pre.accounts, pure.accounts);
+        assert_eq!(pre.data[8..], pure.data[8..]);
+        assert_ne!(pre.data[..8], pure.data[..8]);
+    }
+
+    /// Sharded flow: finalize drops the body buffer, store_shard targets the indexed PDA, verify_stark lists every shard.
+    #[test]
+    fn sharded_flow_accounts() {
+        let (pid, sender, recipient, slot) = (PROGRAM_ID, Pubkey::new_unique(), Pubkey::new_unique(), 9u64);
+        let chat = msg_pda(&pid, &sender, &recipient, slot);
+        let parts = [vec![1u8; 10_017], vec![2u8; 10_017], vec![3u8; 4_964]];
+        let leaves: Vec<[u8; 32]> = parts.iter().enumerate().map(|(i, p)| shard_leaf(i as u16, p)).collect();
+        let root = shards_root(&leaves);
+
+        let fin = build_finalize_sig_sharded_ix(&pid, &sender, &recipient, 16_000, 1_088, &[1u8; 12], slot, [2u8; 32], 0, SIG_ALG_SHA2_128S, 3, root);
+        let single = build_finalize_sig_ix(&pid, &sender, &recipient, 16_000, 1_088, &[1u8; 12], slot, [2u8; 32], 0, SIG_ALG_SHA2_128S);
+        assert_eq!(fin.accounts[..], single.accounts[1..8]);
+
+        let store = build_store_shard_ix(&pid, &sender, &recipient, slot, 2, shard_path(&leaves, 2));
+        let (shard2, _) = Pubkey::find_program_address(&[b"shard", chat.as_ref(), &2u16.to_le_bytes()], &pid);
+        assert_eq!(store.accounts[0].pubkey, buffer_pda(&pid, &sender));
+        assert_eq!((store.accounts[1].pubkey, store.accounts[1].is_writable), (chat, false));
+        assert_eq!(store.accounts[2].pubkey, shard2);
+
+        let ver = build_verify_stark_sharded_ix(&pid, &chat, 3, InputSource::Cipher, 256 * 1024);
+        let keys: Vec<Pubkey> = ver.accounts.iter().map(|m| m.pubkey).collect();
+        assert_eq!(keys, [chat, sysvar::instructions::ID, shard_pda(&pid, &chat, 0), shard_pda(&pid, &chat, 1), shard2]);
+        assert!(ver.accounts.iter().all(|m| !m.is_writable));
+    }
+
+    /// Multi-signer flow: finalize_sig's accounts up to identity, then the CoSigners record, then each co-signer's
+    /// identity and sig PDA.
+    #[test]
+    fn multi_flow_accounts() {
+        let (pid, sender, recipient, slot) = (PROGRAM_ID, Pubkey::new_unique(), Pubkey::new_unique(), 9u64);
+        let cosigner = Pubkey::new_unique();
+        let multi = build_finalize_sig_multi_ix(
+            &pid, &sender, &recipient, 10, 1088, &[1u8; 12], slot, vec![[2u8; 32], [3u8; 32]], 0, SIG_ALG_SHA2_128S, &[cosigner],
+        );
+        let single = build_finalize_sig_ix(&pid, &sender, &recipient, 10, 1088, &[1u8; 12], slot, [2u8; 32], 0, SIG_ALG_SHA2_128S);
+        assert_eq!(multi.accounts[..8], single.accounts[..8]);
+        let (cosigners, _) = Pubkey::find_program_address(&[b"cosig", msg_pda(&pid, &sender, &recipient, slot).as_ref()], &pid);
+        assert_eq!((multi.accounts[8].pubkey, multi.accounts[8].is_writable), (cosigners, true));
+        let (cosig_id, _) = Pubkey::find_program_address(&[b"pqid", cosigner.as_ref()], &pid);
+        let (cosig_buf, _) = Pubkey::find_program_address(&[b"sig", cosigner.as_ref(), recipient.as_ref(), &slot.to_le_bytes()], &pid);
+        let rest: Vec<_> = multi.accounts[9..].iter().map(|m| (m.pubkey, m.is_writable)).collect();
+        assert_eq!(rest, [(cosig_id, false), (cosig_buf, false)]);
+    }
+}
@@ -0,0 +1,153 @@
+//! Bounded LRU of STARK verification results for relayers and indexers.
+//! A message's cipher digest fixes (seed, inc); the proof bytes are hashed into the key as well,
+//! so a cached verdict never vouches for a different proof over the same cipher.
+
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+
+/// Verdicts of past verify_proof calls, evicting the least recently used once `capacity` is reached
+#[derive(Clone, Debug)]
+pub struct VerifiedCache {
+    capacity: usize,
+    verdicts: HashMap<[u8; 32], bool>,
+    order: VecDeque<[u8; 32]>, // Front is least recently used
+    hits: u64,
+    misses: u64,
+}
+
+impl VerifiedCache {
+    /// Empty cache holding at most `capacity` verdicts; capacity 0 caches nothing
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, verdicts: HashMap::new(), order: VecDeque::new(), hits: 0, misses: 0 }
+    }
+
+    /// Cache key: SHA-256(seed_le || inc_le || proof)
+    pub fn key(proof_blob: &[u8], seed_u64: u64, inc_u64: u64) -> [u8; 32] {
+        let mut h = Sha256::new();
+        h.update(seed_u64.to_le_bytes());
+        h.update(inc_u64.to_le_bytes());
+        h.update(proof_blob);
+        h.finalize().into()
+    }
+
+    /// Cached verdict for the key, marking it most recently used
+    pub fn get(&mut self, key: &[u8; 32]) -> Option<bool> {
+        let verdict = self.verdicts.get(key).copied();
+        match verdict {
+            Some(_) => {
+                self.hits += 1;
+                self.touch(key);
+            }
+            None => self.misses += 1,
+        }
+        verdict
+    }
+
+    /// Records a verdict, evicting the least recently used one when full
+    pub fn insert(&mut self, key: [u8; 32], verified: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.verdicts.insert(key, verified).is_some() {
+            self.touch(&key);
+            return;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(old) = self.order.pop_front() {
+                self.verdicts.remove(&old);
+            }
+        }
+        self.order.push_back(key);
+    }
+
+    /// Number of cached verdicts
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// True when nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups that had to verify
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    // Linear in the capacity; relayer caches are small enough that a linked map is not worth a dependency
+    fn touch(&mut self, key: &[u8; 32]) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            let k = self.order.remove(pos).expect("position is in range");
+            self.order.push_back(k);
+        }
+    }
+}
+
+/// verify_proof with a verdict cache: a repeated (proof, seed, inc) returns the stored result without verifying
+/// Rejections are cached too, so a bad proof resent within the cache's lifetime fails without another verification
+pub fn verify_proof_cached(proof_blob: &[u8], seed_u64: u64, inc_u64: u64, cache: &mut VerifiedCache) -> anyhow::Result<()> {
+    let key = VerifiedCache::key(proof_blob, seed_u64, inc_u64);
+    let verified = match cache.get(&key) {
+        Some(verified) => verified,
+        None => {
+            let res = crate::verify_proof(proof_blob, seed_u64, inc_u64);
+            cache.insert(key, res.is_ok());
+            res?;
+            true
+        }
+    };
+    anyhow::ensure!(verified, "proof rejected (cached)");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_proof_for_segment, public_inputs_from_digest};
+
+    /// The second check of the same proof is a hit; another cipher's proof misses and verifies on its own
+    #[test]
+    fn repeat_hits_and_new_digest_misses() {
+        let mut cache = VerifiedCache::new(4);
+        let (_params, proof) = generate_proof_for_segment(b"cached cipher").unwrap();
+        let (seed, inc) = public_inputs_from_digest(&Sha256::digest(b"cached cipher")).unwrap();
+
+        verify_proof_cached(&proof, seed, inc, &mut cache).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+        verify_proof_cached(&proof, seed, inc, &mut cache).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        let (_params, other) = generate_proof_for_segment(b"other cipher").unwrap();
+        let (oseed, oinc) = public_inputs_from_digest(&Sha256::digest(b"other cipher")).unwrap();
+        verify_proof_cached(&other, oseed, oinc, &mut cache).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+
+        // Same cipher, other proof bytes: a miss, and the rejection is cached
+        assert!(verify_proof_cached(&other, seed, inc, &mut cache).is_err());
+        assert!(verify_proof_cached(&other, seed, inc, &mut cache).is_err());
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (2, 3, 3));
+    }
+
+    /// A full cache evicts the least recently used verdict, and a lookup refreshes recency
+    #[test]
+    fn lru_eviction() {
+        let mut cache = VerifiedCache::new(2);
+        let [a, b, c] = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        cache.insert(a, true);
+        cache.insert(b, false);
+        assert_eq!(cache.get(&a), Some(true));
+        cache.insert(c, true);
+        assert_eq!((cache.get(&b), cache.get(&a), cache.get(&c)), (None, Some(true), Some(true)));
+        assert_eq!(cache.len(), 2);
+
+        let mut off = VerifiedCache::new(0);
+        off.insert(a, true);
+        assert!(off.is_empty() && off.get(&a).is_none());
+    }
+}
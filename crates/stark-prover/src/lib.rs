@@ -1,130 +1,396 @@
 //! Minimal STARK prover for the demo.
 //! Derives seed and inc from the first 16 bytes of SHA256 of the cipher and proves the affine counter x_{t+1} = x_t + inc.
 //! Uses Sha2_256 and Winterfell 0.12, uses trace length 8, and uses FRI options that target about 128 bit security.
+//! Prefixes the serialized proof with a one byte hash id so the verifier can dispatch on the hasher.
+//! The AIR, public inputs and hasher aliases come from crates/stark-air, shared with the program.
 
 use winterfell::{
-    crypto::{hashers::Sha2_256, DefaultRandomCoin, MerkleTree},
-    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    math::{fields::f128::BaseElement, FieldElement},
     matrix::ColMatrix,
-    Air, AirContext, Assertion, AuxRandElements, BatchingMethod, CompositionPoly,
-    CompositionPolyTrace, ConstraintCompositionCoefficients, DefaultConstraintCommitment,
-    DefaultConstraintEvaluator, DefaultTraceLde, EvaluationFrame, FieldExtension,
-    PartitionOptions, ProofOptions, Prover, StarkDomain, TraceInfo, TraceTable,
-    TransitionConstraintDegree,
+    AcceptableOptions, AuxRandElements, BatchingMethod, CompositionPoly, CompositionPolyTrace,
+    ConstraintCompositionCoefficients, DefaultConstraintCommitment, DefaultConstraintEvaluator,
+    DefaultTraceLde, FieldExtension, PartitionOptions, Proof, ProofOptions, Prover, StarkDomain,
+    TraceInfo, TraceTable,
 };
-use winter_utils::Serializable;
+use stark_air::{MessageAir, PublicInputs, H, RC, VC};
+use winter_utils::{Deserializable, Serializable};
+use sha2::{Digest, Sha256};
+use serde::Serialize;
+use std::time::{Duration, Instant};
 
-/// Carries public inputs seed and inc derived from SHA256
-#[derive(Clone, Copy)]
-pub(crate) struct PublicInputs {
-    pub seed: BaseElement,
-    pub inc: BaseElement,
+mod cache;
+pub use cache::{verify_proof_cached, VerifiedCache};
+
+/// Hash id byte for proofs built with Sha2_256, matching the on-chain verifier
+pub const HASH_ID_SHA2_256: u8 = 0x01;
+
+/// Tunable prover options; Default reproduces the demo configuration
+#[derive(Clone, Copy, Debug)]
+pub struct ProverOptions {
+    /// FRI folding factor, one of 2, 4, 8, 16
+    /// Larger factors mean fewer FRI layers (smaller proofs, fewer Merkle paths)
+    /// but wider per-query interpolation on the verifier; with trace length 8 the
+    /// remainder already fits, so it only changes proof size once traces grow
+    pub folding_factor: usize,
+    /// Trace length, a power of two of at least 8
+    /// The on-chain verifier pins proofs to CANONICAL_TRACE_LEN (8) and refuses other lengths with ProofFailed;
+    /// longer traces are for host-side size and CU probing with a test build that raises that constant
+    pub trace_len: usize,
+    /// Proof of work bits on the FRI transcript, 0..=32
+    /// Conjectured security is min(128, 30 queries * log2(16) + grinding) - 1, so with these
+    /// options the on-chain MinConjecturedSecurity(127) only accepts grinding >= 8; each extra
+    /// bit doubles prover grinding time and costs the verifier a single hash
+    pub grinding_factor: u32,
 }
-impl ToElements<BaseElement> for PublicInputs {
-    fn to_elements(&self) -> Vec<BaseElement> {
-        vec![self.seed, self.inc]
+
+impl Default for ProverOptions {
+    fn default() -> Self {
+        Self { folding_factor: 4, trace_len: 8, grinding_factor: 8 }
     }
 }
 
-/// Implements the affine counter AIR with two boundary assertions
-pub(crate) struct MessageAir {
-    ctx: AirContext<BaseElement>,
-    pi: PublicInputs,
+/// Shortest trace Winterfell proves; lengths must also be powers of two
+pub const MIN_TRACE_LEN: usize = TraceInfo::MIN_TRACE_LENGTH;
+
+/// Largest grinding factor Winterfell accepts
+pub const MAX_GRINDING_FACTOR: u32 = 32;
+
+/// Folding factors supported by Winterfell FRI
+pub const FOLDING_FACTORS: [usize; 4] = [2, 4, 8, 16];
+
+/// Winterfell ProofOptions for the given prover options: 30 queries, blowup 16, no field extension
+fn proof_options(opts: &ProverOptions) -> ProofOptions {
+    ProofOptions::new(
+        30,
+        16,
+        opts.grinding_factor,
+        FieldExtension::None,
+        opts.folding_factor,
+        31,
+        BatchingMethod::Linear,
+        BatchingMethod::Linear,
+    )
 }
-impl MessageAir {
-    fn new(info: TraceInfo, pi: PublicInputs, opts: ProofOptions) -> Self {
-        let degrees = vec![TransitionConstraintDegree::new(1)];
-        let ctx = AirContext::new(info, degrees, 2, opts);
-        eprintln!("[AIR] prover:new degs=1 assertions=2");
-        Self { ctx, pi }
-    }
+
+/// FRI and query parameters of a proof profile
+/// Conjectured security with the f128 field and Sha2_256 is min(128, num_queries * log2(blowup_factor) + grinding_factor) - 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProfileParams {
+    /// FRI queries, 1..=255; each adds log2(blowup_factor) bits and one Merkle path per layer to the proof
+    pub num_queries: usize,
+    /// LDE blowup, a power of two in 2..=128; larger means fewer queries for the same security but a slower prover
+    pub blowup_factor: usize,
+    /// Proof of work bits on the FRI transcript, 0..=32
+    pub grinding_factor: u32,
+    /// FRI folding factor, one of 2, 4, 8, 16
+    pub folding_factor: usize,
 }
-impl Air for MessageAir {
-    type BaseField = BaseElement;
-    type PublicInputs = PublicInputs;
 
-    fn new(info: TraceInfo, pi: PublicInputs, opts: ProofOptions) -> Self {
-        Self::new(info, pi, opts)
+impl ProfileParams {
+    /// Conjectured security in bits, as Winterfell computes it for this AIR
+    /// Parameters that fail validate() cannot produce a proof, so they report 0 instead of panicking
+    pub fn conjectured_security(&self) -> u32 {
+        if self.validate().is_err() {
+            return 0;
+        }
+        let query_bits = self.num_queries as u32 * self.blowup_factor.ilog2() + self.grinding_factor;
+        query_bits.min(128) - 1
     }
-    fn context(&self) -> &AirContext<Self::BaseField> { &self.ctx }
 
-    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
-        &self,
-        frame: &EvaluationFrame<E>,
-        _periodic: &[E],
-        result: &mut [E],
-    ) {
-        let inc = E::from(self.pi.inc);
-        result[0] = frame.next()[0] - frame.current()[0] - inc;
-    }
-
-    fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
-        let last = self.trace_length() - 1;
-        let asr = vec![
-            Assertion::single(0, 0, self.pi.seed),
-            Assertion::single(
-                0,
-                last,
-                self.pi.seed + (self.pi.inc * BaseElement::from(last as u64)),
-            ),
-        ];
-        eprintln!("[AIR] prover:get_assertions len={} last={}", asr.len(), last);
-        asr
-    }
-}
-
-/// Generates params and proof from sha256 bytes of the cipher
-/// Uses bytes 0..8 for seed and 8..16 for inc in little endian; falls back if shorter
-pub fn generate_proof(hash_bytes: &[u8]) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
-    let trace_len = 8usize;
-
-    let (seed_u64, inc_u64) = if hash_bytes.len() >= 16 {
-        let mut le0 = [0u8; 8];
-        let mut le1 = [0u8; 8];
-        le0.copy_from_slice(&hash_bytes[0..8]);
-        le1.copy_from_slice(&hash_bytes[8..16]);
-        (u64::from_le_bytes(le0), u64::from_le_bytes(le1))
-    } else if hash_bytes.len() >= 8 {
-        let mut le0 = [0u8; 8];
-        le0.copy_from_slice(&hash_bytes[0..8]);
-        (u64::from_le_bytes(le0), 1u64)
-    } else {
-        (0u64, 1u64)
-    };
-    let seed = BaseElement::from(seed_u64);
-    let inc = BaseElement::from(inc_u64);
+    /// Rejects values ProofOptions::new would panic on
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!((1..=255).contains(&self.num_queries), "query count {} outside 1..=255", self.num_queries);
+        anyhow::ensure!(
+            self.blowup_factor.is_power_of_two() && (2..=128).contains(&self.blowup_factor),
+            "blowup factor {} must be a power of two in 2..=128",
+            self.blowup_factor
+        );
+        anyhow::ensure!(
+            self.grinding_factor <= MAX_GRINDING_FACTOR,
+            "grinding factor {} exceeds {}",
+            self.grinding_factor,
+            MAX_GRINDING_FACTOR
+        );
+        anyhow::ensure!(
+            FOLDING_FACTORS.contains(&self.folding_factor),
+            "unsupported folding factor {} (expected one of {:?})",
+            self.folding_factor,
+            FOLDING_FACTORS
+        );
+        Ok(())
+    }
+}
 
-    let mut trace = TraceTable::new(1, trace_len);
-    trace.fill(|state| state[0] = seed, |_step, state| state[0] = state[0] + inc);
-
-    let mut inc_violations = 0usize;
-    for i in 0..(trace_len - 1) {
-        let cur = trace.get(0, i);
-        let nxt = trace.get(0, i + 1);
-        if nxt != cur + inc {
-            inc_violations += 1;
+/// Named proof option profiles; every curated one reaches the on-chain MinConjecturedSecurity(127)
+/// All prove the canonical trace length 8, so their proofs verify with the program's pinned length
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// The demo configuration: 30 queries, blowup 16, grinding 8, folding 4; 127 bits
+    /// Same options as ProverOptions::default(), so proofs and params match generate_proof
+    Demo128,
+    /// Faster proving: 40 queries, blowup 8, grinding 8, folding 4; 127 bits
+    /// Half the LDE of Demo128, paid for with a third more queries in the proof
+    Fast,
+    /// Smaller proofs: 24 queries, blowup 32, grinding 8, folding 8; 127 bits
+    /// A fifth fewer queries than Demo128, paid for with twice the LDE on the prover
+    Small,
+    /// Caller-chosen parameters for experiments; not curated and may fall below the on-chain floor
+    Custom(ProfileParams),
+}
+
+impl Profile {
+    /// The curated profiles, in declaration order
+    pub const CURATED: [Profile; 3] = [Profile::Demo128, Profile::Fast, Profile::Small];
+
+    /// Query, blowup, grinding and folding values of this profile
+    pub fn params(&self) -> ProfileParams {
+        match *self {
+            Profile::Demo128 => ProfileParams { num_queries: 30, blowup_factor: 16, grinding_factor: 8, folding_factor: 4 },
+            Profile::Fast => ProfileParams { num_queries: 40, blowup_factor: 8, grinding_factor: 8, folding_factor: 4 },
+            Profile::Small => ProfileParams { num_queries: 24, blowup_factor: 32, grinding_factor: 8, folding_factor: 8 },
+            Profile::Custom(params) => params,
         }
     }
-    eprintln!("DBG Prover: inc_violations={}", inc_violations);
 
-    let options = ProofOptions::new(
-        30,
-        16,
-        8,
+    /// Conjectured security in bits; the program accepts 127 and above
+    pub fn conjectured_security(&self) -> u32 {
+        self.params().conjectured_security()
+    }
+}
+
+/// Winterfell ProofOptions for a profile, rejecting values ProofOptions::new would panic on
+fn profile_options(profile: &Profile) -> anyhow::Result<ProofOptions> {
+    let p = profile.params();
+    p.validate()?;
+    Ok(ProofOptions::new(
+        p.num_queries,
+        p.blowup_factor,
+        p.grinding_factor,
         FieldExtension::None,
-        4,
+        p.folding_factor,
         31,
         BatchingMethod::Linear,
         BatchingMethod::Linear,
+    ))
+}
+
+/// Serialized ProofOptions for ProverOptions::default(), the params every default proof returns
+/// Params depend only on the options, never on the digest, so they are constant for a fixed configuration
+/// The program ignores them (it checks AcceptableOptions and the options embedded in the proof) and never stores them;
+/// clients that need detached params can pin this value once instead of sending it with every message
+pub fn canonical_params() -> Vec<u8> {
+    proof_options(&ProverOptions::default()).to_bytes()
+}
+
+/// Generates params and proof from sha256 bytes of the cipher with the default options
+/// Proofs are reproducible: the same digest and options give byte-identical output. Every FRI and DEEP
+/// challenge comes from the public coin reseeded with the transcript, and the grinding nonce is the first
+/// one found by a sequential search. Winterfell's `concurrent` feature would search nonces in parallel and
+/// could pick a different one per run (still valid, different bytes); this crate does not enable it
+pub fn generate_proof(hash_bytes: &[u8]) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    generate_proof_with_options(hash_bytes, ProverOptions::default())
+}
+
+/// Derives (seed, inc) from sha256 bytes exactly as the on-chain verifier does
+/// Uses bytes 0..8 for seed and 8..16 for inc in little endian; shorter inputs are rejected
+pub fn public_inputs_from_digest(hash_bytes: &[u8]) -> anyhow::Result<(u64, u64)> {
+    anyhow::ensure!(
+        hash_bytes.len() >= 16,
+        "digest too short: {} bytes (need at least 16)",
+        hash_bytes.len()
     );
-    eprintln!("DBG Prover Options: queries=30 blowup=16 folding=4 grinding=8");
+    let mut le0 = [0u8; 8];
+    let mut le1 = [0u8; 8];
+    le0.copy_from_slice(&hash_bytes[0..8]);
+    le1.copy_from_slice(&hash_bytes[8..16]);
+    Ok((u64::from_le_bytes(le0), u64::from_le_bytes(le1)))
+}
+
+/// Generates params and proof from sha256 bytes of the cipher with explicit prover options
+pub fn generate_proof_with_options(
+    hash_bytes: &[u8],
+    opts: ProverOptions,
+) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    check_options(&opts)?;
+    prove_digest(hash_bytes, opts.trace_len, proof_options(&opts))
+}
+
+/// Proves arbitrary public inputs (seed, inc) with the default options, skipping the SHA-256 derivation
+/// For AIR-focused tests and edge cases (seed = 0, inc = u64::MAX, ...); on-chain proofs must come from a digest
+pub fn prove_with_inputs(seed: u64, inc: u64, trace_len: usize) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let opts = ProverOptions { trace_len, ..ProverOptions::default() };
+    check_options(&opts)?;
+    prove_inputs(seed, inc, trace_len, proof_options(&opts))
+}
+
+/// Rejects prover options Winterfell or the trace builder cannot handle
+fn check_options(opts: &ProverOptions) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        FOLDING_FACTORS.contains(&opts.folding_factor),
+        "unsupported folding factor {} (expected one of {:?})",
+        opts.folding_factor,
+        FOLDING_FACTORS
+    );
+    anyhow::ensure!(
+        opts.trace_len.is_power_of_two(),
+        "trace length {} is not a power of two (the FFT domains need one, e.g. 8, 16, 32)",
+        opts.trace_len
+    );
+    anyhow::ensure!(
+        opts.trace_len >= MIN_TRACE_LEN,
+        "trace length {} is below Winterfell's minimum of {}",
+        opts.trace_len,
+        MIN_TRACE_LEN
+    );
+    anyhow::ensure!(
+        opts.grinding_factor <= MAX_GRINDING_FACTOR,
+        "grinding factor {} exceeds {}",
+        opts.grinding_factor,
+        MAX_GRINDING_FACTOR
+    );
+    Ok(())
+}
+
+/// Generates params and proof from sha256 bytes of the cipher with a named profile at trace length 8
+/// Profile::Demo128 gives the same bytes as generate_proof
+pub fn generate_proof_profile(hash_bytes: &[u8], profile: Profile) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    prove_digest(hash_bytes, 8, profile_options(&profile)?)
+}
+
+/// Proves the affine counter for the digest's (seed, inc) and returns (params, hash_id || proof)
+fn prove_digest(hash_bytes: &[u8], trace_len: usize, options: ProofOptions) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let (seed_u64, inc_u64) = public_inputs_from_digest(hash_bytes)?;
+    prove_inputs(seed_u64, inc_u64, trace_len, options)
+}
+
+/// Proves the affine counter for (seed, inc) and returns (params, hash_id || proof)
+fn prove_inputs(seed_u64: u64, inc_u64: u64, trace_len: usize, options: ProofOptions) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let seed = BaseElement::from(seed_u64);
+    let inc = BaseElement::from(inc_u64);
+
+    let mut trace = TraceTable::new(1, trace_len);
+    trace.fill(|state| state[0] = seed, |_step, state| state[0] += inc);
 
     let proof = MessageProver { options: options.clone(), seed, inc }.prove(trace)?;
     let params = options.to_bytes();
-    let bytes = proof.to_bytes();
+    let mut bytes = Vec::with_capacity(1 + proof.get_size_hint());
+    bytes.push(HASH_ID_SHA2_256);
+    proof.write_into(&mut bytes);
     Ok((params, bytes))
 }
 
+/// Host mirror of the program's crypto::StarkVerifier
+/// Fixes <MessageAir, H, RC, VC>, the minimum conjectured security and an optional trace length pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StarkVerifier {
+    min_security_bits: u32,
+    expected_len: Option<usize>,
+}
+
+impl Default for StarkVerifier {
+    /// MinConjecturedSecurity(127) like programs/stark-pqc-verifier, any trace length
+    fn default() -> Self {
+        Self::new(127)
+    }
+}
+
+impl StarkVerifier {
+    /// Verifier accepting proofs with at least min_security_bits of conjectured security
+    pub fn new(min_security_bits: u32) -> Self {
+        Self { min_security_bits, expected_len: None }
+    }
+
+    /// Refuses proofs whose trace length is not len
+    pub fn with_trace_len(mut self, len: usize) -> Self {
+        self.expected_len = Some(len);
+        self
+    }
+
+    /// Verifies a hash id prefixed proof against (seed, inc)
+    pub fn verify(&self, proof_blob: &[u8], seed_u64: u64, inc_u64: u64) -> anyhow::Result<()> {
+        let proof = self.decode(proof_blob)?;
+        self.check(proof, seed_u64, inc_u64)
+    }
+
+    /// Like verify, and also reports how long each observable stage took
+    /// Stages that did not run because an earlier one failed stay zero
+    /// Reads the clock, so only call it where std::time::Instant works (not wasm32-unknown-unknown)
+    pub fn verify_timed(&self, proof_blob: &[u8], seed_u64: u64, inc_u64: u64) -> (anyhow::Result<()>, VerifyTimings) {
+        let mut t = VerifyTimings::default();
+        let start = Instant::now();
+        let res = self.decode(proof_blob).and_then(|proof| {
+            t.deserialize = start.elapsed();
+            let checked = Instant::now();
+            let res = self.check(proof, seed_u64, inc_u64);
+            t.verify = checked.elapsed();
+            res
+        });
+        t.total = start.elapsed();
+        (res, t)
+    }
+
+    // Checks the hash id and deserializes the proof
+    fn decode(&self, proof_blob: &[u8]) -> anyhow::Result<Proof> {
+        let (&hash_id, bytes) = proof_blob
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("empty proof"))?;
+        anyhow::ensure!(hash_id == HASH_ID_SHA2_256, "unsupported hash id 0x{hash_id:02x}");
+        Proof::from_bytes(bytes).map_err(|e| anyhow::anyhow!("deserialize: {e}"))
+    }
+
+    // Applies the trace length pin and runs winterfell::verify
+    fn check(&self, proof: Proof, seed_u64: u64, inc_u64: u64) -> anyhow::Result<()> {
+        let trace_len = proof.trace_info().length();
+        if let Some(n) = self.expected_len {
+            anyhow::ensure!(n == trace_len, "trace length {trace_len} (expected {n})");
+        }
+        let pi = PublicInputs { seed: BaseElement::from(seed_u64), inc: BaseElement::from(inc_u64) };
+        let opts = AcceptableOptions::MinConjecturedSecurity(self.min_security_bits);
+        winterfell::verify::<MessageAir, H, RC, VC>(proof, pi, &opts)
+            .map_err(|e| anyhow::anyhow!("verify: {e}"))
+    }
+}
+
+/// Wall-clock breakdown of one host verification
+/// Winterfell 0.12 only exposes a monolithic verify, so Merkle openings, constraint checks and FRI share one stage
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct VerifyTimings {
+    /// Hash id check and Proof::from_bytes
+    pub deserialize: Duration,
+    /// Trace length check and winterfell::verify: commitments, Merkle openings, constraint evaluation and FRI
+    pub verify: Duration,
+    /// Whole call
+    pub total: Duration,
+}
+
+/// Verifies a hash id prefixed proof on the host with the on-chain acceptance rule
+/// Uses MinConjecturedSecurity(127) like programs/stark-pqc-verifier
+pub fn verify_proof(proof_blob: &[u8], seed_u64: u64, inc_u64: u64) -> anyhow::Result<()> {
+    StarkVerifier::default().verify(proof_blob, seed_u64, inc_u64)
+}
+
+/// Verifies a detached (proof, params) pair as emitted by generate_proof
+/// params must decode to the ProofOptions embedded in the proof, then the proof is checked like verify_proof
+pub fn verify_proof_with_params(proof_blob: &[u8], params: &[u8], seed_u64: u64, inc_u64: u64) -> anyhow::Result<()> {
+    let expected = ProofOptions::read_from_bytes(params).map_err(|e| anyhow::anyhow!("params: {e}"))?;
+    let bytes = proof_blob.get(1..).ok_or_else(|| anyhow::anyhow!("empty proof"))?;
+    let proof = Proof::from_bytes(bytes).map_err(|e| anyhow::anyhow!("deserialize: {e}"))?;
+    anyhow::ensure!(proof.options() == &expected, "params do not match the proof options");
+    verify_proof(proof_blob, seed_u64, inc_u64)
+}
+
+/// verify_proof with a per-stage timing breakdown for off-chain resource measurements
+pub fn verify_proof_timed(proof_blob: &[u8], seed_u64: u64, inc_u64: u64) -> (anyhow::Result<()>, VerifyTimings) {
+    StarkVerifier::default().verify_timed(proof_blob, seed_u64, inc_u64)
+}
+
+/// Generates params and proof bound to SHA256(segment)
+/// Pass the cipher for the default binding or the KEM ciphertext for InputSource::Kem on chain
+pub fn generate_proof_for_segment(segment: &[u8]) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let digest = Sha256::digest(segment);
+    generate_proof(&digest)
+}
+
 /// Holds prover configuration and public inputs
 pub(crate) struct MessageProver {
     pub options: ProofOptions,
@@ -132,10 +398,6 @@ pub(crate) struct MessageProver {
     pub inc: BaseElement,
 }
 
-type H = Sha2_256<BaseElement>;
-type VC = MerkleTree<H>;
-type RC = DefaultRandomCoin<H>;
-
 impl Prover for MessageProver {
     type BaseField = BaseElement;
     type Air = MessageAir;
@@ -193,7 +455,7 @@ impl Prover for MessageProver {
 
 #[cfg(test)]
 mod tests {
-    use super::generate_proof;
+    use super::*;
     /// Checks that generate_proof returns non empty artifacts
     #[test]
     fn generate_proof_basic() {
@@ -202,4 +464,213 @@ mod tests {
         assert!(!params.is_empty());
         assert!(!proof.is_empty());
     }
+
+    /// Checks that the proof blob starts with the Sha2_256 hash id
+    #[test]
+    fn proof_has_hash_id_prefix() {
+        let (_params, proof) = generate_proof(&[7u8; 32]).unwrap();
+        assert_eq!(proof[0], HASH_ID_SHA2_256);
+    }
+
+    /// Proves with folding factor 8 and verifies with the host verifier
+    #[test]
+    fn folding_factor_8_verifies() {
+        let digest = [3u8; 32];
+        let opts = ProverOptions { folding_factor: 8, ..Default::default() };
+        let (_params, proof) = generate_proof_with_options(&digest, opts).unwrap();
+        let (seed, inc) = public_inputs_from_digest(&digest).unwrap();
+        verify_proof(&proof, seed, inc).unwrap();
+        assert!(verify_proof(&proof, seed, inc.wrapping_add(1)).is_err());
+    }
+
+    /// Rejects folding factors Winterfell does not support
+    #[test]
+    fn folding_factor_validated() {
+        let opts = ProverOptions { folding_factor: 3, ..Default::default() };
+        assert!(generate_proof_with_options(&[0u8; 32], opts).is_err());
+        let opts = ProverOptions { trace_len: 12, ..Default::default() };
+        assert!(generate_proof_with_options(&[0u8; 32], opts).is_err());
+        let opts = ProverOptions { grinding_factor: 33, ..Default::default() };
+        assert!(generate_proof_with_options(&[0u8; 32], opts).is_err());
+    }
+
+    /// Lengths Winterfell cannot prove fail up front with a message naming the problem, not a panic in the prover
+    #[test]
+    fn bad_trace_len_described() {
+        for len in [0usize, 3, 10, 12, 100] {
+            let opts = ProverOptions { trace_len: len, ..Default::default() };
+            let err = generate_proof_with_options(&[0u8; 32], opts).unwrap_err().to_string();
+            assert!(err.contains("not a power of two"), "{len}: {err}");
+            assert!(prove_with_inputs(1, 2, len).unwrap_err().to_string().contains("not a power of two"));
+        }
+        for len in [1usize, 2, 4] {
+            let opts = ProverOptions { trace_len: len, ..Default::default() };
+            let err = generate_proof_with_options(&[0u8; 32], opts).unwrap_err().to_string();
+            assert!(err.contains("below Winterfell's minimum of 8"), "{len}: {err}");
+        }
+        assert_eq!(MIN_TRACE_LEN, 8);
+    }
+
+    /// Short digests are refused instead of proving over zero-filled inputs
+    #[test]
+    fn short_digest_rejected() {
+        assert!(generate_proof(&[1u8; 4]).is_err());
+        assert!(generate_proof(&[1u8; 15]).is_err());
+        assert!(generate_proof(&[1u8; 16]).is_ok());
+    }
+
+    /// Length-64 traces prove and verify under the on-chain acceptance rule
+    #[test]
+    fn trace_len_64_verifies() {
+        let digest = [9u8; 32];
+        let opts = ProverOptions { trace_len: 64, ..Default::default() };
+        let (_params, proof) = generate_proof_with_options(&digest, opts).unwrap();
+        let (seed, inc) = public_inputs_from_digest(&digest).unwrap();
+        verify_proof(&proof, seed, inc).unwrap();
+    }
+
+    /// Grinding below 8 drops conjectured security under the 127 bit acceptance threshold
+    #[test]
+    fn low_grinding_rejected_by_threshold() {
+        let digest = [4u8; 32];
+        let (seed, inc) = public_inputs_from_digest(&digest).unwrap();
+        for (grinding, ok) in [(0, false), (7, false), (8, true)] {
+            let opts = ProverOptions { grinding_factor: grinding, ..Default::default() };
+            let (_params, proof) = generate_proof_with_options(&digest, opts).unwrap();
+            assert_eq!(verify_proof(&proof, seed, inc).is_ok(), ok, "grinding={grinding}");
+        }
+    }
+
+    /// A valid proof reports every stage; a rejected blob still returns its partial timings
+    #[test]
+    fn verify_timed_populates_stages() {
+        let digest = [6u8; 32];
+        let (_params, proof) = generate_proof(&digest).unwrap();
+        let (seed, inc) = public_inputs_from_digest(&digest).unwrap();
+        let (res, t) = verify_proof_timed(&proof, seed, inc);
+        res.unwrap();
+        assert!(t.deserialize > Duration::ZERO && t.verify > Duration::ZERO);
+        assert!(t.total >= t.deserialize + t.verify);
+
+        let (res, t) = verify_proof_timed(&proof, seed ^ 1, inc);
+        assert!(res.is_err());
+        assert!(t.verify > Duration::ZERO);
+        let (res, t) = verify_proof_timed(&[], seed, inc);
+        assert!(res.is_err());
+        assert_eq!((t.deserialize, t.verify), (Duration::ZERO, Duration::ZERO));
+    }
+
+    /// A detached pair verifies only with the params it was generated with
+    #[test]
+    fn params_must_match_proof() {
+        let digest = [2u8; 32];
+        let (seed, inc) = public_inputs_from_digest(&digest).unwrap();
+        let (params, proof) = generate_proof(&digest).unwrap();
+        verify_proof_with_params(&proof, &params, seed, inc).unwrap();
+
+        let (other, _) = generate_proof_with_options(&digest, ProverOptions { grinding_factor: 9, ..Default::default() }).unwrap();
+        assert!(verify_proof_with_params(&proof, &other, seed, inc).is_err());
+        assert!(verify_proof_with_params(&proof, &params[1..], seed, inc).is_err());
+    }
+
+    /// The same digest and options give byte-identical proofs; another digest does not
+    #[test]
+    fn proofs_are_reproducible() {
+        let digest = Sha256::digest(b"reproducible");
+        let (params_a, proof_a) = generate_proof(&digest).unwrap();
+        let (params_b, proof_b) = generate_proof(&digest).unwrap();
+        assert_eq!(params_a, params_b);
+        assert_eq!(proof_a, proof_b);
+
+        let opts = ProverOptions { trace_len: 64, grinding_factor: 12, ..Default::default() };
+        let a = generate_proof_with_options(&digest, opts).unwrap();
+        let b = generate_proof_with_options(&digest, opts).unwrap();
+        assert_eq!(a, b);
+
+        let (_params, other) = generate_proof(&Sha256::digest(b"other")).unwrap();
+        assert_ne!(other, proof_a);
+    }
+
+    /// Default params do not depend on the digest and equal canonical_params; other options differ
+    #[test]
+    fn params_are_canonical() {
+        let canonical = canonical_params();
+        for segment in [&b"a"[..], b"cipher bytes", &[0x42u8; 1088]] {
+            let (params, proof) = generate_proof_for_segment(segment).unwrap();
+            assert_eq!(params, canonical);
+            let (seed, inc) = public_inputs_from_digest(&Sha256::digest(segment)).unwrap();
+            verify_proof_with_params(&proof, &canonical, seed, inc).unwrap();
+        }
+        let opts = ProverOptions { grinding_factor: 9, ..ProverOptions::default() };
+        let (params, _proof) = generate_proof_with_options(&[3u8; 32], opts).unwrap();
+        assert_ne!(params, canonical);
+    }
+
+    /// Proving the digest's inputs directly gives the same bytes; the length is still validated
+    #[test]
+    fn prove_with_inputs_matches_digest_path() {
+        let digest = Sha256::digest(b"inputs");
+        let (seed, inc) = public_inputs_from_digest(&digest).unwrap();
+        assert_eq!(prove_with_inputs(seed, inc, 8).unwrap(), generate_proof(&digest).unwrap());
+        let (_params, proof) = prove_with_inputs(0, u64::MAX, 16).unwrap();
+        verify_proof(&proof, 0, u64::MAX).unwrap();
+        assert!(prove_with_inputs(seed, inc, 12).is_err());
+        assert!(prove_with_inputs(seed, inc, 4).is_err());
+    }
+
+    /// Every curated profile claims 127 bits and its proof verifies; Demo128 reproduces generate_proof
+    #[test]
+    fn curated_profiles_verify() {
+        let digest = Sha256::digest(b"profiles");
+        let (seed, inc) = public_inputs_from_digest(&digest).unwrap();
+        for profile in Profile::CURATED {
+            assert_eq!(profile.conjectured_security(), 127, "{profile:?}");
+            let (params, proof) = generate_proof_profile(&digest, profile).unwrap();
+            verify_proof_with_params(&proof, &params, seed, inc).unwrap();
+            StarkVerifier::default().with_trace_len(8).verify(&proof, seed, inc).unwrap();
+            assert!(verify_proof(&proof, seed, inc ^ 1).is_err());
+        }
+        assert_eq!(generate_proof_profile(&digest, Profile::Demo128).unwrap(), generate_proof(&digest).unwrap());
+    }
+
+    /// A custom profile under 127 bits proves but fails the acceptance rule; invalid values are refused
+    #[test]
+    fn weak_custom_profile_rejected() {
+        let digest = [5u8; 32];
+        let (seed, inc) = public_inputs_from_digest(&digest).unwrap();
+        let weak = Profile::Custom(ProfileParams { num_queries: 20, ..Profile::Demo128.params() });
+        assert_eq!(weak.conjectured_security(), 87);
+        let (_params, proof) = generate_proof_profile(&digest, weak).unwrap();
+        assert!(verify_proof(&proof, seed, inc).is_err());
+        StarkVerifier::new(87).verify(&proof, seed, inc).unwrap();
+
+        for bad in [
+            ProfileParams { num_queries: 0, ..Profile::Demo128.params() },
+            ProfileParams { blowup_factor: 12, ..Profile::Demo128.params() },
+            ProfileParams { grinding_factor: 33, ..Profile::Demo128.params() },
+            ProfileParams { folding_factor: 3, ..Profile::Demo128.params() },
+        ] {
+            assert!(generate_proof_profile(&digest, Profile::Custom(bad)).is_err(), "{bad:?}");
+        }
+    }
+
+    /// Degenerate custom params report 0 bits instead of panicking on ilog2(0) or 0 - 1
+    #[test]
+    fn degenerate_params_report_zero_security() {
+        let demo = Profile::Demo128.params();
+        for bad in [
+            ProfileParams { blowup_factor: 0, ..demo },
+            ProfileParams { num_queries: 0, grinding_factor: 0, ..demo },
+            ProfileParams { num_queries: usize::MAX, ..demo },
+            ProfileParams { blowup_factor: 1, grinding_factor: 0, ..demo },
+            ProfileParams { folding_factor: 0, ..demo },
+        ] {
+            assert!(bad.validate().is_err(), "{bad:?}");
+            assert_eq!(bad.conjectured_security(), 0, "{bad:?}");
+            assert_eq!(Profile::Custom(bad).conjectured_security(), 0, "{bad:?}");
+        }
+        let minimal = ProfileParams { num_queries: 1, blowup_factor: 2, grinding_factor: 0, folding_factor: 2 };
+        assert_eq!(minimal.conjectured_security(), 0);
+        assert_eq!(ProfileParams { num_queries: 255, blowup_factor: 128, grinding_factor: 32, folding_factor: 16 }.conjectured_security(), 127);
+    }
 }
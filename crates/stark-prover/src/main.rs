@@ -1,18 +1,152 @@
-//! CLI wrapper that reads a hex SHA256 digest and writes proof.bin
+//! CLI wrapper that reads a hex SHA256 digest and writes proof.bin (or the --out path)
+//! An optional trace length argument (default 8) is used for CU probing
+//! --emit-params also writes the serialized params next to the proof as <out>.params
+//! `verify` checks a proof file against a digest on the host, without Solana
 
-use std::{env, fs};
-use stark_prover::generate_proof;
+use std::{env, fs, path::{Path, PathBuf}};
+use stark_prover::{generate_proof_with_options, public_inputs_from_digest, verify_proof, ProverOptions};
+
+const USAGE: &str = "usage: cargo run -p stark-prover --release -- gen <sha256_hex> [trace_len] [--out <path>] [--emit-params]\n       \
+                     cargo run -p stark-prover --release -- verify <sha256_hex> <proof.bin>";
 
 fn main() -> anyhow::Result<()> {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 3 || args[1] != "gen" {
-        eprintln!("usage: cargo run -p stark-prover --release -- gen <sha256_hex>");
-        std::process::exit(1);
+    match (args.get(1).map(String::as_str), args.len()) {
+        (Some("gen"), 3..) => match parse_gen(&args[2..]) {
+            Some(gen_args) => gen(&gen_args),
+            None => {
+                eprintln!("{USAGE}");
+                std::process::exit(1);
+            }
+        },
+        (Some("verify"), 4) => match verify_file(&args[2], Path::new(&args[3])) {
+            Ok(()) => {
+                println!("OK ✅");
+                Ok(())
+            }
+            Err(e) => {
+                println!("FAIL ❌ {e:#}");
+                std::process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Arguments of the gen subcommand
+#[derive(Debug, PartialEq, Eq)]
+struct GenArgs {
+    hash_hex: String,
+    trace_len: Option<String>,
+    out: PathBuf,
+    emit_params: bool,
+}
+
+/// Parses `<sha256_hex> [trace_len] [--out <path>] [--emit-params]`; None on anything else
+fn parse_gen(args: &[String]) -> Option<GenArgs> {
+    let (hash_hex, rest) = args.split_first()?;
+    let mut parsed = GenArgs {
+        hash_hex: hash_hex.clone(),
+        trace_len: None,
+        out: PathBuf::from("proof.bin"),
+        emit_params: false,
+    };
+    let mut it = rest.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--out" => parsed.out = PathBuf::from(it.next()?),
+            "--emit-params" => parsed.emit_params = true,
+            _ if parsed.trace_len.is_none() && !arg.starts_with("--") => parsed.trace_len = Some(arg.clone()),
+            _ => return None,
+        }
+    }
+    Some(parsed)
+}
+
+/// Path the params are written to with --emit-params: the proof path with ".params" appended
+fn params_path(out: &Path) -> PathBuf {
+    let mut name = out.as_os_str().to_owned();
+    name.push(".params");
+    PathBuf::from(name)
+}
+
+/// Writes the proof for the digest to args.out, and the params to <out>.params with --emit-params
+fn gen(args: &GenArgs) -> anyhow::Result<()> {
+    let hash_bytes = hex::decode(&args.hash_hex)?;
+    let mut opts = ProverOptions::default();
+    if let Some(n) = &args.trace_len {
+        opts.trace_len = n.parse().map_err(|_| anyhow::anyhow!("trace_len {n:?} is not a number"))?;
+    }
+    let (params_bin, proof_bin) = generate_proof_with_options(&hash_bytes, opts)?;
+    fs::write(&args.out, &proof_bin)?;
+    println!("{} generated ✅", args.out.display());
+    if args.emit_params {
+        let path = params_path(&args.out);
+        fs::write(&path, &params_bin)?;
+        println!("{} generated ✅", path.display());
     }
-    let hash_hex = &args[2];
-    let hash_bytes = hex::decode(hash_hex)?;
-    let (_params_bin, proof_bin) = generate_proof(&hash_bytes)?;
-    fs::write("proof.bin", &proof_bin)?;
-    println!("proof.bin generated ✅");
     Ok(())
 }
+
+/// Reads a proof file and verifies it against (seed, inc) derived from the digest as the program does
+fn verify_file(hash_hex: &str, proof_path: &Path) -> anyhow::Result<()> {
+    let hash_bytes = hex::decode(hash_hex)?;
+    let (seed, inc) = public_inputs_from_digest(&hash_bytes)?;
+    let proof_bin = fs::read(proof_path)?;
+    verify_proof(&proof_bin, seed, inc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    /// A freshly generated proof file verifies; another digest or a flipped byte fails
+    #[test]
+    fn verify_subcommand_checks_generated_proof() {
+        let digest = Sha256::digest(b"cli self-check");
+        let (_params, proof) = stark_prover::generate_proof(&digest).unwrap();
+        let path = env::temp_dir().join(format!("stark-prover-verify-{}.bin", std::process::id()));
+        fs::write(&path, &proof).unwrap();
+
+        assert!(verify_file(&hex::encode(digest), &path).is_ok());
+        assert!(verify_file(&hex::encode(Sha256::digest(b"other")), &path).is_err());
+
+        let mut corrupted = proof.clone();
+        let mid = corrupted.len() / 2;
+        corrupted[mid] ^= 1;
+        fs::write(&path, &corrupted).unwrap();
+        assert!(verify_file(&hex::encode(digest), &path).is_err());
+
+        fs::remove_file(&path).unwrap();
+        assert!(verify_file(&hex::encode(digest), &path).is_err());
+    }
+
+    /// gen --out writes the proof (and with --emit-params the params) to the given path, not proof.bin
+    #[test]
+    fn gen_writes_to_custom_out() {
+        let digest = hex::encode(Sha256::digest(b"cli out path"));
+        let dir = env::temp_dir().join(format!("stark-prover-out-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let out = dir.join("job-7.proof");
+        let argv: Vec<String> = [&digest[..], "--out", out.to_str().unwrap(), "--emit-params"].map(String::from).to_vec();
+
+        let args = parse_gen(&argv).unwrap();
+        assert_eq!((args.out.as_path(), args.emit_params, args.trace_len.as_deref()), (out.as_path(), true, None));
+        gen(&args).unwrap();
+
+        assert!(!fs::read(&out).unwrap().is_empty());
+        assert!(!fs::read(dir.join("job-7.proof.params")).unwrap().is_empty());
+        assert!(verify_file(&digest, &out).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let defaults = parse_gen(&[digest.clone(), "8".into()]).unwrap();
+        assert_eq!(defaults.out, PathBuf::from("proof.bin"));
+        assert_eq!((defaults.trace_len.as_deref(), defaults.emit_params), (Some("8"), false));
+        assert!(parse_gen(&[digest.clone(), "--out".into()]).is_none());
+        assert!(parse_gen(&[digest, "--bogus".into()]).is_none());
+    }
+}
@@ -4,7 +4,7 @@
 import BN from 'bn.js';
 import { program, provider } from './utils/sdk.ts';
 import fs from 'fs/promises';
-import { PublicKey, ComputeBudgetProgram, SystemProgram } from '@solana/web3.js';
+import { PublicKey, ComputeBudgetProgram, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY } from '@solana/web3.js';
 import { b64ToU8 } from './utils/crypto.ts';
 import { dirname, resolve, join as pathJoin } from 'path';
 import { fileURLToPath } from 'url';
@@ -57,6 +57,8 @@ const slotBufSeed = Buffer.alloc(8); slotBufSeed.writeBigUInt64LE(BigInt(meta.sl
 const [bufPda]  = PublicKey.findProgramAddressSync([Buffer.from('buf'), sender.toBuffer()], program.programId);
 const sigPda    = new PublicKey(meta.sigPda);
 const [chatPda] = PublicKey.findProgramAddressSync([Buffer.from('msg'), sender.toBuffer(), recipient.toBuffer(), slotBufSeed], program.programId);
+const [noncePda]    = PublicKey.findProgramAddressSync([Buffer.from('nonce'), sender.toBuffer()], program.programId);
+const [identityPda] = PublicKey.findProgramAddressSync([Buffer.from('pqid'), sender.toBuffer()], program.programId);
 
 // Helpful diagnostics
 const aiBuf  = await provider.connection.getAccountInfo(bufPda);
@@ -64,7 +66,9 @@ const aiSig  = await provider.connection.getAccountInfo(sigPda);
 const aiChat = await provider.connection.getAccountInfo(chatPda);
 console.log('[DBG] bufPda exists? =', !!aiBuf,  aiBuf  ? `len=${aiBuf.data.length}` : '');
 console.log('[DBG] sigPda exists? =', !!aiSig,  aiSig  ? `len=${aiSig.data.length}` : '');
+const aiIdentity = await provider.connection.getAccountInfo(identityPda);
 console.log('[DBG] chatPda exists?=', !!aiChat, aiChat ? `len=${aiChat.data.length}` : '');
+console.log('[DBG] pqid registered?=', !!aiIdentity);
 
 // SLH public key for on-chain verification
 const { pkB64 } = JSON.parse(await fs.readFile('keys/slh_pub.json', 'utf8'));
@@ -72,7 +76,7 @@ const pkBytes   = Buffer.from(b64ToU8(pkB64));
 
 // Rough expected size for sanity
 const nonceBuf = Buffer.from(meta.nonce);
-const expected = 8 + 164 + (Number(meta.cipher_len) + Number(meta.kem_len) + Number(meta.proof_len));
+const expected = 8 + 215 + (Number(meta.cipher_len) + Number(meta.kem_len) + Number(meta.proof_len));
 console.log('DBG expected chat_msg space =', expected, '(kyber=768)');
 
 // Step 1 finalizeSig; heap and CU tuned for devnet
@@ -83,17 +87,23 @@ const sig1 = await program.methods
   .finalizeSig(
     Number(meta.cipher_len),
     Number(meta.kem_len),
-    Array.from(nonceBuf) as number[],
+    nonceBuf, // bytes: 12 or 24
     slotBN,
     Array.from(pkBytes) as number[],
+    Number(meta.msg_type ?? 0),
+    Number(meta.sig_alg ?? 1),
   )
   .accountsStrict({
     buffer: bufPda,
     sigbuf: sigPda,
     chatMsg: chatPda,
+    nonceRegistry: noncePda,
     recipient: recipient,
     payer: sender,
     systemProgram: SystemProgram.programId,
+    identity: identityPda, // Always the sender's pqid address; checked only once registered
+    config: null,
+    relayer: null,
   })
   .preInstructions([heapIx1, cuIx1])
   .rpc();
@@ -104,13 +114,14 @@ await printCu(sig1, 'finalizeSig', 550_000);
 const chatAcc1 = await program.account.chatMsg.fetch(chatPda);
 console.log('[DBG] step1 chat.len =', chatAcc1.payload.length, 'slot=', chatAcc1.slot.toString());
 
-// Step 2 verifyStark; heap must match on-chain limit
-const heapIx2 = ComputeBudgetProgram.requestHeapFrame({ bytes: 256 * 1024 });
+// Step 2 verifyStark; heap_bytes must match requestHeapFrame (the program reads it from the instructions sysvar)
+const HEAP2   = 256 * 1024;
+const heapIx2 = ComputeBudgetProgram.requestHeapFrame({ bytes: HEAP2 });
 const cuIx2   = ComputeBudgetProgram.setComputeUnitLimit({ units: 1_200_000 });
 
 const sig2 = await program.methods
-  .verifyStark()
-  .accountsStrict({ chatMsg: chatPda })
+  .verifyStark({ cipher: {} }, HEAP2)
+  .accountsStrict({ chatMsg: chatPda, instructions: SYSVAR_INSTRUCTIONS_PUBKEY })
   .preInstructions([heapIx2, cuIx2])
   .rpc();
 console.log('step-2 done ✅');
@@ -3,6 +3,7 @@
 
 import { program, provider } from './utils/sdk.ts';
 import { slhVerify, kemDecapsulate, aeadOpen } from './utils/crypto.ts';
+import { signedBlob } from './utils/blob.ts';
 import fs from 'fs/promises';
 import { PublicKey } from '@solana/web3.js';
 import { dirname, resolve, join as pathJoin } from 'path';
@@ -69,7 +70,7 @@ if (!sigHashLocal.equals(sigHashOnchain)) {
   throw new Error('sig hash mismatch');
 }
 
-// Recreate signed blob and verify SLH-DSA
+// Recreate the signed blob (same builder as upload.ts) and verify SLH-DSA
 const payload  = Uint8Array.from(chosen.acc.payload);
 const cipher   = payload.slice(0, chosen.acc.cipherLen);
 const kemStart = chosen.acc.cipherLen;
@@ -77,9 +78,17 @@ const kemEnd   = kemStart + chosen.acc.kemLen;
 const kemCt    = payload.slice(kemStart, kemEnd);
 
 const slotBig = typeof chosen.acc.slot === 'number' ? BigInt(chosen.acc.slot) : BigInt(chosen.acc.slot.toString());
-const slotBuf = Buffer.alloc(8); slotBuf.writeBigUInt64LE(slotBig);
 
-const blob = Buffer.concat([Buffer.from(cipher), Buffer.from(kemCt), Buffer.from(chosen.acc.nonce), slotBuf]);
+// sig_alg picks the verifier; this demo only knows SHA2-128s (1)
+const sigAlg = Number(chosen.acc.sigAlg);
+if (sigAlg !== 1) throw new Error(`unsupported sig_alg ${sigAlg}`);
+
+// Only the first nonceLen bytes of the stored nonce are used (12 here, 24 for XChaCha20)
+const nonce = Buffer.from(chosen.acc.nonce).subarray(0, chosen.acc.nonceLen);
+const blob = signedBlob({
+  cipher, kem: kemCt, sender: chosen.acc.sender, recipient: chosen.acc.recipient, nonce,
+  slot: slotBig, msgType: Number(chosen.acc.msgType), sigAlg,
+});
 
 // For this demo the sender is self; in real apps fetch sender VK from app storage
 const { pkB64: slhPkB64 } = JSON.parse(await fs.readFile('keys/slh_pub.json', 'utf8'));
@@ -93,7 +102,7 @@ if (!ok) throw new Error('SLH-DSA verify NG');
 const { skB64: kemSkB64 } = JSON.parse(await fs.readFile('keys/kem_sec.json', 'utf8'));
 const kemSk = Uint8Array.from(Buffer.from(kemSkB64, 'base64'));
 const aeadKey = await kemDecapsulate(kemCt, kemSk);
-const plaintext = aeadOpen(cipher, aeadKey, Uint8Array.from(chosen.acc.nonce));
+const plaintext = aeadOpen(cipher, aeadKey, Uint8Array.from(nonce));
 
 console.log('PLAINTEXT =', plaintext.toString());
 console.log('receive ✅');
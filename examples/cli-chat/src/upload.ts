@@ -3,7 +3,8 @@
 
 import BN from 'bn.js';
 import { program, provider } from './utils/sdk.ts';
-import { slhSign, kemEncapsulate } from './utils/crypto.ts';
+import { slhSign, kemEncapsulate, nextUploadChain } from './utils/crypto.ts';
+import { signedBlob } from './utils/blob.ts';
 import fs from 'fs/promises';
 import { PublicKey, SystemProgram } from '@solana/web3.js';
 import { execSync } from 'node:child_process';
@@ -89,24 +90,28 @@ execSync(
 );
 const proof = await fs.readFile(resolve(PROVER_DIR, 'proof.bin'));
 
-// SLH-DSA sign over cipher, KEM ct, nonce, slot
+// SLH-DSA sign over cipher, KEM ct, sender, recipient, nonce, nonce length, slot, msg_type, sig_alg
 const { skB64 } = JSON.parse(await fs.readFile('keys/slh_sec.json', 'utf8'));
 const sk        = Uint8Array.from(Buffer.from(skB64, 'base64'));
 
-const slotBuf = Buffer.alloc(8); slotBuf.writeBigUInt64LE(BigInt(slot));
-const signBlob = Buffer.concat([Buffer.from(cipher), Buffer.from(kemCiphertext), nonce, slotBuf]);
+const MSG_TYPE = 0; // Application-defined kind; 0 = text in this demo
+const SIG_ALG = 1; // SHA2-128s, the only parameter set the program accepts
+const signBlob = signedBlob({
+  cipher, kem: kemCiphertext, sender, recipient, nonce,
+  slot: BigInt(slot), msgType: MSG_TYPE, sigAlg: SIG_ALG,
+});
 const sigU8 = await slhSign(signBlob, sk);
 const SIG   = Buffer.from(sigU8);
 const SIG_LEN = SIG.length;
 
-// Upload signature with hash-chaining
+// Upload signature with hash-chaining (chain hash comes from the wasm build, see nextUploadChain)
 async function sendChunksSig(pda: PublicKey, raw: Buffer) {
   let offset = 0;
   let hash: Buffer = Buffer.alloc(32); // zero seed
   let n = 0;
   while (offset < raw.length) {
     const chunk = raw.slice(offset, offset + CHUNK_MAX);
-    hash = crypto.createHash('sha256').update(Buffer.concat([hash, chunk])).digest();
+    hash = nextUploadChain(hash, chunk);
     await program.methods
       .uploadSignature(recipient, slotBN, offset, chunk, Array.from(hash))
       .accountsStrict({
@@ -128,7 +133,7 @@ async function sendChunksBody(pda: PublicKey, raw: Buffer) {
   let hash: Buffer = Buffer.alloc(32);
   while (offset < raw.length) {
     const chunk = raw.slice(offset, offset + CHUNK_MAX);
-    hash = crypto.createHash('sha256').update(Buffer.concat([hash, chunk])).digest();
+    hash = nextUploadChain(hash, chunk);
     await program.methods
       .uploadBody(offset, chunk, Array.from(hash))
       .accountsStrict({ buffer: pda, sender })
@@ -168,6 +173,8 @@ await fs.writeFile(
     proof_len : proof.length,
     nonce     : [...nonce],
     slot,
+    msg_type  : MSG_TYPE,
+    sig_alg   : SIG_ALG,
     sigPda    : sigPda.toBase58(),
     recipient : recipient.toBase58(),
   })
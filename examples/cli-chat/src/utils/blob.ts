@@ -0,0 +1,25 @@
+// SLH-DSA signed blob shared by upload.ts (signer) and receive.ts (verifier).
+// Layout mirrors the program's signed_message:
+// cipher || kem || sender || recipient || nonce || nonce_len(u8) || slot_le || msg_type_le || sig_alg(u8)
+
+import { PublicKey } from '@solana/web3.js';
+
+export interface BlobFields {
+  cipher   : Uint8Array;
+  kem      : Uint8Array;
+  sender   : PublicKey;
+  recipient: PublicKey;
+  nonce    : Uint8Array; // 12 or 24 bytes, only the used part
+  slot     : bigint;
+  msgType  : number;
+  sigAlg   : number;
+}
+
+export function signedBlob(f: BlobFields): Buffer {
+  const slotBuf = Buffer.alloc(8); slotBuf.writeBigUInt64LE(f.slot);
+  const typeBuf = Buffer.alloc(2); typeBuf.writeUInt16LE(f.msgType);
+  return Buffer.concat([
+    Buffer.from(f.cipher), Buffer.from(f.kem), f.sender.toBuffer(), f.recipient.toBuffer(),
+    Buffer.from(f.nonce), Buffer.from([f.nonce.length]), slotBuf, typeBuf, Buffer.from([f.sigAlg]),
+  ]);
+}
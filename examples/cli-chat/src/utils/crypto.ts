@@ -12,6 +12,7 @@ import {
   sign   as wasm_sign,
   verify as wasm_verify,
   vk_bytes_from_sk as wasm_vk_bytes,
+  next_upload_chain as wasm_next_upload_chain,
 } from '../../../../crates/slh-dsa-wasm/pkg/slh_dsa_wasm/slh_dsa_wasm.js';
 
 export const PARAM = 'sha2_128s';
@@ -39,6 +40,9 @@ export function deriveVkFromSk(skB64: string): Uint8Array {
 export const slhSign   = wasm_sign;
 export const slhVerify = wasm_verify;
 
+// Upload chain value H(prev || chunk); SHA-256 or BLAKE3 per the wasm build, which must match the program's
+export const nextUploadChain = (prev: Uint8Array, chunk: Uint8Array) => Buffer.from(wasm_next_upload_chain(prev, chunk));
+
 // AES-256-GCM seal; returns ciphertext concatenated with 16-byte tag
 export function aeadSeal(
   plaintext: Uint8Array,
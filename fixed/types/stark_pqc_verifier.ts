@@ -12,10 +12,75 @@ export type StarkPqcVerifier = {
     "spec": "0.1.0"
   },
   "instructions": [
+    {
+      "name": "auditChatmsg",
+      "docs": [
+        "Returns (as return data) a ChatMsgAudit: sig_len, nonce_len, sig_alg, cipher_len + kem_len against the payload,",
+        "proof size and shard layout, with the first inconsistency found. Read-only; simulate it."
+      ],
+      "discriminator": [
+        167,
+        86,
+        208,
+        191,
+        55,
+        111,
+        75,
+        72
+      ],
+      "accounts": [
+        {
+          "name": "chatMsg"
+        }
+      ],
+      "args": [],
+      "returns": {
+        "defined": {
+          "name": "chatMsgAudit"
+        }
+      }
+    },
+    {
+      "name": "auditSig",
+      "docs": [
+        "Re-hashes the ChatMsg's signature buffer and fails unless it matches sig_hash and `expected_sig_hash`."
+      ],
+      "discriminator": [
+        65,
+        226,
+        141,
+        220,
+        141,
+        89,
+        170,
+        13
+      ],
+      "accounts": [
+        {
+          "name": "chatMsg"
+        },
+        {
+          "name": "sigbuf"
+        }
+      ],
+      "args": [
+        {
+          "name": "expectedSigHash",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          }
+        }
+      ]
+    },
     {
       "name": "finalizeSig",
       "docs": [
-        "Step 1: Verifies SLH-DSA and persists a ChatMsg."
+        "Step 1: Verifies SLH-DSA and persists a ChatMsg.",
+        "`msg_type` is an application-defined kind stored in ChatMsg and covered by the signature.",
+        "`sig_alg` names the SLH-DSA parameter set (only SIG_ALG_SHA2_128S today); it is stored and signed too."
       ],
       "discriminator": [
         242,
@@ -105,7 +170,32 @@ export type StarkPqcVerifier = {
           }
         },
         {
-          "name": "recipient"
+          "name": "nonceRegistry",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  110,
+                  111,
+                  110,
+                  99,
+                  101
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "recipient",
+          "docs": [
+            "so a body signed for one recipient fails SigFailed here under another."
+          ]
         },
         {
           "name": "payer",
@@ -115,6 +205,59 @@ export type StarkPqcVerifier = {
         {
           "name": "systemProgram",
           "address": "11111111111111111111111111111111"
+        },
+        {
+          "name": "identity",
+          "docs": [
+            "the registered key once this account exists."
+          ],
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  112,
+                  113,
+                  105,
+                  100
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "config",
+          "docs": [
+            "Trusted-relayer config; only read when `relayer` signs."
+          ],
+          "optional": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  99,
+                  111,
+                  110,
+                  102,
+                  105,
+                  103
+                ]
+              }
+            ]
+          }
+        },
+        {
+          "name": "relayer",
+          "docs": [
+            "Relayer attesting that it verified the STARK off chain; honored only if config enables it."
+          ],
+          "signer": true,
+          "optional": true
         }
       ],
       "args": [
@@ -128,12 +271,7 @@ export type StarkPqcVerifier = {
         },
         {
           "name": "nonce",
-          "type": {
-            "array": [
-              "u8",
-              12
-            ]
-          }
+          "type": "bytes"
         },
         {
           "name": "slot",
@@ -147,23 +285,34 @@ export type StarkPqcVerifier = {
               32
             ]
           }
+        },
+        {
+          "name": "msgType",
+          "type": "u16"
+        },
+        {
+          "name": "sigAlg",
+          "type": "u8"
         }
       ]
     },
     {
-      "name": "initBuffer",
+      "name": "finalizeSigMulti",
       "docs": [
-        "Initializes the body buffer PDA used for streaming uploads, zeros length and sha_chain."
+        "Step 1 with co-signers: every key in `slh_pubs` (at most 2) must sign the same message.",
+        "The payer's sig buffer holds slh_pubs[0]'s signature; each other key follows as remaining accounts",
+        "(its owner's PqIdentity, then that owner's sig PDA), so a co-signer must be a registered identity.",
+        "Every signer is recorded in the CoSigners account."
       ],
       "discriminator": [
-        123,
-        211,
-        233,
-        210,
-        166,
-        139,
-        218,
-        60
+        231,
+        54,
+        111,
+        138,
+        215,
+        94,
+        147,
+        81
       ],
       "accounts": [
         {
@@ -187,43 +336,43 @@ export type StarkPqcVerifier = {
           }
         },
         {
-          "name": "payer",
+          "name": "sigbuf",
           "writable": true,
-          "signer": true
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  115,
+                  105,
+                  103
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              },
+              {
+                "kind": "account",
+                "path": "recipient"
+              },
+              {
+                "kind": "arg",
+                "path": "slot"
+              }
+            ]
+          }
         },
         {
-          "name": "systemProgram",
-          "address": "11111111111111111111111111111111"
-        }
-      ],
-      "args": []
-    },
-    {
-      "name": "initSignature",
-      "docs": [
-        "Initializes the signature buffer PDA for a (sender, recipient, slot) tuple."
-      ],
-      "discriminator": [
-        92,
-        231,
-        134,
-        178,
-        185,
-        71,
-        16,
-        65
-      ],
-      "accounts": [
-        {
-          "name": "buffer",
+          "name": "chatMsg",
           "writable": true,
           "pda": {
             "seeds": [
               {
                 "kind": "const",
                 "value": [
+                  109,
                   115,
-                  105,
                   103
                 ]
               },
@@ -232,7 +381,7 @@ export type StarkPqcVerifier = {
                 "path": "payer"
               },
               {
-                "kind": "arg",
+                "kind": "account",
                 "path": "recipient"
               },
               {
@@ -243,7 +392,32 @@ export type StarkPqcVerifier = {
           }
         },
         {
-          "name": "recipient"
+          "name": "nonceRegistry",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  110,
+                  111,
+                  110,
+                  99,
+                  101
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "recipient",
+          "docs": [
+            "so a body signed for one recipient fails SigFailed here under another."
+          ]
         },
         {
           "name": "payer",
@@ -253,33 +427,106 @@ export type StarkPqcVerifier = {
         {
           "name": "systemProgram",
           "address": "11111111111111111111111111111111"
+        },
+        {
+          "name": "identity",
+          "docs": [
+            "the registered key once this account exists."
+          ],
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  112,
+                  113,
+                  105,
+                  100
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "cosigners",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  99,
+                  111,
+                  115,
+                  105,
+                  103
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "chatMsg"
+              }
+            ]
+          }
         }
       ],
       "args": [
         {
-          "name": "recipient",
-          "type": "pubkey"
+          "name": "cipherLen",
+          "type": "u32"
+        },
+        {
+          "name": "kemLen",
+          "type": "u32"
+        },
+        {
+          "name": "nonce",
+          "type": "bytes"
         },
         {
           "name": "slot",
           "type": "u64"
+        },
+        {
+          "name": "slhPubs",
+          "type": {
+            "vec": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          }
+        },
+        {
+          "name": "msgType",
+          "type": "u16"
+        },
+        {
+          "name": "sigAlg",
+          "type": "u8"
         }
       ]
     },
     {
-      "name": "uploadBody",
+      "name": "finalizeSigPrehash",
       "docs": [
-        "Appends a body chunk to the body buffer (cipher || kem || proof) with hash-chaining."
+        "Step 1 with a HashSLH-DSA signature: slh_pub signed SHA-256 of the finalize_sig message (FIPS 205 prehash).",
+        "Same accounts, arguments and ChatMsg as finalize_sig."
       ],
       "discriminator": [
-        66,
-        112,
-        164,
-        113,
-        136,
-        164,
-        71,
-        93
+        216,
+        157,
+        25,
+        16,
+        111,
+        202,
+        43,
+        116
       ],
       "accounts": [
         {
@@ -297,17 +544,947 @@ export type StarkPqcVerifier = {
               },
               {
                 "kind": "account",
-                "path": "sender"
+                "path": "payer"
               }
             ]
           }
         },
         {
-          "name": "sender",
-          "signer": true
-        }
-      ],
-      "args": [
+          "name": "sigbuf",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  115,
+                  105,
+                  103
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              },
+              {
+                "kind": "account",
+                "path": "recipient"
+              },
+              {
+                "kind": "arg",
+                "path": "slot"
+              }
+            ]
+          }
+        },
+        {
+          "name": "chatMsg",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  109,
+                  115,
+                  103
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              },
+              {
+                "kind": "account",
+                "path": "recipient"
+              },
+              {
+                "kind": "arg",
+                "path": "slot"
+              }
+            ]
+          }
+        },
+        {
+          "name": "nonceRegistry",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  110,
+                  111,
+                  110,
+                  99,
+                  101
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "recipient",
+          "docs": [
+            "so a body signed for one recipient fails SigFailed here under another."
+          ]
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        },
+        {
+          "name": "identity",
+          "docs": [
+            "the registered key once this account exists."
+          ],
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  112,
+                  113,
+                  105,
+                  100
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "config",
+          "docs": [
+            "Trusted-relayer config; only read when `relayer` signs."
+          ],
+          "optional": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  99,
+                  111,
+                  110,
+                  102,
+                  105,
+                  103
+                ]
+              }
+            ]
+          }
+        },
+        {
+          "name": "relayer",
+          "docs": [
+            "Relayer attesting that it verified the STARK off chain; honored only if config enables it."
+          ],
+          "signer": true,
+          "optional": true
+        }
+      ],
+      "args": [
+        {
+          "name": "cipherLen",
+          "type": "u32"
+        },
+        {
+          "name": "kemLen",
+          "type": "u32"
+        },
+        {
+          "name": "nonce",
+          "type": "bytes"
+        },
+        {
+          "name": "slot",
+          "type": "u64"
+        },
+        {
+          "name": "slhPub",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          }
+        },
+        {
+          "name": "msgType",
+          "type": "u16"
+        },
+        {
+          "name": "sigAlg",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "finalizeSigSharded",
+      "docs": [
+        "Step 1 for a sharded payload: slh_pub signs shard_commitment(shard_root, shard_count, cipher_len, kem_len)",
+        "(prefixed with SHARD_COMMITMENT_TAG) in place of cipher || kem; cipher_len + kem_len must fit the shards.",
+        "The ChatMsg stores no payload; store_shard adds each shard afterwards."
+      ],
+      "discriminator": [
+        49,
+        244,
+        39,
+        221,
+        230,
+        15,
+        77,
+        21
+      ],
+      "accounts": [
+        {
+          "name": "sigbuf",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  115,
+                  105,
+                  103
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              },
+              {
+                "kind": "account",
+                "path": "recipient"
+              },
+              {
+                "kind": "arg",
+                "path": "slot"
+              }
+            ]
+          }
+        },
+        {
+          "name": "chatMsg",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  109,
+                  115,
+                  103
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              },
+              {
+                "kind": "account",
+                "path": "recipient"
+              },
+              {
+                "kind": "arg",
+                "path": "slot"
+              }
+            ]
+          }
+        },
+        {
+          "name": "nonceRegistry",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  110,
+                  111,
+                  110,
+                  99,
+                  101
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "recipient",
+          "docs": [
+            "so a body signed for one recipient fails SigFailed here under another."
+          ]
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        },
+        {
+          "name": "identity",
+          "docs": [
+            "the registered key once this account exists."
+          ],
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  112,
+                  113,
+                  105,
+                  100
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        }
+      ],
+      "args": [
+        {
+          "name": "cipherLen",
+          "type": "u32"
+        },
+        {
+          "name": "kemLen",
+          "type": "u32"
+        },
+        {
+          "name": "nonce",
+          "type": "bytes"
+        },
+        {
+          "name": "slot",
+          "type": "u64"
+        },
+        {
+          "name": "slhPub",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          }
+        },
+        {
+          "name": "msgType",
+          "type": "u16"
+        },
+        {
+          "name": "sigAlg",
+          "type": "u8"
+        },
+        {
+          "name": "shardCount",
+          "type": "u16"
+        },
+        {
+          "name": "shardRoot",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          }
+        }
+      ]
+    },
+    {
+      "name": "initBuffer",
+      "docs": [
+        "Initializes the body buffer PDA used for streaming uploads, zeros length and sha_chain."
+      ],
+      "discriminator": [
+        123,
+        211,
+        233,
+        210,
+        166,
+        139,
+        218,
+        60
+      ],
+      "accounts": [
+        {
+          "name": "buffer",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  98,
+                  117,
+                  102
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "initSignature",
+      "docs": [
+        "Initializes the signature buffer PDA for a (sender, recipient, slot) tuple."
+      ],
+      "discriminator": [
+        92,
+        231,
+        134,
+        178,
+        185,
+        71,
+        16,
+        65
+      ],
+      "accounts": [
+        {
+          "name": "buffer",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  115,
+                  105,
+                  103
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              },
+              {
+                "kind": "arg",
+                "path": "recipient"
+              },
+              {
+                "kind": "arg",
+                "path": "slot"
+              }
+            ]
+          }
+        },
+        {
+          "name": "recipient"
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "recipient",
+          "type": "pubkey"
+        },
+        {
+          "name": "slot",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "messageExists",
+      "docs": [
+        "Returns (as return data) whether the ChatMsg for (sender, recipient, slot) has been finalized.",
+        "Read-only: lets clients skip a duplicate upload without racing a second finalize."
+      ],
+      "discriminator": [
+        17,
+        187,
+        69,
+        153,
+        109,
+        224,
+        109,
+        172
+      ],
+      "accounts": [
+        {
+          "name": "chatMsg",
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  109,
+                  115,
+                  103
+                ]
+              },
+              {
+                "kind": "arg",
+                "path": "sender"
+              },
+              {
+                "kind": "arg",
+                "path": "recipient"
+              },
+              {
+                "kind": "arg",
+                "path": "slot"
+              }
+            ]
+          }
+        }
+      ],
+      "args": [
+        {
+          "name": "sender",
+          "type": "pubkey"
+        },
+        {
+          "name": "recipient",
+          "type": "pubkey"
+        },
+        {
+          "name": "slot",
+          "type": "u64"
+        }
+      ],
+      "returns": "bool"
+    },
+    {
+      "name": "registerPqKey",
+      "docs": [
+        "Registers the signer's SLH-DSA verifying key; idempotent, and a different key rotates it."
+      ],
+      "discriminator": [
+        67,
+        43,
+        77,
+        216,
+        230,
+        139,
+        248,
+        143
+      ],
+      "accounts": [
+        {
+          "name": "identity",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  112,
+                  113,
+                  105,
+                  100
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "owner"
+              }
+            ]
+          }
+        },
+        {
+          "name": "owner",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "vk",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          }
+        }
+      ]
+    },
+    {
+      "name": "resetMessage",
+      "docs": [
+        "Empties the caller's existing body buffer (length 0, zero sha_chain) so a changed message can be re-uploaded."
+      ],
+      "discriminator": [
+        187,
+        185,
+        160,
+        222,
+        248,
+        90,
+        21,
+        10
+      ],
+      "accounts": [
+        {
+          "name": "buffer",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  98,
+                  117,
+                  102
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "sender"
+              }
+            ]
+          }
+        },
+        {
+          "name": "sender",
+          "signer": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "rotatePqKey",
+      "docs": [
+        "Replaces the owner's registered key and records rotated_at_slot."
+      ],
+      "discriminator": [
+        66,
+        32,
+        103,
+        192,
+        157,
+        83,
+        84,
+        67
+      ],
+      "accounts": [
+        {
+          "name": "identity",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  112,
+                  113,
+                  105,
+                  100
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "owner"
+              }
+            ]
+          },
+          "relations": [
+            "owner"
+          ]
+        },
+        {
+          "name": "owner",
+          "signer": true
+        }
+      ],
+      "args": [
+        {
+          "name": "newVk",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          }
+        }
+      ]
+    },
+    {
+      "name": "setStarkConfig",
+      "docs": [
+        "Upgrade authority only: names the trusted relayer and enables or disables skip_stark.",
+        "While enabled, finalize_sig co-signed by that relayer stores relayer_attested = true instead of needing verify_stark."
+      ],
+      "discriminator": [
+        238,
+        146,
+        182,
+        195,
+        94,
+        190,
+        213,
+        132
+      ],
+      "accounts": [
+        {
+          "name": "config",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  99,
+                  111,
+                  110,
+                  102,
+                  105,
+                  103
+                ]
+              }
+            ]
+          }
+        },
+        {
+          "name": "program",
+          "address": "CECNRbDxFQVfWiQwvG8qcSGPGSk8eLWraBCERcdL5DKT"
+        },
+        {
+          "name": "programData"
+        },
+        {
+          "name": "admin",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "relayer",
+          "type": "pubkey"
+        },
+        {
+          "name": "skipStark",
+          "type": "bool"
+        }
+      ]
+    },
+    {
+      "name": "storeShard",
+      "docs": [
+        "Moves the uploaded body buffer into ChatShard `index` of a sharded ChatMsg.",
+        "`path` is the Merkle path of the shard under ChatMsg.shard_root."
+      ],
+      "discriminator": [
+        181,
+        100,
+        155,
+        13,
+        163,
+        212,
+        80,
+        94
+      ],
+      "accounts": [
+        {
+          "name": "buffer",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  98,
+                  117,
+                  102
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              }
+            ]
+          }
+        },
+        {
+          "name": "chatMsg",
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  109,
+                  115,
+                  103
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "payer"
+              },
+              {
+                "kind": "arg",
+                "path": "recipient"
+              },
+              {
+                "kind": "arg",
+                "path": "slot"
+              }
+            ]
+          }
+        },
+        {
+          "name": "chatShard",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  115,
+                  104,
+                  97,
+                  114,
+                  100
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "chatMsg"
+              },
+              {
+                "kind": "arg",
+                "path": "index"
+              }
+            ]
+          }
+        },
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "recipient",
+          "type": "pubkey"
+        },
+        {
+          "name": "slot",
+          "type": "u64"
+        },
+        {
+          "name": "index",
+          "type": "u16"
+        },
+        {
+          "name": "path",
+          "type": {
+            "vec": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          }
+        }
+      ]
+    },
+    {
+      "name": "uploadBody",
+      "docs": [
+        "Appends a body chunk to the body buffer (cipher || kem || proof) with hash-chaining.",
+        "Both uploads return the new (length, sha_chain) as return data for the next chunk."
+      ],
+      "discriminator": [
+        66,
+        112,
+        164,
+        113,
+        136,
+        164,
+        71,
+        93
+      ],
+      "accounts": [
+        {
+          "name": "buffer",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  98,
+                  117,
+                  102
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "sender"
+              }
+            ]
+          }
+        },
+        {
+          "name": "sender",
+          "signer": true
+        }
+      ],
+      "args": [
         {
           "name": "off",
           "type": "u32"
@@ -325,7 +1502,12 @@ export type StarkPqcVerifier = {
             ]
           }
         }
-      ]
+      ],
+      "returns": {
+        "defined": {
+          "name": "uploadProgress"
+        }
+      }
     },
     {
       "name": "uploadSignature",
@@ -432,12 +1614,20 @@ export type StarkPqcVerifier = {
             ]
           }
         }
-      ]
+      ],
+      "returns": {
+        "defined": {
+          "name": "uploadProgress"
+        }
+      }
     },
     {
       "name": "verifyStark",
       "docs": [
-        "Step 2: Verifies the STARK proof for the affine-counter AIR."
+        "Step 2: Verifies the STARK proof for the affine-counter AIR.",
+        "`source` selects which payload segment (cipher or kem) derives (seed, inc).",
+        "`heap_bytes` must equal the transaction's requestHeapFrame (clamped to 256 KiB); a larger value fails with HeapFrameExceeded.",
+        "For a sharded ChatMsg, pass its ChatShards in index order as remaining accounts."
       ],
       "discriminator": [
         31,
@@ -452,9 +1642,26 @@ export type StarkPqcVerifier = {
       "accounts": [
         {
           "name": "chatMsg"
+        },
+        {
+          "name": "instructions",
+          "address": "Sysvar1nstructions1111111111111111111111111"
         }
       ],
-      "args": []
+      "args": [
+        {
+          "name": "source",
+          "type": {
+            "defined": {
+              "name": "inputSource"
+            }
+          }
+        },
+        {
+          "name": "heapBytes",
+          "type": "u32"
+        }
+      ]
     }
   ],
   "accounts": [
@@ -472,16 +1679,81 @@ export type StarkPqcVerifier = {
       ]
     },
     {
-      "name": "chatMsg",
+      "name": "chatMsg",
+      "discriminator": [
+        88,
+        253,
+        180,
+        118,
+        244,
+        42,
+        109,
+        102
+      ]
+    },
+    {
+      "name": "chatShard",
+      "discriminator": [
+        183,
+        26,
+        137,
+        138,
+        41,
+        99,
+        86,
+        80
+      ]
+    },
+    {
+      "name": "coSigners",
+      "discriminator": [
+        85,
+        44,
+        3,
+        19,
+        114,
+        228,
+        130,
+        25
+      ]
+    },
+    {
+      "name": "nonceRegistry",
+      "discriminator": [
+        115,
+        114,
+        189,
+        172,
+        239,
+        92,
+        79,
+        240
+      ]
+    },
+    {
+      "name": "pqIdentity",
+      "discriminator": [
+        240,
+        139,
+        81,
+        205,
+        28,
+        100,
+        0,
+        158
+      ]
+    },
+    {
+      "name": "starkConfig",
       "discriminator": [
-        88,
-        253,
+        137,
+        4,
+        27,
+        182,
+        217,
+        11,
         180,
-        118,
-        244,
-        42,
-        109,
-        102
+        96
       ]
     }
   ],
@@ -510,6 +1782,111 @@ export type StarkPqcVerifier = {
       "code": 6004,
       "name": "alreadyFinalized",
       "msg": "signature buffer is frozen (finalized)"
+    },
+    {
+      "code": 6005,
+      "name": "emptyChunk",
+      "msg": "empty chunk"
+    },
+    {
+      "code": 6006,
+      "name": "sigTooLong",
+      "msg": "signature upload would exceed 7,856 bytes"
+    },
+    {
+      "code": 6007,
+      "name": "notBufferSender",
+      "msg": "signer is not the buffer's sender"
+    },
+    {
+      "code": 7000,
+      "name": "lenMismatch",
+      "msg": "length mismatch"
+    },
+    {
+      "code": 7001,
+      "name": "sigFailed",
+      "msg": "signature verify failed"
+    },
+    {
+      "code": 7002,
+      "name": "proofFailed",
+      "msg": "STARK proof verify failed"
+    },
+    {
+      "code": 7003,
+      "name": "unsupportedHashId",
+      "msg": "proof hash-id not supported by this build"
+    },
+    {
+      "code": 7004,
+      "name": "nonceReused",
+      "msg": "nonce already used by this sender"
+    },
+    {
+      "code": 7005,
+      "name": "heapLimitInvalid",
+      "msg": "heap limit must be a 1024-byte multiple of at least 32 KiB"
+    },
+    {
+      "code": 7006,
+      "name": "sigHashMismatch",
+      "msg": "signature buffer does not match sig_hash"
+    },
+    {
+      "code": 7007,
+      "name": "identityKeyMismatch",
+      "msg": "key is not the owner's registered PQ identity"
+    },
+    {
+      "code": 7008,
+      "name": "signerCountInvalid",
+      "msg": "signer list must hold 1..=2 distinct keys, one per signature buffer"
+    },
+    {
+      "code": 7009,
+      "name": "sigBufferMissing",
+      "msg": "signature buffer missing or empty: run init_signature and upload_signature first"
+    },
+    {
+      "code": 7010,
+      "name": "nonceLengthInvalid",
+      "msg": "AEAD nonce must be 12 or 24 bytes"
+    },
+    {
+      "code": 7011,
+      "name": "shardInvalid",
+      "msg": "shard count, index, account or Merkle path does not match ChatMsg.shard_root"
+    },
+    {
+      "code": 7012,
+      "name": "configAuthorityMismatch",
+      "msg": "signer is not the program upgrade authority"
+    },
+    {
+      "code": 7013,
+      "name": "relayerNotAuthorized",
+      "msg": "relayer attestation needs config.skip_stark on and this relayer configured"
+    },
+    {
+      "code": 7014,
+      "name": "sigAlgUnsupported",
+      "msg": "SLH-DSA parameter set not supported by this build"
+    },
+    {
+      "code": 7015,
+      "name": "recipientMismatch",
+      "msg": "recipient argument does not match the recipient account used for PDA seeds"
+    },
+    {
+      "code": 7016,
+      "name": "heapFrameExceeded",
+      "msg": "heap_bytes exceeds the transaction's requestHeapFrame"
+    },
+    {
+      "code": 7017,
+      "name": "coSignerUnbound",
+      "msg": "co-signer needs a registered PqIdentity for its key and its own sig PDA"
     }
   ],
   "types": [
@@ -562,12 +1939,16 @@ export type StarkPqcVerifier = {
             "name": "kemLen",
             "type": "u32"
           },
+          {
+            "name": "nonceLen",
+            "type": "u8"
+          },
           {
             "name": "nonce",
             "type": {
               "array": [
                 "u8",
-                12
+                24
               ]
             }
           },
@@ -592,12 +1973,311 @@ export type StarkPqcVerifier = {
               ]
             }
           },
+          {
+            "name": "msgType",
+            "type": "u16"
+          },
+          {
+            "name": "shardCount",
+            "type": "u16"
+          },
+          {
+            "name": "shardRoot",
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            "name": "relayerAttested",
+            "type": "bool"
+          },
+          {
+            "name": "sigAlg",
+            "type": "u8"
+          },
           {
             "name": "payload",
             "type": "bytes"
           }
         ]
       }
+    },
+    {
+      "name": "chatMsgAudit",
+      "docs": [
+        "Result of audit_chatmsg, returned via set_return_data.",
+        "proof_len is payload.len() - cipher_len - kem_len (0 when sharded or when the lengths overrun)."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "payloadLen",
+            "type": "u32"
+          },
+          {
+            "name": "proofLen",
+            "type": "u32"
+          },
+          {
+            "name": "issue",
+            "type": {
+              "option": {
+                "defined": {
+                  "name": "chatMsgIssue"
+                }
+              }
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "chatMsgIssue",
+      "docs": [
+        "First internal inconsistency audit_chatmsg found in a ChatMsg."
+      ],
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "sigLenMismatch"
+          },
+          {
+            "name": "nonceLenInvalid"
+          },
+          {
+            "name": "sigAlgUnsupported"
+          },
+          {
+            "name": "lengthsOverrunPayload"
+          },
+          {
+            "name": "proofTooLarge"
+          },
+          {
+            "name": "shardLayoutInvalid"
+          }
+        ]
+      }
+    },
+    {
+      "name": "chatShard",
+      "docs": [
+        "One slice of a sharded payload (seeds: \"shard\", chat_msg, index_le).",
+        "Concatenating shards 0..shard_count gives cipher || kem || proof."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "chatMsg",
+            "type": "pubkey"
+          },
+          {
+            "name": "index",
+            "type": "u16"
+          },
+          {
+            "name": "data",
+            "type": "bytes"
+          }
+        ]
+      }
+    },
+    {
+      "name": "coSigners",
+      "docs": [
+        "Signers of a finalize_sig_multi ChatMsg (seeds: \"cosig\", chat_msg), in slh_pubs order.",
+        "Entries past count are zero; entry 0 repeats ChatMsg.sig_pda and sig_hash."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "chatMsg",
+            "type": "pubkey"
+          },
+          {
+            "name": "count",
+            "type": "u8"
+          },
+          {
+            "name": "keys",
+            "type": {
+              "array": [
+                {
+                  "array": [
+                    "u8",
+                    32
+                  ]
+                },
+                2
+              ]
+            }
+          },
+          {
+            "name": "sigPdas",
+            "type": {
+              "array": [
+                "pubkey",
+                2
+              ]
+            }
+          },
+          {
+            "name": "sigHashes",
+            "type": {
+              "array": [
+                {
+                  "array": [
+                    "u8",
+                    32
+                  ]
+                },
+                2
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "inputSource",
+      "docs": [
+        "Payload segment hashed into the STARK public inputs (seed, inc); prover and verifier must agree."
+      ],
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "cipher"
+          },
+          {
+            "name": "kem"
+          }
+        ]
+      }
+    },
+    {
+      "name": "nonceRegistry",
+      "docs": [
+        "Per-sender ring buffer of the last NONCE_WINDOW nonces accepted by finalize_sig."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "sender",
+            "type": "pubkey"
+          },
+          {
+            "name": "len",
+            "type": "u8"
+          },
+          {
+            "name": "head",
+            "type": "u8"
+          },
+          {
+            "name": "recent",
+            "type": {
+              "array": [
+                {
+                  "array": [
+                    "u8",
+                    12
+                  ]
+                },
+                64
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "pqIdentity",
+      "docs": [
+        "Owner's registered SLH-DSA verifying key (seeds: \"pqid\", owner)."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "owner",
+            "type": "pubkey"
+          },
+          {
+            "name": "vk",
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            "name": "registeredSlot",
+            "type": "u64"
+          },
+          {
+            "name": "rotatedAtSlot",
+            "type": "u64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "starkConfig",
+      "docs": [
+        "Trusted-relayer switch (seeds: \"config\"); only the upgrade authority can write it."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "admin",
+            "type": "pubkey"
+          },
+          {
+            "name": "relayer",
+            "type": "pubkey"
+          },
+          {
+            "name": "skipStark",
+            "type": "bool"
+          }
+        ]
+      }
+    },
+    {
+      "name": "uploadProgress",
+      "docs": [
+        "Buffer state after an upload, returned via set_return_data (4 + 32 = 36 bytes).",
+        "`length` is the next chunk's offset and `sha_chain` its prev value."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "length",
+            "type": "u32"
+          },
+          {
+            "name": "shaChain",
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          }
+        ]
+      }
     }
   ]
 };
@@ -0,0 +1,174 @@
+//! Post-finalize audit of the signature buffer.
+//!
+//! handle_audit_sig: re-hashes the sig buffer named by ChatMsg.sig_pda and compares it with ChatMsg.sig_hash
+//! and the caller's expected hash in constant time. Fails with SigHashMismatch if the buffer was reset,
+//! re-uploaded or otherwise changed since finalize_sig verified it.
+//!
+//! handle_audit_chatmsg: re-checks the ChatMsg's own length invariants (sig_len, nonce_len, sig_alg,
+//! cipher_len + kem_len against the payload, proof size, shard layout) and returns a ChatMsgAudit.
+//! Read-only: an inconsistency is reported in the result, not as an error, so clients can simulate it.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::state::{
+    check_sig_alg, BufferPda, ChatMsg, ErrorCode, BUF_HEAD, MAX_PROOF_BYTES, MAX_SHARDS, MAX_SIG_PAYLOAD, NONCE_LEN,
+    SIG_BYTES, XNONCE_LEN,
+};
+
+/// Accounts for auditing a ChatMsg against its signature buffer.
+#[derive(Accounts)]
+pub struct AuditSig<'info> {
+    pub chat_msg: Account<'info, ChatMsg>,
+
+    #[account(address = chat_msg.sig_pda)]
+    pub sigbuf: Account<'info, BufferPda>,
+}
+
+/// Checks that the signature buffer still holds the bytes finalize_sig verified.
+pub fn handle_audit_sig(ctx: Context<AuditSig>, expected_sig_hash: [u8; 32]) -> Result<()> {
+    let chat = &ctx.accounts.chat_msg;
+    let sig_ai = ctx.accounts.sigbuf.to_account_info();
+    let data = sig_ai.data.borrow();
+    audit_buffer(&data, ctx.accounts.sigbuf.length, chat, &expected_sig_hash)
+}
+
+/// Re-hashes the first `length` payload bytes of raw sig buffer data and matches them against the ChatMsg.
+pub(crate) fn audit_buffer(data: &[u8], length: u32, chat: &ChatMsg, expected: &[u8; 32]) -> Result<()> {
+    let len = length as usize;
+    require!(len == chat.sig_len as usize && len <= MAX_SIG_PAYLOAD, ErrorCode::SigHashMismatch);
+    let sig = data.get(BUF_HEAD..BUF_HEAD + len).ok_or(ErrorCode::SigHashMismatch)?;
+    let hash = hashv(&[sig]).to_bytes();
+    // Evaluate both comparisons so timing does not reveal which one failed.
+    let ok = ct_eq(&hash, &chat.sig_hash) & ct_eq(&hash, expected);
+    require!(ok, ErrorCode::SigHashMismatch);
+    Ok(())
+}
+
+/// Accounts for the ChatMsg integrity check.
+#[derive(Accounts)]
+pub struct AuditChatMsg<'info> {
+    pub chat_msg: Account<'info, ChatMsg>,
+}
+
+/// First internal inconsistency audit_chatmsg found in a ChatMsg.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatMsgIssue {
+    SigLenMismatch,        // sig_len != SIG_BYTES
+    NonceLenInvalid,       // nonce_len is neither NONCE_LEN nor XNONCE_LEN
+    SigAlgUnsupported,     // sig_alg names no parameter set this build verifies
+    LengthsOverrunPayload, // cipher_len + kem_len overflows or exceeds payload.len()
+    ProofTooLarge,         // payload.len() - cipher_len - kem_len > MAX_PROOF_BYTES
+    ShardLayoutInvalid,    // sharded with shard_count > MAX_SHARDS or a non-empty inline payload
+}
+
+/// Result of audit_chatmsg, returned via set_return_data.
+/// proof_len is payload.len() - cipher_len - kem_len (0 when sharded or when the lengths overrun).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChatMsgAudit {
+    pub payload_len: u32,
+    pub proof_len  : u32,
+    pub issue      : Option<ChatMsgIssue>,
+}
+
+/// Re-checks the ChatMsg's length invariants; never fails on an inconsistency, it reports it.
+pub fn handle_audit_chatmsg(ctx: Context<AuditChatMsg>) -> Result<ChatMsgAudit> {
+    let audit = audit_chat_msg(&ctx.accounts.chat_msg);
+    if let Some(issue) = audit.issue {
+        msg!("audit_chatmsg: {:?}", issue);
+    }
+    Ok(audit)
+}
+
+/// Checks the invariants finalize_sig establishes and verify_stark relies on, in that order.
+/// Works on a deserialized account, so clients can run it on RPC data without a transaction.
+pub fn audit_chat_msg(chat: &ChatMsg) -> ChatMsgAudit {
+    let mut audit = ChatMsgAudit { payload_len: chat.payload.len() as u32, proof_len: 0, issue: None };
+    audit.issue = if chat.sig_len as usize != SIG_BYTES {
+        Some(ChatMsgIssue::SigLenMismatch)
+    } else if !matches!(chat.nonce_len as usize, NONCE_LEN | XNONCE_LEN) {
+        Some(ChatMsgIssue::NonceLenInvalid)
+    } else if check_sig_alg(chat.sig_alg).is_err() {
+        Some(ChatMsgIssue::SigAlgUnsupported)
+    } else if chat.is_sharded() {
+        let ok = chat.shard_count as usize <= MAX_SHARDS && chat.payload.is_empty();
+        (!ok).then_some(ChatMsgIssue::ShardLayoutInvalid)
+    } else {
+        match chat.proof() {
+            Err(_) => Some(ChatMsgIssue::LengthsOverrunPayload),
+            Ok(proof) => {
+                audit.proof_len = proof.len() as u32;
+                (proof.len() > MAX_PROOF_BYTES).then_some(ChatMsgIssue::ProofTooLarge)
+            }
+        }
+    };
+    audit
+}
+
+/// Compares two digests without an early exit.
+fn ct_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_chat;
+
+    /// Lays out sig buffer account data (header then signature) and the ChatMsg finalize_sig would write.
+    fn finalized(sig: &[u8]) -> (Vec<u8>, ChatMsg) {
+        let data = [vec![0u8; BUF_HEAD], sig.to_vec()].concat();
+        let chat = ChatMsg { sig_len: sig.len() as u32, sig_hash: hashv(&[sig]).to_bytes(), ..test_chat(0, 0, vec![]) };
+        (data, chat)
+    }
+
+    /// A well-formed ChatMsg passes; each hand-corrupted field is reported as its own issue.
+    #[test]
+    fn chat_msg_invariants_audited() {
+        let sig = vec![7u8; SIG_BYTES];
+        let (_data, mut chat) = finalized(&sig);
+        chat.cipher_len = 3;
+        chat.kem_len = 4;
+        chat.payload = vec![0u8; 3 + 4 + 100];
+        assert_eq!(audit_chat_msg(&chat), ChatMsgAudit { payload_len: 107, proof_len: 100, issue: None });
+
+        let issue = |f: &dyn Fn(&mut ChatMsg)| {
+            let mut bad = chat.clone();
+            f(&mut bad);
+            audit_chat_msg(&bad).issue
+        };
+        assert_eq!(issue(&|c| c.sig_len -= 1), Some(ChatMsgIssue::SigLenMismatch));
+        assert_eq!(issue(&|c| c.nonce_len = 30), Some(ChatMsgIssue::NonceLenInvalid));
+        assert_eq!(issue(&|c| c.sig_alg = 0), Some(ChatMsgIssue::SigAlgUnsupported));
+        assert_eq!(issue(&|c| c.kem_len = 105), Some(ChatMsgIssue::LengthsOverrunPayload));
+        assert_eq!(issue(&|c| (c.cipher_len, c.kem_len) = (u32::MAX, u32::MAX)), Some(ChatMsgIssue::LengthsOverrunPayload));
+        assert_eq!(issue(&|c| c.payload.resize(7 + MAX_PROOF_BYTES + 1, 0)), Some(ChatMsgIssue::ProofTooLarge));
+        assert_eq!(issue(&|c| c.shard_count = 2), Some(ChatMsgIssue::ShardLayoutInvalid));
+        assert_eq!(issue(&|c| { c.shard_count = 2; c.payload.clear(); }), None);
+
+        // The result survives the return-data round trip.
+        let mut bad = chat.clone();
+        bad.kem_len = 105;
+        let audit = audit_chat_msg(&bad);
+        assert_eq!(audit.proof_len, 0);
+        let bytes = audit.try_to_vec().unwrap();
+        assert_eq!(ChatMsgAudit::try_from_slice(&bytes).unwrap(), audit);
+    }
+
+    /// An untouched buffer passes; a flipped byte, a truncated length or a wrong expected hash fails.
+    #[test]
+    fn mutated_sig_buffer_detected() {
+        let sig: Vec<u8> = (0..7_856u32).map(|i| i as u8).collect();
+        let (mut data, chat) = finalized(&sig);
+        let len = sig.len() as u32;
+        let expected = chat.sig_hash;
+        assert!(audit_buffer(&data, len, &chat, &expected).is_ok());
+
+        let mismatch: Error = ErrorCode::SigHashMismatch.into();
+        assert_eq!(audit_buffer(&data, len, &chat, &[0u8; 32]).unwrap_err(), mismatch);
+        assert_eq!(audit_buffer(&data, len - 1, &chat, &expected).unwrap_err(), mismatch);
+
+        data[BUF_HEAD + 100] ^= 1;
+        assert_eq!(audit_buffer(&data, len, &chat, &expected).unwrap_err(), mismatch);
+    }
+}
@@ -0,0 +1,73 @@
+//! Program config (StarkConfig PDA, seeds ["config"]) for the trusted-relayer mode.
+//!
+//! SetStarkConfig / handle_set_stark_config: only the program's upgrade authority may create or change it.
+//! With skip_stark on, finalize_sig accepts the configured relayer as a co-signer and records
+//! ChatMsg.relayer_attested = true: the relayer vouches that it verified the STARK off chain, and
+//! verify_stark does not need to run. This trades the on-chain proof check for trust in the relayer.
+
+use anchor_lang::prelude::*;
+use crate::{
+    program::StarkPqcVerifier,
+    state::{ErrorCode, StarkConfig, STARK_CONFIG_SPACE},
+};
+
+/// Accounts for setting the config; the signer must be the upgrade authority in the program's ProgramData.
+#[derive(Accounts)]
+pub struct SetStarkConfig<'info> {
+    #[account(
+        init_if_needed, payer=admin, space=STARK_CONFIG_SPACE,
+        seeds=[b"config"], bump
+    )]
+    pub config: Account<'info, StarkConfig>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, StarkPqcVerifier>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::ConfigAuthorityMismatch)]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Names the trusted relayer and turns skip_stark on or off.
+pub fn handle_set_stark_config(ctx: Context<SetStarkConfig>, relayer: Pubkey, skip_stark: bool) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin      = ctx.accounts.admin.key();
+    config.relayer    = relayer;
+    config.skip_stark = skip_stark;
+    Ok(())
+}
+
+/// Value finalize_sig stores in ChatMsg.relayer_attested.
+/// No relayer signer: false. A relayer signer is honored only if the config exists, has skip_stark on
+/// and names that relayer; otherwise RelayerNotAuthorized, so a relayer never believes it attested when it did not.
+pub(crate) fn relayer_attested(config: Option<&StarkConfig>, relayer: Option<Pubkey>) -> Result<bool> {
+    let Some(relayer) = relayer else { return Ok(false) };
+    let ok = config.is_some_and(|c| c.skip_stark && c.relayer == relayer);
+    require!(ok, ErrorCode::RelayerNotAuthorized);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The attestation is recorded only while the config enables skip_stark for that exact relayer.
+    #[test]
+    fn relayer_flag_honored_only_when_enabled() {
+        let relayer = Pubkey::new_unique();
+        let mut config = StarkConfig { admin: Pubkey::new_unique(), relayer, skip_stark: true };
+        let denied: Error = ErrorCode::RelayerNotAuthorized.into();
+
+        assert!(relayer_attested(Some(&config), Some(relayer)).unwrap());
+        assert_eq!(relayer_attested(Some(&config), Some(Pubkey::new_unique())).unwrap_err(), denied);
+        assert!(!relayer_attested(Some(&config), None).unwrap());
+
+        config.skip_stark = false;
+        assert_eq!(relayer_attested(Some(&config), Some(relayer)).unwrap_err(), denied);
+        assert_eq!(relayer_attested(None, Some(relayer)).unwrap_err(), denied);
+        assert!(!relayer_attested(None, None).unwrap());
+    }
+}
@@ -3,68 +3,514 @@
 //! AIR: affine counter x_{t+1} = x_t + inc, with x_0 = seed, x_last = seed + inc*(n-1).
 //! Public inputs (seed, inc) are derived from SHA-256(cipher) in finalize::handle_verify_stark.
 //! Security: AcceptableOptions::MinConjecturedSecurity(127) (≈128-bit).
-
-use anchor_lang::prelude::msg;
+//! Proof blobs carry a 1-byte hash-id prefix selecting the hasher (H, VC, RC) used for verification.
 
 // SLH-DSA re-export (SHA2-128s)
 pub use slh_dsa::onchain_sha2::verify_sha2_128s as verify;
+pub use slh_dsa::onchain_sha2::verify_sha2_128s_prehash as verify_prehash; // HashSLH-DSA, SHA-256 prehash
 pub use slh_dsa::onchain_sha2::SIG_LEN_128S     as SIG_LEN;
 
 // STARK verifier (Winterfell 0.12)
 use winterfell::{
-    verify as stark_verify, AcceptableOptions, Proof, ProofOptions, VerifierError,
-    crypto::{hashers::Sha2_256, DefaultRandomCoin, MerkleTree},
-    math::{fields::f128::BaseElement, FieldElement, ToElements},
-    Air, AirContext, EvaluationFrame, TransitionConstraintDegree, TraceInfo, Assertion,
+    verify as stark_verify, AcceptableOptions, Proof, VerifierError,
+    crypto::{DefaultRandomCoin, ElementHasher, MerkleTree},
+    math::fields::f128::BaseElement,
 };
 use winter_utils::{Deserializable, SliceReader};
+#[cfg(feature = "blake3-proofs")]
+use winterfell::crypto::hashers::Blake3_256;
+
+// AIR shared with crates/stark-prover (crates/stark-air), so the two cannot drift apart.
+pub use stark_air::{MessageAir, PublicInputs, NUM_ASSERTIONS};
+use stark_air::{H, TRACE_WIDTH};
+
+// Hash-id prefix byte written by the prover in front of the serialized proof.
+pub const HASH_ID_SHA2_256  : u8 = 0x01;
+pub const HASH_ID_BLAKE3_256: u8 = 0x02;
+
+/// Failure modes of the STARK verify entry point.
+#[derive(Debug)]
+pub enum StarkError {
+    /// Proof blob is empty (no hash-id byte).
+    Empty,
+    /// Proof was built with a hasher this program was not compiled with.
+    UnsupportedHash(u8),
+    /// Proof's main trace is not the single column MessageAir expects.
+    UnexpectedWidth(usize),
+    /// Proof's trace length differs from the length the caller pinned.
+    UnexpectedLength(usize),
+    /// Out-of-domain constraint evaluations disagree with the proof: the prover's AIR differs
+    /// (e.g. not NUM_ASSERTIONS boundary assertions) or the public inputs are not the ones it proved.
+    ConstraintMismatch,
+    /// SHA-256(seed_le || inc_le) differs from the commitment the caller was bound to.
+    CommitmentMismatch,
+    /// Winterfell rejected the proof.
+    Verifier(VerifierError),
+}
 
-type H  = Sha2_256<BaseElement>;
-type VC = MerkleTree<H>;
-type RC = DefaultRandomCoin<H>;
+impl StarkError {
+    /// Coarse failure class logged by verify_stark before it maps every failure to ProofFailed:
+    /// "format" (blob or trace shape), "deserialization" (bytes do not decode as a proof for this field),
+    /// "options" (proof options or security below the floor), "soundness" (the proof or its committed inputs do not check out).
+    pub fn category(&self) -> &'static str {
+        match self {
+            StarkError::Empty | StarkError::UnsupportedHash(_)
+            | StarkError::UnexpectedWidth(_) | StarkError::UnexpectedLength(_) => "format",
+            StarkError::Verifier(
+                VerifierError::ProofDeserializationError(_) | VerifierError::InconsistentBaseField,
+            ) => "deserialization",
+            StarkError::Verifier(
+                VerifierError::UnsupportedFieldExtension(_)
+                | VerifierError::InsufficientConjecturedSecurity(..)
+                | VerifierError::InsufficientProvenSecurity(..)
+                | VerifierError::UnacceptableProofOptions,
+            ) => "options",
+            StarkError::ConstraintMismatch | StarkError::CommitmentMismatch | StarkError::Verifier(_) => "soundness",
+        }
+    }
+}
 
-/// Public inputs for the AIR: (seed, inc) as base field elements.
-#[derive(Clone, Copy)]
-pub struct PublicInputs { pub seed: BaseElement, pub inc: BaseElement }
-impl ToElements<BaseElement> for PublicInputs {
-    fn to_elements(&self) -> Vec<BaseElement> { vec![self.seed, self.inc] }
+impl core::fmt::Display for StarkError {
+    /// "<category>: <detail>", with Winterfell's own message for verifier errors.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}: ", self.category())?;
+        match self {
+            StarkError::Empty => f.write_str("empty proof blob"),
+            StarkError::UnsupportedHash(id) => write!(f, "unsupported hash-id 0x{id:02x}"),
+            StarkError::UnexpectedWidth(w) => write!(f, "main trace width {w}, expected {TRACE_WIDTH}"),
+            StarkError::UnexpectedLength(n) => write!(f, "trace length {n}"),
+            StarkError::ConstraintMismatch => f.write_str("out-of-domain constraint evaluations disagree"),
+            StarkError::CommitmentMismatch => f.write_str("public inputs do not match the commitment"),
+            StarkError::Verifier(e) => write!(f, "{e}"),
+        }
+    }
 }
 
-/// Affine-counter AIR (x_{t+1} = x_t + inc; 2 boundary assertions).
-pub struct MessageAir { ctx: AirContext<BaseElement>, pi: PublicInputs }
-impl MessageAir {
-    fn new(info: TraceInfo, pi: PublicInputs, opts: ProofOptions) -> Self {
-        let deg = vec![TransitionConstraintDegree::new(1)];
-        let ctx = AirContext::new(info, deg, 2, opts);
-        Self { ctx, pi }
+/// Trace length the demo prover uses; the program pins proofs to it.
+pub const CANONICAL_TRACE_LEN: usize = 8;
+
+/// Typed STARK entry point: fixes <MessageAir, H, RC, VC> per hash-id, the minimum conjectured
+/// security and an optional trace-length pin, so integrators never spell the type parameters themselves.
+/// Stores the security bits rather than AcceptableOptions, which is neither Clone nor Debug.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StarkVerifier {
+    min_security_bits: u32,
+    expected_len: Option<usize>,
+}
+
+impl Default for StarkVerifier {
+    /// MinConjecturedSecurity(127) (≈128-bit), any trace length.
+    fn default() -> Self {
+        Self::new(127)
+    }
+}
+
+impl StarkVerifier {
+    /// Verifier accepting proofs with at least `min_security_bits` of conjectured security.
+    pub fn new(min_security_bits: u32) -> Self {
+        Self { min_security_bits, expected_len: None }
+    }
+
+    /// Refuses proofs whose trace length is not `len`.
+    pub fn with_trace_len(mut self, len: usize) -> Self {
+        self.expected_len = Some(len);
+        self
+    }
+
+    /// Verifies a hash-id prefixed proof for the above AIR against (seed, inc).
+    pub fn verify(&self, blob: &[u8], seed_u64: u64, inc_u64: u64) -> Result<(), StarkError> {
+        let (&hash_id, bytes) = blob.split_first().ok_or(StarkError::Empty)?;
+        match hash_id {
+            HASH_ID_SHA2_256 => self.verify_with::<H>(bytes, seed_u64, inc_u64),
+            #[cfg(feature = "blake3-proofs")]
+            HASH_ID_BLAKE3_256 => self.verify_with::<Blake3_256<BaseElement>>(bytes, seed_u64, inc_u64),
+            #[cfg(not(feature = "blake3-proofs"))]
+            HASH_ID_BLAKE3_256 => Err(StarkError::UnsupportedHash(hash_id)),
+            other => Err(StarkError::UnsupportedHash(other)),
+        }
+    }
+
+    // Verifies the raw proof bytes with the hasher selected by the hash-id.
+    fn verify_with<Hx>(&self, bytes: &[u8], seed_u64: u64, inc_u64: u64) -> Result<(), StarkError>
+    where
+        Hx: ElementHasher<BaseField = BaseElement>,
+    {
+        let proof = Proof::read_from(&mut SliceReader::new(bytes)).map_err(|e| {
+            StarkError::Verifier(VerifierError::ProofDeserializationError(format!("{e:?}")))
+        })?;
+        // MessageAir is a single-column trace; anything wider is refused before verification.
+        let width = proof.trace_info().main_trace_width();
+        if width != TRACE_WIDTH {
+            return Err(StarkError::UnexpectedWidth(width));
+        }
+        let trace_len = proof.trace_info().length();
+        if self.expected_len.is_some_and(|n| n != trace_len) {
+            return Err(StarkError::UnexpectedLength(trace_len));
+        }
+        dbg_msg!("DBG STARK(verify): degs=1 assertions={}", NUM_ASSERTIONS);
+        let opts = AcceptableOptions::MinConjecturedSecurity(self.min_security_bits);
+        let pi = PublicInputs::new(seed_u64, inc_u64);
+        stark_verify::<MessageAir, Hx, DefaultRandomCoin<Hx>, MerkleTree<Hx>>(proof, pi, &opts)
+            .map_err(|e| match e {
+                VerifierError::InconsistentOodConstraintEvaluations => StarkError::ConstraintMismatch,
+                e => StarkError::Verifier(e),
+            })
     }
 }
-impl Air for MessageAir {
-    type BaseField    = BaseElement;
-    type PublicInputs = PublicInputs;
-    fn new(i: TraceInfo, pi: PublicInputs, o: ProofOptions) -> Self { Self::new(i, pi, o) }
-    fn context(&self)->&AirContext<Self::BaseField>{&self.ctx}
-    fn evaluate_transition<E: FieldElement<BaseField = Self::BaseField>>(
-        &self, f: &EvaluationFrame<E>, _:&[E], r:&mut [E]
-    ){
-        let inc = E::from(self.pi.inc);
-        r[0] = f.next()[0] - f.current()[0] - inc;
-    }
-    fn get_assertions(&self)->Vec<winterfell::Assertion<Self::BaseField>>{
-        let last = self.trace_length() - 1;
-        vec![
-            Assertion::single(0, 0, self.pi.seed),
-            Assertion::single(0, last, self.pi.seed + (self.pi.inc * BaseElement::from(last as u64))),
-        ]
+
+/// Verifies a hash-id prefixed proof for the above AIR (≈128-bit via MinConjecturedSecurity(127)).
+/// Accepts whatever trace length the proof declares; see verify_stark_with_len to pin it.
+pub fn verify_stark(blob: &[u8], seed_u64: u64, inc_u64: u64) -> Result<(), StarkError> {
+    StarkVerifier::default().verify(blob, seed_u64, inc_u64)
+}
+
+/// Commitment to the public inputs for commit-reveal flows: SHA-256(seed_le || inc_le).
+pub fn inputs_commitment(seed_u64: u64, inc_u64: u64) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[&seed_u64.to_le_bytes(), &inc_u64.to_le_bytes()]).to_bytes()
+}
+
+/// Like verify_stark, but first checks the revealed (seed, inc) against a previously published commitment,
+/// so the proof is bound to inputs that did not have to appear in instruction data up front.
+pub fn verify_stark_committed(
+    blob: &[u8],
+    commitment: &[u8; 32],
+    seed_u64: u64,
+    inc_u64: u64,
+) -> Result<(), StarkError> {
+    if inputs_commitment(seed_u64, inc_u64) != *commitment {
+        return Err(StarkError::CommitmentMismatch);
     }
+    verify_stark(blob, seed_u64, inc_u64)
+}
+
+/// Like verify_stark, but refuses proofs whose trace length is not `expected_len`.
+pub fn verify_stark_with_len(
+    blob: &[u8],
+    seed_u64: u64,
+    inc_u64: u64,
+    expected_len: usize,
+) -> Result<(), StarkError> {
+    StarkVerifier::default().with_trace_len(expected_len).verify(blob, seed_u64, inc_u64)
+}
+
+/// Deterministic SHA2-128s key `n` for tests, seeded with bytes 3n+1, 3n+2 and 3n+3.
+#[cfg(test)]
+pub(crate) fn test_key(n: u8) -> slh_dsa::SigningKey<slh_dsa::Sha2_128s> {
+    let seed = |i: u8| [3 * n + i; 16];
+    slh_dsa::SigningKey::slh_keygen_internal(&seed(1), &seed(2), &seed(3))
 }
 
-/// Verifies a proof for the above AIR (≈128-bit via MinConjecturedSecurity(127)).
-pub fn verify_stark(bytes: &[u8], seed_u64: u64, inc_u64: u64) -> Result<(), VerifierError> {
-    let proof = Proof::read_from(&mut SliceReader::new(bytes))
-        .map_err(|e| VerifierError::ProofDeserializationError(format!("{e:?}")))?;
-    let opts = AcceptableOptions::MinConjecturedSecurity(127);
-    msg!("DBG STARK(verify): degs=1 assertions=2");
-    let pi = PublicInputs { seed: BaseElement::from(seed_u64), inc: BaseElement::from(inc_u64) };
-    stark_verify::<MessageAir, H, RC, VC>(proof, pi, &opts)
+/// The 32-byte public key of [`test_key`]`(n)`.
+#[cfg(test)]
+pub(crate) fn test_pub(n: u8) -> [u8; 32] {
+    use slh_dsa::signature::Keypair;
+    test_key(n).verifying_key().to_bytes().as_slice().try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rejects a proof whose hash-id this build does not support.
+    #[test]
+    fn unknown_hash_id_rejected() {
+        let blob = [0x7f, 0, 0, 0];
+        assert!(matches!(verify_stark(&blob, 0, 1), Err(StarkError::UnsupportedHash(0x7f))));
+        assert!(matches!(verify_stark(&[], 0, 1), Err(StarkError::Empty)));
+    }
+
+    /// Blake3 proofs are refused unless the program is built with `blake3-proofs`.
+    #[cfg(not(feature = "blake3-proofs"))]
+    #[test]
+    fn blake3_hash_id_rejected_without_feature() {
+        let blob = [HASH_ID_BLAKE3_256, 0, 0];
+        assert!(matches!(
+            verify_stark(&blob, 0, 1),
+            Err(StarkError::UnsupportedHash(HASH_ID_BLAKE3_256))
+        ));
+    }
+
+    /// A proof claiming a two-column main trace is refused before Winterfell runs.
+    #[test]
+    fn wider_trace_rejected() {
+        let (_params, mut blob) = stark_prover::generate_proof(&[5u8; 32]).unwrap();
+        assert_eq!(blob[1], 1); // TraceInfo starts with the main segment width
+        blob[1] = 2;
+        assert!(matches!(verify_stark(&blob, 0, 1), Err(StarkError::UnexpectedWidth(2))));
+    }
+
+    /// A length-16 proof is refused when the caller pins the canonical length 8.
+    #[test]
+    fn other_trace_length_rejected() {
+        let digest = [6u8; 32];
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        let opts = stark_prover::ProverOptions { trace_len: 16, ..Default::default() };
+        let (_params, blob) = stark_prover::generate_proof_with_options(&digest, opts).unwrap();
+        assert!(verify_stark(&blob, seed, inc).is_ok());
+        assert!(matches!(
+            verify_stark_with_len(&blob, seed, inc, CANONICAL_TRACE_LEN),
+            Err(StarkError::UnexpectedLength(16))
+        ));
+
+        let (_params, blob) = stark_prover::generate_proof(&digest).unwrap();
+        assert!(verify_stark_with_len(&blob, seed, inc, CANONICAL_TRACE_LEN).is_ok());
+    }
+
+    /// One StarkVerifier instance checks two proofs in a row.
+    #[test]
+    fn verifier_reused_for_two_proofs() {
+        let verifier = StarkVerifier::default().with_trace_len(CANONICAL_TRACE_LEN);
+        for digest in [[1u8; 32], [2u8; 32]] {
+            let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+            let (_params, blob) = stark_prover::generate_proof(&digest).unwrap();
+            assert!(verifier.verify(&blob, seed, inc).is_ok());
+            assert!(verifier.verify(&blob, seed, inc ^ 1).is_err());
+        }
+    }
+
+    /// Grinding 0 leaves 119 bits of conjectured security, below the on-chain 127-bit floor.
+    #[test]
+    fn low_grinding_rejected() {
+        let digest = [8u8; 32];
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        let opts = stark_prover::ProverOptions { grinding_factor: 0, ..Default::default() };
+        let (_params, blob) = stark_prover::generate_proof_with_options(&digest, opts).unwrap();
+        assert!(matches!(
+            verify_stark(&blob, seed, inc),
+            Err(StarkError::Verifier(VerifierError::InsufficientConjecturedSecurity(127, 119)))
+        ));
+    }
+
+    /// Every curated prover profile passes the on-chain floor at the canonical length; a weaker custom one does not.
+    #[test]
+    fn prover_profiles_checked_on_chain() {
+        use stark_prover::{generate_proof_profile, Profile, ProfileParams};
+        let digest = [10u8; 32];
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        for profile in Profile::CURATED {
+            let (_params, blob) = generate_proof_profile(&digest, profile).unwrap();
+            assert!(verify_stark_with_len(&blob, seed, inc, CANONICAL_TRACE_LEN).is_ok(), "{profile:?}");
+        }
+
+        let weak = Profile::Custom(ProfileParams { num_queries: 20, ..Profile::Demo128.params() });
+        let (_params, blob) = generate_proof_profile(&digest, weak).unwrap();
+        assert!(matches!(
+            verify_stark(&blob, seed, inc),
+            Err(StarkError::Verifier(VerifierError::InsufficientConjecturedSecurity(127, 87)))
+        ));
+    }
+
+    /// Minimal prover for an affine-counter AIR that asserts only x_0, to simulate prover/verifier drift.
+    mod one_assertion {
+        use super::*;
+        use winterfell::{
+            math::FieldElement, Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo,
+            TransitionConstraintDegree,
+        };
+        use winterfell::{
+            matrix::ColMatrix, AuxRandElements, BatchingMethod, CompositionPoly, CompositionPolyTrace,
+            ConstraintCompositionCoefficients, DefaultConstraintCommitment, DefaultConstraintEvaluator,
+            DefaultTraceLde, FieldExtension, PartitionOptions, Prover, StarkDomain, TracePolyTable, TraceTable,
+        };
+
+        pub struct OneAssertionAir { ctx: AirContext<BaseElement>, pi: PublicInputs }
+        impl Air for OneAssertionAir {
+            type BaseField = BaseElement;
+            type PublicInputs = PublicInputs;
+            fn new(i: TraceInfo, pi: PublicInputs, o: ProofOptions) -> Self {
+                Self { ctx: AirContext::new(i, vec![TransitionConstraintDegree::new(1)], 1, o), pi }
+            }
+            fn context(&self) -> &AirContext<BaseElement> { &self.ctx }
+            fn evaluate_transition<E: FieldElement<BaseField = BaseElement>>(
+                &self, f: &EvaluationFrame<E>, _: &[E], r: &mut [E]
+            ) {
+                r[0] = f.next()[0] - f.current()[0] - E::from(self.pi.inc);
+            }
+            fn get_assertions(&self) -> Vec<Assertion<BaseElement>> {
+                vec![Assertion::single(0, 0, self.pi.seed)]
+            }
+        }
+
+        struct OneAssertionProver { options: ProofOptions, pi: PublicInputs }
+        impl Prover for OneAssertionProver {
+            type BaseField = BaseElement;
+            type Air = OneAssertionAir;
+            type Trace = TraceTable<BaseElement>;
+            type HashFn = H;
+            type VC = MerkleTree<H>;
+            type RandomCoin = DefaultRandomCoin<H>;
+            type TraceLde<E: FieldElement<BaseField = BaseElement>> = DefaultTraceLde<E, H, MerkleTree<H>>;
+            type ConstraintCommitment<E: FieldElement<BaseField = BaseElement>> =
+                DefaultConstraintCommitment<E, H, MerkleTree<H>>;
+            type ConstraintEvaluator<'a, E: FieldElement<BaseField = BaseElement>> =
+                DefaultConstraintEvaluator<'a, OneAssertionAir, E>;
+
+            fn get_pub_inputs(&self, _: &Self::Trace) -> PublicInputs { self.pi }
+            fn options(&self) -> &ProofOptions { &self.options }
+            fn new_trace_lde<E: FieldElement<BaseField = BaseElement>>(
+                &self, info: &TraceInfo, main: &ColMatrix<BaseElement>, domain: &StarkDomain<BaseElement>,
+                part: PartitionOptions,
+            ) -> (Self::TraceLde<E>, TracePolyTable<E>) {
+                DefaultTraceLde::new(info, main, domain, part)
+            }
+            fn build_constraint_commitment<E: FieldElement<BaseField = BaseElement>>(
+                &self, trace: CompositionPolyTrace<E>, columns: usize, domain: &StarkDomain<BaseElement>,
+                part: PartitionOptions,
+            ) -> (Self::ConstraintCommitment<E>, CompositionPoly<E>) {
+                DefaultConstraintCommitment::new(trace, columns, domain, part)
+            }
+            fn new_evaluator<'a, E: FieldElement<BaseField = BaseElement>>(
+                &self, air: &'a OneAssertionAir, aux: Option<AuxRandElements<E>>,
+                coeffs: ConstraintCompositionCoefficients<E>,
+            ) -> Self::ConstraintEvaluator<'a, E> {
+                DefaultConstraintEvaluator::new(air, aux, coeffs)
+            }
+        }
+
+        /// Proves the canonical trace with only the x_0 assertion and returns a hash-id prefixed blob.
+        pub fn prove(seed_u64: u64, inc_u64: u64) -> Vec<u8> {
+            let pi = PublicInputs { seed: BaseElement::from(seed_u64), inc: BaseElement::from(inc_u64) };
+            let mut trace = TraceTable::new(TRACE_WIDTH, CANONICAL_TRACE_LEN);
+            trace.fill(|s| s[0] = pi.seed, |_, s| s[0] += pi.inc);
+            let options = ProofOptions::new(
+                30, 16, 8, FieldExtension::None, 4, 31, BatchingMethod::Linear, BatchingMethod::Linear,
+            );
+            let proof = OneAssertionProver { options, pi }.prove(trace).unwrap();
+            [vec![HASH_ID_SHA2_256], proof.to_bytes()].concat()
+        }
+    }
+
+    /// A proof from a 1-assertion AIR is reported as a constraint mismatch, not an opaque verifier error.
+    #[test]
+    fn assertion_count_drift_rejected() {
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&[3u8; 32]).unwrap();
+        let blob = one_assertion::prove(seed, inc);
+        assert!(matches!(verify_stark(&blob, seed, inc), Err(StarkError::ConstraintMismatch)));
+    }
+
+    /// The revealed inputs must open the commitment before the proof is checked; a matching pair verifies.
+    #[test]
+    fn committed_inputs_checked() {
+        let digest = [9u8; 32];
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        let (_params, blob) = stark_prover::generate_proof(&digest).unwrap();
+        let commitment = inputs_commitment(seed, inc);
+        let mut joined = seed.to_le_bytes().to_vec();
+        joined.extend_from_slice(&inc.to_le_bytes());
+        assert_eq!(commitment, anchor_lang::solana_program::hash::hash(&joined).to_bytes());
+
+        assert!(verify_stark_committed(&blob, &commitment, seed, inc).is_ok());
+        let other = inputs_commitment(seed, inc ^ 1);
+        assert!(matches!(verify_stark_committed(&blob, &other, seed, inc), Err(StarkError::CommitmentMismatch)));
+        let reveal_other = verify_stark_committed(&blob, &other, seed, inc ^ 1).unwrap_err();
+        assert_eq!(reveal_other.category(), "soundness"); // Opens `other`, but the proof is for (seed, inc)
+        assert_eq!(StarkError::CommitmentMismatch.category(), "soundness");
+    }
+
+    /// Deserialization, options and soundness failures log distinct strings.
+    #[test]
+    fn error_categories_distinguishable() {
+        let digest = [4u8; 32];
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        let (_params, blob) = stark_prover::generate_proof(&digest).unwrap();
+
+        let truncated = verify_stark(&blob[..blob.len() / 2], seed, inc).unwrap_err();
+        let opts = stark_prover::ProverOptions { grinding_factor: 0, ..Default::default() };
+        let (_params, weak) = stark_prover::generate_proof_with_options(&digest, opts).unwrap();
+        let insecure = verify_stark(&weak, seed, inc).unwrap_err();
+        let wrong_inputs = verify_stark(&blob, seed, inc ^ 1).unwrap_err();
+        let drifted = verify_stark(&one_assertion::prove(seed, inc), seed, inc).unwrap_err();
+
+        assert_eq!(truncated.category(), "deserialization");
+        assert_eq!(insecure.category(), "options");
+        assert_eq!((wrong_inputs.category(), drifted.category()), ("soundness", "soundness"));
+        assert_eq!(StarkError::Empty.category(), "format");
+
+        let logs = [truncated.to_string(), insecure.to_string(), drifted.to_string()];
+        assert!(logs[0].starts_with("deserialization: "));
+        assert!(logs[1].starts_with("options: ") && logs[1].contains("119"));
+        assert!(logs[2].starts_with("soundness: "));
+        assert_eq!(StarkError::UnsupportedHash(0x7f).to_string(), "format: unsupported hash-id 0x7f");
+    }
+
+    /// Heap profile of the canonical proof. Proof::read_from holds the whole proof, then verify adds its working set
+    /// on top; peak counts live bytes, total every allocation. The SBF bump allocator only frees the last allocation,
+    /// so its use lies between the two: the peak must fit 256 KiB, and with pure-bump (which never frees) the total too.
+    #[test]
+    fn proof_heap_profile() {
+        use crate::heap::meter;
+        use crate::state::MAX_HEAP_BYTES;
+        let segment = b"heap profile";
+        let (_params, blob) = stark_prover::generate_proof_for_segment(segment).unwrap();
+        let (seed, inc) = crate::finalize::derive_public_inputs(segment);
+
+        let (parsed, read) = meter::measure(|| Proof::read_from(&mut SliceReader::new(&blob[1..])));
+        assert!(parsed.is_ok());
+        let (res, full) = meter::measure(|| verify_stark_with_len(&blob, seed, inc, CANONICAL_TRACE_LEN));
+        assert!(res.is_ok());
+
+        assert!(0 < read.peak && read.peak <= read.total);
+        assert!(read.peak <= full.peak && full.peak <= full.total);
+        assert!(full.peak <= MAX_HEAP_BYTES, "verify peak {} B exceeds the heap frame", full.peak);
+        if cfg!(feature = "pure-bump") {
+            assert!(full.total <= MAX_HEAP_BYTES, "pure-bump verify needs {} B, over the heap frame", full.total);
+        }
+    }
+
+    /// verify_stark's heap high-water mark for the canonical proof, read from heap::snapshot() with the bump allocator
+    /// serving every allocation as on chain: the payload copy Anchor makes, then verify_payload. The mark must hold
+    /// that copy and exceed the live peak, and fit the 256 KiB frame verify_stark requests.
+    #[test]
+    fn proof_heap_high_water() {
+        use crate::heap::meter;
+        use crate::state::{InputSource, MAX_HEAP_BYTES};
+        let segment = b"heap profile";
+        let (_params, blob) = stark_prover::generate_proof_for_segment(segment).unwrap();
+        let stored = [&segment[..], &blob].concat();
+
+        let ((ok, high), usage) = meter::measure(|| {
+            meter::measure_bump(MAX_HEAP_BYTES, || {
+                let payload = stored.clone();
+                crate::finalize::verify_payload(&payload, segment.len() as u32, 0, InputSource::Cipher).is_ok()
+            })
+        });
+        assert!(ok);
+
+        // A bump heap that only frees its last allocation ends up between the live peak and everything ever allocated.
+        assert!(stored.len() < high && usage.peak < high);
+        assert!(high <= MAX_HEAP_BYTES, "high-water {} B exceeds the heap frame", high);
+    }
+
+    /// Length-64 probe for chunked verification: the proof verifies off chain, but verify_payload stops it at
+    /// MAX_PROOF_BYTES before deserializing, and the trace-length pin would refuse it as well. Verifying it needs more
+    /// heap than the canonical proof (read as in proof_heap_high_water) yet fits the 256 KiB frame, so heap is not
+    /// the limit. CU is not measured here; that needs an SBF build.
+    #[test]
+    fn length_64_probe() {
+        use crate::heap::meter;
+        use crate::state::{ErrorCode, InputSource, MAX_HEAP_BYTES, MAX_PROOF_BYTES};
+        let segment = b"length 64 probe";
+        let digest = anchor_lang::solana_program::hash::hash(segment).to_bytes();
+        let (seed, inc) = stark_prover::public_inputs_from_digest(&digest).unwrap();
+        let opts = stark_prover::ProverOptions { trace_len: 64, ..Default::default() };
+        let (_params, blob) = stark_prover::generate_proof_with_options(&digest, opts).unwrap();
+        let stored = [&segment[..], &blob].concat();
+
+        let (ok, high) = meter::measure_bump(MAX_HEAP_BYTES, || verify_stark(&blob, seed, inc).is_ok());
+        assert!(ok);
+        let err = crate::finalize::verify_payload(&stored, segment.len() as u32, 0, InputSource::Cipher).unwrap_err();
+        assert_eq!(err, ErrorCode::ProofFailed.into());
+
+        // Stop point: the size cap comes first; the pin alone would also refuse it.
+        assert!(blob.len() > MAX_PROOF_BYTES, "length-64 proof is {} B, within the cap", blob.len());
+        assert!(verify_stark_with_len(&blob, seed, inc, CANONICAL_TRACE_LEN).is_err());
+        assert!(verify_stark_with_len(&blob, seed, inc, 64).is_ok());
+
+        // Heap: more than the canonical proof needs, within the frame.
+        let (_params, canonical) = stark_prover::generate_proof_for_segment(segment).unwrap();
+        let (canon_ok, canon_high) = meter::measure_bump(MAX_HEAP_BYTES, || verify_stark(&canonical, seed, inc).is_ok());
+        assert!(canon_ok);
+        assert!(canon_high < high, "length 64 needs {high} B, canonical {canon_high} B");
+        assert!(high <= MAX_HEAP_BYTES, "length 64 needs {high} B, over the heap frame");
+    }
 }
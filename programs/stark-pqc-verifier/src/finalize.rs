@@ -2,27 +2,44 @@
 //!
 //! Step 1: handle_finalize_sig
 //! Reads body (cipher|kem|proof) from buf.
-//! Verifies SLH-DSA (SHA2-128s) over cipher||kem||nonce||slot_le.
+//! Verifies SLH-DSA (SHA2-128s) over cipher||kem||sender||recipient||nonce||nonce_len||slot_le||msg_type_le||sig_alg
+//! (nonce 12 or 24 bytes).
+//! sig_alg must name a parameter set this build verifies (SIG_ALG_SHA2_128S); ChatMsg records it.
+//! Rejects a nonce already present in the sender's NonceRegistry window.
+//! Once the sender has registered a PqIdentity, slh_pub must be its current key.
 //! Persists ChatMsg with metadata and sig_hash (tamper-evidence). Auto-closes the body buffer (close = payer).
 //!
+//! If the configured relayer co-signs while StarkConfig.skip_stark is on, ChatMsg.relayer_attested is set.
+//!
+//! handle_finalize_sig_prehash: same, but the signature is HashSLH-DSA over SHA-256 of that message.
+//! handle_finalize_sig_multi: same, but requires up to MAX_COSIGNERS signatures over the same message and records
+//! every signer in a CoSigners account. Each co-signer passes its PqIdentity and its own sig PDA, so every key is a
+//! registered one and every signature sits in a buffer only that identity's owner could have written.
+//! handle_finalize_sig_sharded: same checks, but cipher||kem in the signed message is replaced by
+//! shard_commitment(shard_root, shard_count, cipher_len, kem_len); the payload is stored later by store_shard.
+//!
 //! Step 2: handle_verify_stark
-//! Derives public inputs from SHA-256(cipher) → (seed, inc).
-//! Verifies the Winterfell STARK proof for the affine-counter AIR.
+//! Applies the caller's heap limit (no more than the transaction's requestHeapFrame, read from the instructions
+//! sysvar, and clamped to 256 KiB).
+//! Reassembles a sharded payload from the ChatShards passed as remaining accounts (root re-checked).
+//! Derives public inputs from SHA-256(cipher) (or SHA-256(kem) with InputSource::Kem) → (seed, inc).
+//! Dispatches on the proof's hash-id byte and verifies the Winterfell STARK proof for the affine-counter AIR.
 
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::{hash::hashv, sysvar::instructions as ix_sysvar};
 
 use crate::{
     state::{
-        BufferPda, ChatMsg, BUF_HEAD, CHAT_HEAD, MAX_CHAT_PAYLOAD, MAX_SIG_PAYLOAD, SIG_BYTES,
-        ErrorCode,
+        chat_account_space, check_nonce_len, check_sig_alg, split_payload, BufferPda, ChatMsg, ChatShard, CoSigners, NonceRegistry, PqIdentity, StarkConfig, BUF_HEAD, CO_SIGNERS_SPACE, MAX_CHAT_PAYLOAD,
+        MAX_PROOF_BYTES, MAX_SIG_PAYLOAD, MAX_COSIGNERS, MAX_HEAP_BYTES, MAX_SHARDS, MIN_HEAP_BYTES, NONCE_REGISTRY_SPACE, SIG_BYTES, ErrorCode,
+        InputSource,
     },
-    crypto,
+    config, crypto, identity, shard,
 };
 
 /// Accounts for Step 1 (signature finalization).
 #[derive(Accounts)]
-#[instruction(cipher_len: u32, kem_len: u32, nonce: [u8; 12], slot: u64, slh_pub: [u8; 32])]
+#[instruction(cipher_len: u32, kem_len: u32, nonce: Vec<u8>, slot: u64, slh_pub: [u8; 32])]
 pub struct FinalizeSig<'info> {
     #[account(
         mut,
@@ -32,50 +49,109 @@ pub struct FinalizeSig<'info> {
     )]
     pub buffer: Account<'info, BufferPda>,
 
+    /// CHECK: address fixed by seeds; read_sig checks owner and discriminator so a missing buffer gets SigBufferMissing.
     #[account(
         mut,
         seeds=[b"sig", payer.key().as_ref(), recipient.key().as_ref(), &slot.to_le_bytes()],
         bump
     )]
-    pub sigbuf: Account<'info, BufferPda>,
+    pub sigbuf: UncheckedAccount<'info>,
 
     #[account(
         init_if_needed, payer=payer,
-        space=8 + CHAT_HEAD + buffer.length as usize,
+        space=chat_account_space(buffer.length as usize),
         seeds=[b"msg", payer.key().as_ref(), recipient.key().as_ref(), &slot.to_le_bytes()],
         bump
     )]
     pub chat_msg: Account<'info, ChatMsg>,
-    /// CHECK: recipient is app-layer identity; enforced via seeds only.
+
+    #[account(
+        init_if_needed, payer=payer, space=NONCE_REGISTRY_SPACE,
+        seeds=[b"nonce", payer.key().as_ref()], bump
+    )]
+    pub nonce_registry: Account<'info, NonceRegistry>,
+
+    /// CHECK: recipient is app-layer identity; it seeds the PDAs and is part of the signed message,
+    /// so a body signed for one recipient fails SigFailed here under another.
     pub recipient: UncheckedAccount<'info>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: address fixed by seeds so the sender cannot omit it; check_registered_key requires slh_pub to be
+    /// the registered key once this account exists.
+    #[account(seeds=[b"pqid", payer.key().as_ref()], bump)]
+    pub identity: UncheckedAccount<'info>,
+
+    /// Trusted-relayer config; only read when `relayer` signs.
+    #[account(seeds=[b"config"], bump)]
+    pub config: Option<Account<'info, StarkConfig>>,
+
+    /// Relayer attesting that it verified the STARK off chain; honored only if config enables it.
+    pub relayer: Option<Signer<'info>>,
 }
 
 #[allow(clippy::too_many_arguments)]
 /// Verifies SLH-DSA and persists a ChatMsg.
-/// Reads body & signature from PDAs, verifies cipher||kem||nonce||slot_le,
-/// then stores ChatMsg with sig_hash for tamper evidence.
+/// Reads body & signature from PDAs, verifies cipher||kem||sender||recipient||nonce||nonce_len||slot_le||msg_type_le||sig_alg,
+/// then stores ChatMsg with msg_type, sig_alg and sig_hash for tamper evidence.
 pub fn handle_finalize_sig(
     ctx       : Context<FinalizeSig>,
     cipher_len: u32,
     kem_len   : u32,
-    nonce     : [u8; 12],
+    nonce     : Vec<u8>,
     slot      : u64,
     slh_pub   : [u8; 32],
+    msg_type  : u16,
+    sig_alg   : u8,
 ) -> Result<()> {
+    finalize_single(ctx, cipher_len, kem_len, nonce, slot, slh_pub, msg_type, sig_alg, false)
+}
+
+#[allow(clippy::too_many_arguments)]
+/// finalize_sig for a HashSLH-DSA (SHA-256 prehash) signature over the same signed message.
+/// Lets signers that only see a digest (hardware, remote signers) sign large messages.
+pub fn handle_finalize_sig_prehash(
+    ctx       : Context<FinalizeSig>,
+    cipher_len: u32,
+    kem_len   : u32,
+    nonce     : Vec<u8>,
+    slot      : u64,
+    slh_pub   : [u8; 32],
+    msg_type  : u16,
+    sig_alg   : u8,
+) -> Result<()> {
+    finalize_single(ctx, cipher_len, kem_len, nonce, slot, slh_pub, msg_type, sig_alg, true)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn finalize_single(
+    ctx       : Context<FinalizeSig>,
+    cipher_len: u32,
+    kem_len   : u32,
+    nonce     : Vec<u8>,
+    slot      : u64,
+    slh_pub   : [u8; 32],
+    msg_type  : u16,
+    sig_alg   : u8,
+    prehash   : bool,
+) -> Result<()> {
+    let sig = read_sig(&ctx.accounts.sigbuf)?;
+    check_nonce_len(&nonce)?;
+    check_sig_alg(sig_alg)?;
     let total = ctx.accounts.buffer.length as usize;
     require!(total <= MAX_CHAT_PAYLOAD, ErrorCode::LenMismatch);
+    require!(!ctx.accounts.nonce_registry.contains(&nonce), ErrorCode::NonceReused);
+    identity::check_registered_key(&ctx.accounts.identity, &slh_pub)?;
+    let relayer = ctx.accounts.relayer.as_ref().map(|r| r.key());
+    let attested = config::relayer_attested(ctx.accounts.config.as_deref(), relayer)?;
 
-    let proof_len_est = total
-        .saturating_sub(cipher_len as usize)
-        .saturating_sub(kem_len as usize);
-    msg!(
+    dbg_msg!(
         "DBG step1: slot={} total={} (cipher={} kem={} proof≈{}) need_space={}",
-        slot, total, cipher_len, kem_len, proof_len_est,
-        8 + CHAT_HEAD + total
+        slot, total, cipher_len, kem_len,
+        total.saturating_sub(cipher_len as usize).saturating_sub(kem_len as usize),
+        chat_account_space(total)
     );
 
     let body = {
@@ -84,72 +160,977 @@ pub fn handle_finalize_sig(
         data[BUF_HEAD..BUF_HEAD + total].to_vec()
     };
 
-    let sig_len = ctx.accounts.sigbuf.length as usize;
-    // SLH-DSA/SHA2-128s signature is fixed 7,856 bytes; must also fit PDA payload cap.
+    let (cipher, kem, _proof) = split_payload(&body, cipher_len, kem_len)?;
+    let blob = signed_message(&body[..cipher.len() + kem.len()], &ctx.accounts.payer.key(), &ctx.accounts.recipient.key(), &nonce, slot, msg_type, sig_alg);
+    verify_signed(&blob, &sig, &slh_pub, prehash)?;
+
+    let reg = &mut ctx.accounts.nonce_registry;
+    reg.sender = ctx.accounts.payer.key();
+    reg.check_and_record(&nonce)?;
+
+    let chat = &mut ctx.accounts.chat_msg;
+    chat.sender     = ctx.accounts.payer.key();
+    chat.recipient  = ctx.accounts.recipient.key();
+    chat.cipher_len = cipher_len;
+    chat.kem_len    = kem_len;
+    chat.set_nonce(&nonce);
+    chat.slot       = slot;
+    chat.msg_type   = msg_type;
+    chat.sig_pda    = ctx.accounts.sigbuf.key();
+    chat.sig_len    = sig.len() as u32;
+    chat.sig_hash   = hashv(&[&sig[..]]).to_bytes();
+    chat.shard_count = 0;
+    chat.shard_root = [0u8; 32];
+    chat.relayer_attested = attested;
+    chat.sig_alg    = sig_alg;
+    chat.payload    = body;
+    if attested {
+        msg!("STARK not verified on chain: relayer-attested by {}", relayer.unwrap_or_default());
+    }
+    Ok(())
+}
+
+// Copies the signature out of a sig buffer.
+// SigBufferMissing if init_signature or upload_signature never ran (no account, or nothing uploaded).
+// SLH-DSA/SHA2-128s signature is fixed 7,856 bytes; must also fit PDA payload cap.
+pub(crate) fn read_sig(sig_ai: &AccountInfo) -> Result<Vec<u8>> {
+    require!(!sig_ai.data_is_empty() && *sig_ai.owner == crate::ID, ErrorCode::SigBufferMissing);
+    let data = sig_ai.data.borrow();
+    let buf  = BufferPda::try_deserialize(&mut &data[..])?; // Discriminator checked
+    require!(buf.length != 0, ErrorCode::SigBufferMissing);
+    let sig_len = buf.length as usize;
     require!(sig_len == SIG_BYTES && sig_len <= MAX_SIG_PAYLOAD, ErrorCode::SigFailed);
-    let sig = {
-        let sig_ai = ctx.accounts.sigbuf.to_account_info();
-        let data   = sig_ai.data.borrow();
-        data[BUF_HEAD..BUF_HEAD + sig_len].to_vec()
+    Ok(data[BUF_HEAD..BUF_HEAD + sig_len].to_vec())
+}
+
+/// Accounts for finalize_sig_multi; each co-signer's (PqIdentity, sig buffer) pair follows as remaining accounts.
+#[derive(Accounts)]
+#[instruction(cipher_len: u32, kem_len: u32, nonce: Vec<u8>, slot: u64)]
+pub struct FinalizeSigMulti<'info> {
+    #[account(
+        mut,
+        seeds=[b"buf", payer.key().as_ref()],
+        bump,
+        close = payer
+    )]
+    pub buffer: Account<'info, BufferPda>,
+
+    /// CHECK: address fixed by seeds; read_sig checks owner and discriminator so a missing buffer gets SigBufferMissing.
+    #[account(
+        mut,
+        seeds=[b"sig", payer.key().as_ref(), recipient.key().as_ref(), &slot.to_le_bytes()],
+        bump
+    )]
+    pub sigbuf: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed, payer=payer,
+        space=chat_account_space(buffer.length as usize),
+        seeds=[b"msg", payer.key().as_ref(), recipient.key().as_ref(), &slot.to_le_bytes()],
+        bump
+    )]
+    pub chat_msg: Account<'info, ChatMsg>,
+
+    #[account(
+        init_if_needed, payer=payer, space=NONCE_REGISTRY_SPACE,
+        seeds=[b"nonce", payer.key().as_ref()], bump
+    )]
+    pub nonce_registry: Account<'info, NonceRegistry>,
+
+    /// CHECK: recipient is app-layer identity; it seeds the PDAs and is part of the signed message,
+    /// so a body signed for one recipient fails SigFailed here under another.
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: address fixed by seeds so the sender cannot omit it; check_registered_key requires slh_pubs[0] to be
+    /// the registered key once this account exists.
+    #[account(seeds=[b"pqid", payer.key().as_ref()], bump)]
+    pub identity: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed, payer=payer, space=CO_SIGNERS_SPACE,
+        seeds=[b"cosig", chat_msg.key().as_ref()], bump
+    )]
+    pub cosigners: Account<'info, CoSigners>,
+}
+
+#[allow(clippy::too_many_arguments)]
+/// Verifies up to MAX_COSIGNERS SLH-DSA signatures over the same message and persists a ChatMsg.
+/// slh_pubs[0] signs the payer's sig buffer and must be the payer's registered key. For i ≥ 1, remaining_accounts
+/// holds slh_pubs[i]'s PqIdentity and then its sig buffer (see read_cosigner_sig).
+/// ChatMsg records the payer's signature as sig_pda/sig_hash; CoSigners records every key, sig buffer and hash.
+pub fn handle_finalize_sig_multi<'info>(
+    ctx       : Context<'_, '_, 'info, 'info, FinalizeSigMulti<'info>>,
+    cipher_len: u32,
+    kem_len   : u32,
+    nonce     : Vec<u8>,
+    slot      : u64,
+    slh_pubs  : Vec<[u8; 32]>,
+    msg_type  : u16,
+    sig_alg   : u8,
+) -> Result<()> {
+    // Bounded before any remaining account is read.
+    require!(
+        (1..=MAX_COSIGNERS).contains(&slh_pubs.len()) && ctx.remaining_accounts.len() == 2 * (slh_pubs.len() - 1),
+        ErrorCode::SignerCountInvalid
+    );
+    let recipient = ctx.accounts.recipient.key();
+    let mut sigs = vec![read_sig(&ctx.accounts.sigbuf)?];
+    let mut sig_pdas = vec![ctx.accounts.sigbuf.key()];
+    for (pair, pk) in ctx.remaining_accounts.chunks_exact(2).zip(&slh_pubs[1..]) {
+        sigs.push(read_cosigner_sig(&pair[0], &pair[1], pk, &recipient, slot)?);
+        sig_pdas.push(pair[1].key());
+    }
+    check_nonce_len(&nonce)?;
+    check_sig_alg(sig_alg)?;
+    let total = ctx.accounts.buffer.length as usize;
+    require!(total <= MAX_CHAT_PAYLOAD, ErrorCode::LenMismatch);
+    require!(!ctx.accounts.nonce_registry.contains(&nonce), ErrorCode::NonceReused);
+    identity::check_registered_key(&ctx.accounts.identity, &slh_pubs[0])?;
+
+    let body = {
+        let buf_ai = ctx.accounts.buffer.to_account_info();
+        let data   = buf_ai.data.borrow();
+        data[BUF_HEAD..BUF_HEAD + total].to_vec()
     };
 
-    let blob = [
-        &body[..cipher_len as usize + kem_len as usize],
-        &nonce,
-        &slot.to_le_bytes(),
-    ]
-    .concat();
-    crypto::verify(&blob, &sig, &slh_pub).map_err(|_| ErrorCode::SigFailed)?;
+    let (cipher, kem, _proof) = split_payload(&body, cipher_len, kem_len)?;
+    let blob = signed_message(&body[..cipher.len() + kem.len()], &ctx.accounts.payer.key(), &ctx.accounts.recipient.key(), &nonce, slot, msg_type, sig_alg);
+    verify_all(&blob, &sigs, &slh_pubs)?;
+
+    let signers: Vec<_> = slh_pubs.iter().zip(sig_pdas).zip(&sigs)
+        .map(|((pk, sig_pda), sig)| (*pk, sig_pda, hashv(&[&sig[..]]).to_bytes()))
+        .collect();
+    let chat_key = ctx.accounts.chat_msg.key();
+    ctx.accounts.cosigners.record(chat_key, &signers);
+
+    let reg = &mut ctx.accounts.nonce_registry;
+    reg.sender = ctx.accounts.payer.key();
+    reg.check_and_record(&nonce)?;
 
     let chat = &mut ctx.accounts.chat_msg;
     chat.sender     = ctx.accounts.payer.key();
     chat.recipient  = ctx.accounts.recipient.key();
     chat.cipher_len = cipher_len;
     chat.kem_len    = kem_len;
-    chat.nonce      = nonce;
+    chat.set_nonce(&nonce);
     chat.slot       = slot;
+    chat.msg_type   = msg_type;
     chat.sig_pda    = ctx.accounts.sigbuf.key();
-    chat.sig_len    = sig_len as u32;
-    chat.sig_hash   = hashv(&[&sig[..]]).to_bytes();
+    chat.sig_len    = sigs[0].len() as u32;
+    chat.sig_hash   = hashv(&[&sigs[0][..]]).to_bytes();
+    chat.shard_count = 0;
+    chat.shard_root = [0u8; 32];
+    chat.relayer_attested = false;
+    chat.sig_alg    = sig_alg;
     chat.payload    = body;
     Ok(())
 }
 
-/// Accounts for Step 2 (STARK verification).
+/// Reads a co-signer's signature, bound to a registered key: `identity` must be a PqIdentity whose current key is
+/// slh_pub, and `sig_ai` must be the sig PDA its owner creates for this recipient and slot. Only register_pq_key
+/// writes a PqIdentity, under the owner's own ["pqid", owner] seeds, and only that owner uploads to its sig PDA.
+pub(crate) fn read_cosigner_sig(
+    identity : &AccountInfo,
+    sig_ai   : &AccountInfo,
+    slh_pub  : &[u8; 32],
+    recipient: &Pubkey,
+    slot     : u64,
+) -> Result<Vec<u8>> {
+    require!(!identity.data_is_empty() && *identity.owner == crate::ID, ErrorCode::CoSignerUnbound);
+    let id = PqIdentity::try_deserialize(&mut &identity.data.borrow()[..])?;
+    id.check_key(slh_pub)?;
+    let (sig_pda, _) = Pubkey::find_program_address(
+        &[b"sig", id.owner.as_ref(), recipient.as_ref(), &slot.to_le_bytes()],
+        &crate::ID,
+    );
+    require_keys_eq!(sig_ai.key(), sig_pda, ErrorCode::CoSignerUnbound);
+    read_sig(sig_ai)
+}
+
+/// Accounts for finalize_sig_sharded; no body buffer, the payload arrives later through store_shard.
+#[derive(Accounts)]
+#[instruction(cipher_len: u32, kem_len: u32, nonce: Vec<u8>, slot: u64)]
+pub struct FinalizeSigSharded<'info> {
+    /// CHECK: address fixed by seeds; read_sig checks owner and discriminator so a missing buffer gets SigBufferMissing.
+    #[account(
+        mut,
+        seeds=[b"sig", payer.key().as_ref(), recipient.key().as_ref(), &slot.to_le_bytes()],
+        bump
+    )]
+    pub sigbuf: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed, payer=payer,
+        space=chat_account_space(0),
+        seeds=[b"msg", payer.key().as_ref(), recipient.key().as_ref(), &slot.to_le_bytes()],
+        bump
+    )]
+    pub chat_msg: Account<'info, ChatMsg>,
+
+    #[account(
+        init_if_needed, payer=payer, space=NONCE_REGISTRY_SPACE,
+        seeds=[b"nonce", payer.key().as_ref()], bump
+    )]
+    pub nonce_registry: Account<'info, NonceRegistry>,
+
+    /// CHECK: recipient is app-layer identity; it seeds the PDAs and is part of the signed message,
+    /// so a body signed for one recipient fails SigFailed here under another.
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: address fixed by seeds so the sender cannot omit it; check_registered_key requires slh_pub to be
+    /// the registered key once this account exists.
+    #[account(seeds=[b"pqid", payer.key().as_ref()], bump)]
+    pub identity: UncheckedAccount<'info>,
+}
+
+#[allow(clippy::too_many_arguments)]
+/// Verifies SLH-DSA over shard_commitment(..)||sender||recipient||nonce||nonce_len||slot_le||msg_type_le||sig_alg
+/// and persists a ChatMsg with an empty payload, shard_count (1..=MAX_SHARDS) and shard_root. store_shard then fills the shards.
+/// cipher_len + kem_len must fit shard_count * MAX_SHARD_DATA, or LenMismatch.
+pub fn handle_finalize_sig_sharded(
+    ctx        : Context<FinalizeSigSharded>,
+    cipher_len : u32,
+    kem_len    : u32,
+    nonce      : Vec<u8>,
+    slot       : u64,
+    slh_pub    : [u8; 32],
+    msg_type   : u16,
+    sig_alg    : u8,
+    shard_count: u16,
+    shard_root : [u8; 32],
+) -> Result<()> {
+    let sig = read_sig(&ctx.accounts.sigbuf)?;
+    check_nonce_len(&nonce)?;
+    check_sig_alg(sig_alg)?;
+    require!((1..=MAX_SHARDS).contains(&(shard_count as usize)), ErrorCode::ShardInvalid);
+    shard::check_sharded_lens(cipher_len, kem_len, shard_count)?;
+    require!(!ctx.accounts.nonce_registry.contains(&nonce), ErrorCode::NonceReused);
+    identity::check_registered_key(&ctx.accounts.identity, &slh_pub)?;
+
+    let commitment = shard::shard_commitment(&shard_root, shard_count, cipher_len, kem_len);
+    let blob = signed_message(&commitment, &ctx.accounts.payer.key(), &ctx.accounts.recipient.key(), &nonce, slot, msg_type, sig_alg);
+    verify_signed(&blob, &sig, &slh_pub, false)?;
+
+    let reg = &mut ctx.accounts.nonce_registry;
+    reg.sender = ctx.accounts.payer.key();
+    reg.check_and_record(&nonce)?;
+
+    let chat = &mut ctx.accounts.chat_msg;
+    chat.sender     = ctx.accounts.payer.key();
+    chat.recipient  = ctx.accounts.recipient.key();
+    chat.cipher_len = cipher_len;
+    chat.kem_len    = kem_len;
+    chat.set_nonce(&nonce);
+    chat.slot       = slot;
+    chat.msg_type   = msg_type;
+    chat.sig_pda    = ctx.accounts.sigbuf.key();
+    chat.sig_len    = sig.len() as u32;
+    chat.sig_hash   = hashv(&[&sig[..]]).to_bytes();
+    chat.shard_count = shard_count;
+    chat.shard_root = shard_root;
+    chat.relayer_attested = false;
+    chat.sig_alg    = sig_alg;
+    chat.payload    = Vec::new();
+    Ok(())
+}
+
+/// Requires every signature to verify over the same blob under its own key.
+/// The blob never includes the signer set, so the order of (key, signature) pairs cannot change what is signed;
+/// keys must be distinct so one signature cannot be counted twice.
+pub(crate) fn verify_all<S: AsRef<[u8]>>(blob: &[u8], sigs: &[S], pubs: &[[u8; 32]]) -> Result<()> {
+    require!(
+        (1..=MAX_COSIGNERS).contains(&pubs.len()) && sigs.len() == pubs.len(),
+        ErrorCode::SignerCountInvalid
+    );
+    for (i, pk) in pubs.iter().enumerate() {
+        require!(!pubs[..i].contains(pk), ErrorCode::SignerCountInvalid);
+    }
+    for (sig, pk) in sigs.iter().zip(pubs) {
+        crypto::verify(blob, sig.as_ref(), pk).map_err(|_| ErrorCode::SigFailed)?;
+    }
+    Ok(())
+}
+
+/// Verifies a pure signature over blob, or with `prehash` a HashSLH-DSA signature over SHA-256(blob).
+/// The prehash path still hashes the blob once (85 + len/2 CU), but H_msg then only absorbs 45 bytes.
+pub(crate) fn verify_signed(blob: &[u8], sig: &[u8], pk: &[u8; 32], prehash: bool) -> Result<()> {
+    let res = if prehash {
+        crypto::verify_prehash(&hashv(&[blob]).to_bytes(), sig, pk)
+    } else {
+        crypto::verify(blob, sig, pk)
+    };
+    res.map_err(|_| ErrorCode::SigFailed.into())
+}
+
+/// Builds the SLH-DSA signed message:
+/// cipher || kem || sender || recipient || nonce || nonce_len(u8) || slot_le || msg_type_le || sig_alg(u8).
+/// Signing sender and recipient keeps a signed body from being finalized to another recipient's PDAs.
+/// The length byte sits after the nonce, at a fixed distance from the end, so a 24-byte nonce
+/// cannot be re-read as 12 bytes of cipher_kem followed by a 12-byte nonce.
+/// Signing sig_alg keeps a reader from being pointed at another parameter set's verifier.
+pub fn signed_message(
+    cipher_kem: &[u8],
+    sender    : &Pubkey,
+    recipient : &Pubkey,
+    nonce     : &[u8],
+    slot      : u64,
+    msg_type  : u16,
+    sig_alg   : u8,
+) -> Vec<u8> {
+    [
+        cipher_kem, sender.as_ref(), recipient.as_ref(), nonce, &[nonce.len() as u8],
+        &slot.to_le_bytes(), &msg_type.to_le_bytes(), &[sig_alg],
+    ].concat()
+}
+
+/// Accounts for Step 2 (STARK verification); a sharded ChatMsg's shards follow as remaining accounts.
 #[derive(Accounts)]
 pub struct VerifyStark<'info> {
     pub chat_msg: Account<'info, ChatMsg>,
+
+    /// CHECK: instructions sysvar (address checked); read for the transaction's requestHeapFrame.
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
 }
 
-/// Verifies the STARK proof embedded in ChatMsg.
-pub fn handle_verify_stark(ctx: Context<VerifyStark>) -> Result<()> {
-    // Sets the heap limit to match the transaction's requestHeapFrame (e.g. 256 KiB).
-    #[cfg(all(feature = "custom-heap", any(target_arch = "bpf", target_os = "solana")))]
-    { crate::heap::set_heap_limit_bytes(256 * 1024); msg!("DBG Heap: 256KiB"); }
+/// Verifies the STARK proof embedded in ChatMsg (or reassembled from its shards) against the selected input source.
+pub fn handle_verify_stark<'info>(
+    ctx       : Context<'_, '_, 'info, 'info, VerifyStark<'info>>,
+    source    : InputSource,
+    heap_bytes: u32,
+) -> Result<()> {
+    // Sets the heap limit; it may not exceed the transaction's requestHeapFrame.
+    let frame = requested_heap_frame(&ctx.accounts.instructions)?;
+    #[allow(unused_variables)] // Only read with verbose-logs
+    let limit = apply_heap_limit(heap_bytes, frame)?;
+    dbg_msg!("DBG Heap: {}KiB (frame {}KiB)", limit / 1024, frame / 1024);
 
     let chat = &ctx.accounts.chat_msg;
-    let cipher_end = chat.cipher_len as usize;
-    let total_len = chat.payload.len();
-    require!(cipher_end <= total_len, ErrorCode::ProofFailed);
+    if !chat.is_sharded() {
+        return verify_payload(&chat.payload, chat.cipher_len, chat.kem_len, source);
+    }
+    // Account::try_from checks owner and discriminator; reassemble checks chat_msg, order and root.
+    let shards = ctx.remaining_accounts
+        .iter()
+        .map(|ai| Account::<ChatShard>::try_from(ai).map(Account::into_inner))
+        .collect::<Result<Vec<_>>>()?;
+    let payload = shard::reassemble(&chat.key(), chat, &shards)?;
+    verify_payload(&payload, chat.cipher_len, chat.kem_len, source)
+}
+
+/// ComputeBudget program; RequestHeapFrame is its instruction tag 1 followed by a u32 LE byte count.
+const COMPUTE_BUDGET_ID: Pubkey = pubkey!("ComputeBudget111111111111111111111111111111");
+const REQUEST_HEAP_FRAME_TAG: u8 = 1;
+
+/// Heap frame a ComputeBudget instruction requests, if it is a RequestHeapFrame.
+pub(crate) fn heap_frame_of(program_id: &Pubkey, data: &[u8]) -> Option<usize> {
+    match data {
+        [REQUEST_HEAP_FRAME_TAG, b0, b1, b2, b3] if *program_id == COMPUTE_BUDGET_ID => {
+            Some(u32::from_le_bytes([*b0, *b1, *b2, *b3]) as usize)
+        }
+        _ => None,
+    }
+}
 
-    let off = cipher_end + chat.kem_len as usize;
-    require!(off <= total_len, ErrorCode::ProofFailed);
+/// Heap frame the current transaction requested, read from the instructions sysvar.
+/// Without a RequestHeapFrame instruction the runtime gives the default 32 KiB.
+pub(crate) fn requested_heap_frame(ixs: &AccountInfo) -> Result<usize> {
+    let mut i = 0;
+    loop {
+        let ix = match ix_sysvar::load_instruction_at_checked(i, ixs) {
+            Ok(ix) => ix,
+            Err(ProgramError::InvalidArgument) => return Ok(MIN_HEAP_BYTES), // Past the last instruction
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(frame) = heap_frame_of(&ix.program_id, &ix.data) {
+            return Ok(frame);
+        }
+        i += 1;
+    }
+}
 
-    let proof = &chat.payload[off..];
-    let cipher = &chat.payload[..cipher_end];
+/// Validates a requested heap limit against the transaction's heap frame and clamps it to MAX_HEAP_BYTES.
+/// requestHeapFrame only accepts 1024-byte multiples from 32 KiB, so anything else cannot match it;
+/// a limit above the frame would let the allocator hand out memory the runtime never mapped.
+// `%` rather than is_multiple_of: the SBF platform-tools rustc predates it.
+#[allow(clippy::manual_is_multiple_of)]
+pub(crate) fn heap_limit_bytes(requested: u32, frame: usize) -> Result<usize> {
+    let bytes = requested as usize;
+    require!(bytes % 1024 == 0 && bytes >= MIN_HEAP_BYTES, ErrorCode::HeapLimitInvalid);
+    require!(bytes <= frame, ErrorCode::HeapFrameExceeded);
+    Ok(bytes.min(MAX_HEAP_BYTES))
+}
 
-    msg!("DBG STARK: cipher_len={} kem_len={} proof_len={}",
-        chat.cipher_len, chat.kem_len, proof.len());
+/// Validates heap_bytes against the frame and hands the limit to the allocator.
+pub(crate) fn apply_heap_limit(heap_bytes: u32, frame: usize) -> Result<usize> {
+    let limit = heap_limit_bytes(heap_bytes, frame)?;
+    #[cfg(any(test, all(feature = "custom-heap", any(target_arch = "bpf", target_os = "solana"))))]
+    crate::heap::set_heap_limit_bytes(limit);
+    Ok(limit)
+}
 
-    let digest = hashv(&[cipher]).to_bytes();
+/// Derives (seed, inc) = (LE_u64(d[0..8]), LE_u64(d[8..16])) with d = SHA-256(segment).
+/// sol_sha256 caps the slice count (20,000), not the byte count, and prices 85 + len/2 CU per slice,
+/// so one slice covers any segment a ChatMsg can hold (≤ MAX_CHAT_PAYLOAD, ≈ 5.1k CU); no chunking is needed.
+pub fn derive_public_inputs(segment: &[u8]) -> (u64, u64) {
+    let digest = hashv(&[segment]).to_bytes();
     let mut le0 = [0u8; 8];
     let mut le1 = [0u8; 8];
     le0.copy_from_slice(&digest[0..8]);
     le1.copy_from_slice(&digest[8..16]);
-    let seed = u64::from_le_bytes(le0);
-    let inc = u64::from_le_bytes(le1);
+    (u64::from_le_bytes(le0), u64::from_le_bytes(le1))
+}
+
+/// Verifies the proof in a cipher || kem || proof payload; public inputs come from `source`.
+/// Lengths that overflow or overrun the payload (e.g. a crafted ChatMsg) fail with ProofFailed.
+/// Proofs over MAX_PROOF_BYTES fail with ProofFailed before deserialization touches the heap.
+pub(crate) fn verify_payload(
+    payload   : &[u8],
+    cipher_len: u32,
+    kem_len   : u32,
+    source    : InputSource,
+) -> Result<()> {
+    let (cipher, kem, proof) =
+        split_payload(payload, cipher_len, kem_len).map_err(|_| ErrorCode::ProofFailed)?;
+    require!(proof.len() <= MAX_PROOF_BYTES, ErrorCode::ProofFailed);
+    let segment = match source {
+        InputSource::Cipher => cipher,
+        InputSource::Kem    => kem,
+    };
 
-    crate::crypto::verify_stark(proof, seed, inc)
-        .map_err(|_| ErrorCode::ProofFailed)?;
+    dbg_msg!("DBG STARK: cipher_len={} kem_len={} proof_len={} source={:?}",
+        cipher_len, kem_len, proof.len(), source);
+
+    let (seed, inc) = derive_public_inputs(segment);
+    crypto::verify_stark_with_len(proof, seed, inc, crypto::CANONICAL_TRACE_LEN).map_err(|e| match e {
+        crypto::StarkError::UnsupportedHash(id) => {
+            msg!("STARK: unsupported proof hash-id 0x{:02x}", id);
+            ErrorCode::UnsupportedHashId
+        }
+        crypto::StarkError::ConstraintMismatch => {
+            msg!(
+                "STARK: constraint evaluations disagree; the verifier AIR has {} boundary assertions \
+                 (prover AIR drift or wrong public inputs)",
+                crypto::NUM_ASSERTIONS
+            );
+            ErrorCode::ProofFailed
+        }
+        e => {
+            msg!("STARK: {}", e);
+            ErrorCode::ProofFailed
+        }
+    })?;
+    #[cfg(all(feature = "custom-heap", any(target_arch = "bpf", target_os = "solana")))]
+    dbg_msg!("DBG Heap used: {}B after STARK verify (proof {}B)", crate::heap::used_bytes(), proof.len());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{test_chat, CHAT_HEAD, DISC_SIZE, SIG_ALG_SHA2_128S};
+    use crate::test_accounts::{account_data, empty_account, leak_info, program_account};
+    use slh_dsa::signature::Signer as _;
+
+    fn payload(cipher: &[u8], kem: &[u8], proof: &[u8]) -> Vec<u8> {
+        [cipher, kem, proof].concat()
+    }
+
+    /// A proof bound to the KEM ciphertext verifies only under InputSource::Kem.
+    #[test]
+    fn kem_source_selects_kem_segment() {
+        let (cipher, kem) = (b"cipher bytes".to_vec(), vec![0x42u8; 1088]);
+        let (_params, proof) = stark_prover::generate_proof_for_segment(&kem).unwrap();
+        let p = payload(&cipher, &kem, &proof);
+        let (cl, kl) = (cipher.len() as u32, kem.len() as u32);
+
+        assert!(verify_payload(&p, cl, kl, InputSource::Kem).is_ok());
+        let err = verify_payload(&p, cl, kl, InputSource::Cipher).unwrap_err();
+        assert_eq!(err, ErrorCode::ProofFailed.into());
+    }
+
+    /// Instructions sysvar data for a transaction made of `ixs` (program id, data).
+    fn sysvar_data(ixs: &[(Pubkey, Vec<u8>)]) -> Vec<u8> {
+        let borrowed: Vec<_> = ixs
+            .iter()
+            .map(|(program_id, data)| ix_sysvar::BorrowedInstruction { program_id, accounts: vec![], data })
+            .collect();
+        ix_sysvar::construct_instructions_data(&borrowed)
+    }
+
+    fn frame_of(ixs: &[(Pubkey, Vec<u8>)]) -> Result<usize> {
+        requested_heap_frame(leak_info(ix_sysvar::ID, false, Pubkey::default(), sysvar_data(ixs)))
+    }
+
+    /// The frame comes from the transaction's RequestHeapFrame instruction, or is 32 KiB without one.
+    #[test]
+    fn heap_frame_read_from_sysvar() {
+        let heap_frame = |n: u32| (COMPUTE_BUDGET_ID, [&[REQUEST_HEAP_FRAME_TAG][..], &n.to_le_bytes()].concat());
+        let set_cu_limit = (COMPUTE_BUDGET_ID, [&[2u8][..], &400_000u32.to_le_bytes()].concat());
+        let verify = (crate::ID, vec![0u8; 9]);
+
+        assert_eq!(frame_of(&[set_cu_limit.clone(), heap_frame(256 * 1024), verify.clone()]).unwrap(), 256 * 1024);
+        assert_eq!(frame_of(&[set_cu_limit, verify.clone()]).unwrap(), MIN_HEAP_BYTES);
+        // The same bytes under another program id are not a heap frame request.
+        assert_eq!(frame_of(&[(crate::ID, heap_frame(64 * 1024).1), verify]).unwrap(), MIN_HEAP_BYTES);
+    }
+
+    /// The requested limit reaches the allocator, clamped to 256 KiB; malformed values and
+    /// values above the transaction's frame are refused and leave the allocator's limit alone.
+    #[test]
+    fn heap_limit_applied_and_clamped() {
+        let _guard = crate::heap::HOST_HEAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let applied = || {
+            let (start, _, limit) = crate::heap::snapshot();
+            limit - start
+        };
+
+        assert_eq!(apply_heap_limit(64 * 1024, 64 * 1024).unwrap(), 64 * 1024);
+        assert_eq!(applied(), 64 * 1024);
+        assert_eq!(apply_heap_limit(256 * 1024, 256 * 1024).unwrap(), MAX_HEAP_BYTES);
+        assert_eq!(applied(), MAX_HEAP_BYTES);
+        assert_eq!(apply_heap_limit(512 * 1024, 512 * 1024).unwrap(), MAX_HEAP_BYTES);
+        assert_eq!(applied(), MAX_HEAP_BYTES);
+
+        apply_heap_limit(MIN_HEAP_BYTES as u32, MIN_HEAP_BYTES).unwrap();
+        for bad in [0u32, 16 * 1024, 64 * 1024 + 1] {
+            assert_eq!(apply_heap_limit(bad, MAX_HEAP_BYTES).unwrap_err(), ErrorCode::HeapLimitInvalid.into());
+        }
+        assert_eq!(apply_heap_limit(64 * 1024, 32 * 1024).unwrap_err(), ErrorCode::HeapFrameExceeded.into());
+        assert_eq!(applied(), MIN_HEAP_BYTES);
+    }
+
+    /// A ChatMsg whose recorded lengths overrun or overflow its payload fails cleanly, without panicking.
+    #[test]
+    fn oversized_lengths_rejected() {
+        let (_params, proof) = stark_prover::generate_proof_for_segment(b"c").unwrap();
+        let body = payload(b"c", b"k", &proof);
+        for (cl, kl) in [(body.len() as u32 + 1, 0), (1, body.len() as u32), (u32::MAX, u32::MAX)] {
+            let chat = test_chat(cl, kl, body.clone());
+            let err = verify_payload(&chat.payload, chat.cipher_len, chat.kem_len, InputSource::Cipher).unwrap_err();
+            assert_eq!(err, ErrorCode::ProofFailed.into());
+        }
+        assert!(verify_payload(&body, 1, 1, InputSource::Cipher).is_ok());
+    }
+
+    /// A proof bound to cipher B does not verify against a ChatMsg storing cipher A.
+    #[test]
+    fn proof_for_other_cipher_rejected() {
+        let (cipher_a, cipher_b, kem) = (b"cipher A".to_vec(), b"cipher B".to_vec(), vec![0x11u8; 1088]);
+        let (_params, proof_b) = stark_prover::generate_proof_for_segment(&cipher_b).unwrap();
+        let (cl, kl) = (cipher_a.len() as u32, kem.len() as u32);
+
+        let err = verify_payload(&payload(&cipher_a, &kem, &proof_b), cl, kl, InputSource::Cipher).unwrap_err();
+        assert_eq!(err, ErrorCode::ProofFailed.into());
+        assert!(verify_payload(&payload(&cipher_b, &kem, &proof_b), cl, kl, InputSource::Cipher).is_ok());
+    }
+
+    /// The canonical proof fits under MAX_PROOF_BYTES; a larger blob is refused before the hash-id dispatch.
+    #[test]
+    fn oversized_proof_rejected_before_deserialize() {
+        let (cipher, kem) = (b"c".to_vec(), vec![0u8; 1088]);
+        let (_params, proof) = stark_prover::generate_proof_for_segment(&cipher).unwrap();
+        assert!(proof.len() <= MAX_PROOF_BYTES, "canonical proof is {} bytes", proof.len());
+        let (cl, kl) = (cipher.len() as u32, kem.len() as u32);
+
+        // An unknown hash id is reported as UnsupportedHashId only once the size check has passed.
+        let small = [vec![0x7f], vec![0u8; 100]].concat();
+        let err = verify_payload(&payload(&cipher, &kem, &small), cl, kl, InputSource::Cipher).unwrap_err();
+        assert_eq!(err, ErrorCode::UnsupportedHashId.into());
+        let large = [vec![0x7f], vec![0u8; MAX_PROOF_BYTES]].concat();
+        let err = verify_payload(&payload(&cipher, &kem, &large), cl, kl, InputSource::Cipher).unwrap_err();
+        assert_eq!(err, ErrorCode::ProofFailed.into());
+    }
+
+    /// All co-signatures over the same message pass in either order; a tampered one or a repeated key fails.
+    #[test]
+    fn multi_sig_requires_every_signature() {
+        let keys = [crypto::test_key(0), crypto::test_key(1)];
+        let blob = signed_message(b"cipher||kem", &PAYER, &RECIPIENT, &[9u8; 12], 42, 0, SIG_ALG_SHA2_128S);
+        let sigs: Vec<Vec<u8>> = keys.iter().map(|k| k.sign(&blob).to_bytes().to_vec()).collect();
+        let pubs = [crypto::test_pub(0), crypto::test_pub(1)];
+
+        assert!(verify_all(&blob, &sigs, &pubs).is_ok());
+        let (rsigs, rpubs) = ([sigs[1].clone(), sigs[0].clone()], [pubs[1], pubs[0]]);
+        assert!(verify_all(&blob, &rsigs, &rpubs).is_ok());
+        assert!(verify_all(&blob, &sigs[..1], &pubs[..1]).is_ok());
+
+        let mut tampered = sigs.clone();
+        tampered[1][100] ^= 1;
+        assert_eq!(verify_all(&blob, &tampered, &pubs).unwrap_err(), ErrorCode::SigFailed.into());
+        assert_eq!(verify_all(&blob, &[&sigs[1], &sigs[0]], &pubs).unwrap_err(), ErrorCode::SigFailed.into());
+
+        let count: Error = ErrorCode::SignerCountInvalid.into();
+        assert_eq!(verify_all(&blob, &[&sigs[0], &sigs[0]], &[pubs[0], pubs[0]]).unwrap_err(), count);
+        assert_eq!(verify_all(&blob, &sigs[..1], &pubs).unwrap_err(), count);
+        assert_eq!(verify_all::<Vec<u8>>(&blob, &[], &[]).unwrap_err(), count);
+    }
+
+    /// finalize without init_signature or without any uploaded chunk fails with SigBufferMissing, not a framework error.
+    #[test]
+    fn missing_sig_buffer_reported() {
+        use crate::state::BUF_ACCOUNT_SPACE;
+        let missing: Error = ErrorCode::SigBufferMissing.into();
+
+        // Never initialized: the seeds address holds no data and belongs to the system program.
+        assert_eq!(read_sig(empty_account(Pubkey::new_unique())).unwrap_err(), missing);
+
+        // Initialized by init_signature, nothing uploaded.
+        let mut data = vec![0u8; BUF_ACCOUNT_SPACE];
+        data[..8].copy_from_slice(&BufferPda::DISCRIMINATOR[..8]);
+        assert_eq!(read_sig(program_account(data.clone())).unwrap_err(), missing);
+
+        // Fully uploaded: the signature bytes come back.
+        data[8 + 32..8 + 36].copy_from_slice(&(SIG_BYTES as u32).to_le_bytes());
+        data[BUF_HEAD..BUF_HEAD + SIG_BYTES].fill(7);
+        assert_eq!(read_sig(program_account(data)).unwrap(), vec![7u8; SIG_BYTES]);
+    }
+
+    fn uploaded(bytes: &[u8]) -> Vec<u8> {
+        let head = BufferPda { sender: Pubkey::default(), length: bytes.len() as u32, sha_chain: [0u8; 32] };
+        account_data(&head, bytes)
+    }
+
+    const NONCE: [u8; 12] = [9u8; 12];
+    const SLOT: u64 = 42;
+    const PAYER: Pubkey = Pubkey::new_from_array([0xa1; 32]);
+    const RECIPIENT: Pubkey = Pubkey::new_from_array([0xb2; 32]);
+
+    /// A 6-byte cipher, 32-byte kem and 8-byte stand-in proof, with the message finalize signs over them.
+    fn signed_body(sender: &Pubkey, recipient: &Pubkey) -> (Vec<u8>, Vec<u8>) {
+        let body = [&b"cipher"[..], &[0x42u8; 32], &[0u8; 8]].concat();
+        let blob = signed_message(&body[..38], sender, recipient, &NONCE, SLOT, 0, SIG_ALG_SHA2_128S);
+        (body, blob)
+    }
+
+    /// buffer, sigbuf (holding `sig`), chat_msg, nonce_registry, recipient, payer, system_program and identity for
+    /// PAYER sending to RECIPIENT, with PAYER's identity registered to `registered` (None: never registered).
+    fn finalize_infos(registered: Option<[u8; 32]>, sig: &[u8]) -> [&'static AccountInfo<'static>; 8] {
+        use crate::state::{PqIdentity, NONCE_LEN, NONCE_WINDOW};
+        let sys = anchor_lang::system_program::ID;
+        let registry = NonceRegistry { sender: Pubkey::default(), len: 0, head: 0, recent: [[0u8; NONCE_LEN]; NONCE_WINDOW] };
+        let identity = match registered {
+            Some(vk) => program_account(account_data(&PqIdentity { owner: PAYER, vk, registered_slot: 1, rotated_at_slot: 0 }, &[])),
+            None => empty_account(Pubkey::new_unique()),
+        };
+        [
+            program_account(uploaded(&signed_body(&PAYER, &RECIPIENT).0)),
+            program_account(uploaded(sig)),
+            program_account(account_data(&test_chat(0, 0, vec![]), &[])),
+            program_account(account_data(&registry, &[])),
+            empty_account(RECIPIENT),
+            leak_info(PAYER, true, sys, vec![]),
+            leak_info(sys, false, sys, vec![]),
+            identity,
+        ]
+    }
+
+    /// Runs finalize_sig from PAYER to RECIPIENT on the body signed by test key 0 and returns the ChatMsg the handler wrote.
+    fn finalize_signed(registered: Option<[u8; 32]>) -> Result<ChatMsg> {
+        finalize_signed_for(registered, &PAYER, &RECIPIENT)
+    }
+
+    /// finalize_signed, with the signature made over `sender` and `recipient` instead of the accounts passed.
+    fn finalize_signed_for(registered: Option<[u8; 32]>, signed_sender: &Pubkey, signed_recipient: &Pubkey) -> Result<ChatMsg> {
+        let sig = crypto::test_key(0).sign(&signed_body(signed_sender, signed_recipient).1).to_bytes();
+        let [buffer, sigbuf, chat_msg, nonce_registry, recipient, payer, system_program, identity] = finalize_infos(registered, &sig);
+        let mut accounts = FinalizeSig {
+            buffer: Account::try_from(buffer)?,
+            sigbuf: UncheckedAccount::try_from(sigbuf),
+            chat_msg: Account::try_from(chat_msg)?,
+            nonce_registry: Account::try_from(nonce_registry)?,
+            recipient: UncheckedAccount::try_from(recipient),
+            payer: Signer::try_from(payer)?,
+            system_program: Program::try_from(system_program)?,
+            identity: UncheckedAccount::try_from(identity),
+            config: None,
+            relayer: None,
+        };
+        let ctx = Context::new(&crate::ID, &mut accounts, &[], FinalizeSigBumps::default());
+        handle_finalize_sig(ctx, 6, 32, NONCE.to_vec(), SLOT, crypto::test_pub(0), 0, SIG_ALG_SHA2_128S)?;
+        assert_eq!(accounts.chat_msg.sig_hash, hashv(&[&sig[..]]).to_bytes());
+        Ok(accounts.chat_msg.into_inner())
+    }
+
+    const COSIGNER: Pubkey = Pubkey::new_from_array([0xc3; 32]);
+
+    /// COSIGNER's sig PDA for RECIPIENT and SLOT, the only buffer finalize_sig_multi reads its signature from.
+    fn cosigner_sig_pda() -> Pubkey {
+        Pubkey::find_program_address(&[b"sig", COSIGNER.as_ref(), RECIPIENT.as_ref(), &SLOT.to_le_bytes()], &crate::ID).0
+    }
+
+    /// A co-signer's remaining accounts: COSIGNER's identity registered to `vk` (None: never registered),
+    /// then a buffer holding `sig` at `sig_at`.
+    fn cosigner_accounts(vk: Option<[u8; 32]>, sig_at: Pubkey, sig: &[u8]) -> &'static [AccountInfo<'static>] {
+        let identity = match vk {
+            Some(vk) => program_account(account_data(&PqIdentity { owner: COSIGNER, vk, registered_slot: 1, rotated_at_slot: 0 }, &[])),
+            None => empty_account(Pubkey::new_unique()),
+        };
+        let buf = leak_info(sig_at, false, crate::ID, uploaded(sig));
+        Box::leak(Box::new([identity.clone(), buf.clone()]))
+    }
+
+    /// Runs finalize_sig_multi with test keys 0 (payer's sig buffer) and 1 (COSIGNER, registered and using its sig PDA).
+    fn finalize_multi_signed(registered: Option<[u8; 32]>) -> Result<(ChatMsg, CoSigners)> {
+        let pubs = vec![crypto::test_pub(0), crypto::test_pub(1)];
+        finalize_multi_with(registered, pubs, |sig| cosigner_accounts(Some(crypto::test_pub(1)), cosigner_sig_pda(), sig))
+    }
+
+    /// finalize_sig_multi over `pubs`, with the remaining accounts built by `remaining` from key 1's signature.
+    fn finalize_multi_with(
+        registered: Option<[u8; 32]>,
+        pubs: Vec<[u8; 32]>,
+        remaining: impl FnOnce(&[u8]) -> &'static [AccountInfo<'static>],
+    ) -> Result<(ChatMsg, CoSigners)> {
+        let blob = signed_body(&PAYER, &RECIPIENT).1;
+        let sigs = [crypto::test_key(0).sign(&blob).to_bytes(), crypto::test_key(1).sign(&blob).to_bytes()];
+        let [buffer, sigbuf, chat_msg, nonce_registry, recipient, payer, system_program, identity] = finalize_infos(registered, &sigs[0]);
+        let empty = CoSigners {
+            chat_msg: Pubkey::default(), count: 0, keys: [[0u8; 32]; MAX_COSIGNERS],
+            sig_pdas: [Pubkey::default(); MAX_COSIGNERS], sig_hashes: [[0u8; 32]; MAX_COSIGNERS],
+        };
+        let cosigners = program_account(account_data(&empty, &[]));
+        let cosig = remaining(&sigs[1]);
+        let mut accounts = FinalizeSigMulti {
+            buffer: Account::try_from(buffer)?,
+            sigbuf: UncheckedAccount::try_from(sigbuf),
+            chat_msg: Account::try_from(chat_msg)?,
+            nonce_registry: Account::try_from(nonce_registry)?,
+            recipient: UncheckedAccount::try_from(recipient),
+            payer: Signer::try_from(payer)?,
+            system_program: Program::try_from(system_program)?,
+            identity: UncheckedAccount::try_from(identity),
+            cosigners: Account::try_from(cosigners)?,
+        };
+        let ctx = Context::new(&crate::ID, &mut accounts, cosig, FinalizeSigMultiBumps::default());
+        handle_finalize_sig_multi(ctx, 6, 32, NONCE.to_vec(), SLOT, pubs, 0, SIG_ALG_SHA2_128S)?;
+
+        let record = accounts.cosigners.clone().into_inner();
+        assert_eq!(record.chat_msg, chat_msg.key());
+        assert_eq!(record.sig_pdas, [sigbuf.key(), cosig[1].key()]);
+        assert_eq!(record.sig_hashes, sigs.map(|s| hashv(&[&s[..]]).to_bytes()));
+        Ok((accounts.chat_msg.into_inner(), record))
+    }
+
+    /// finalize_sig accepts any key before the sender registers, then only the registered key: after a rotation
+    /// to key 1, a message signed with the old key 0 is refused.
+    #[test]
+    fn finalize_enforces_registered_identity() {
+        let chat = finalize_signed(None).unwrap();
+        assert_eq!((chat.cipher_len, chat.kem_len, chat.payload.len()), (6, 32, 46));
+        assert!(finalize_signed(Some(crypto::test_pub(0))).is_ok());
+        assert_eq!(finalize_signed(Some(crypto::test_pub(1))).err(), Some(ErrorCode::IdentityKeyMismatch.into()));
+    }
+
+    /// A body signed for another recipient, or by another sender, fails at finalize: the PDAs' parties are signed.
+    #[test]
+    fn finalize_rejects_other_parties() {
+        let chat = finalize_signed(None).unwrap();
+        assert_eq!((chat.sender, chat.recipient), (PAYER, RECIPIENT));
+        let failed = Some(ErrorCode::SigFailed.into());
+        let other = Pubkey::new_unique();
+        assert_eq!(finalize_signed_for(None, &PAYER, &other).err(), failed);
+        assert_eq!(finalize_signed_for(None, &other, &RECIPIENT).err(), failed);
+        assert_eq!(finalize_signed_for(None, &RECIPIENT, &PAYER).err(), failed);
+    }
+
+    /// finalize_sig_multi stores every co-signer's key, sig buffer and signature hash, and holds slh_pubs[0]
+    /// to the payer's registered key like finalize_sig.
+    #[test]
+    fn finalize_multi_records_cosigners_and_identity() {
+        let (chat, record) = finalize_multi_signed(None).unwrap();
+        assert_eq!(record.count, 2);
+        assert_eq!(record.keys, [crypto::test_pub(0), crypto::test_pub(1)]);
+        assert_eq!((chat.sig_pda, chat.sig_hash), (record.sig_pdas[0], record.sig_hashes[0]));
+        assert!(finalize_multi_signed(Some(crypto::test_pub(0))).is_ok());
+        assert_eq!(finalize_multi_signed(Some(crypto::test_pub(1))).err(), Some(ErrorCode::IdentityKeyMismatch.into()));
+    }
+
+    /// A co-signer counts only through a registered PqIdentity holding its key and that owner's own sig PDA:
+    /// a throwaway key, a buffer at any other address or another registered key is refused. The signer count is
+    /// checked before any remaining account is read.
+    #[test]
+    fn finalize_multi_binds_cosigners() {
+        let (pubs, sig_pda) = (vec![crypto::test_pub(0), crypto::test_pub(1)], cosigner_sig_pda());
+        let unbound = Some(ErrorCode::CoSignerUnbound.into());
+        assert_eq!(finalize_multi_with(None, pubs.clone(), |sig| cosigner_accounts(None, sig_pda, sig)).err(), unbound);
+        let elsewhere = |sig: &[u8]| cosigner_accounts(Some(crypto::test_pub(1)), Pubkey::new_unique(), sig);
+        assert_eq!(finalize_multi_with(None, pubs.clone(), elsewhere).err(), unbound);
+        let other_key = |sig: &[u8]| cosigner_accounts(Some(crypto::test_pub(2)), sig_pda, sig);
+        assert_eq!(finalize_multi_with(None, pubs, other_key).err(), Some(ErrorCode::IdentityKeyMismatch.into()));
+
+        // Three keys with two unreadable pairs: refused on the count, not on the accounts.
+        let three = vec![crypto::test_pub(0), crypto::test_pub(1), crypto::test_pub(2)];
+        let unreadable = |_: &[u8]| -> &'static [AccountInfo<'static>] {
+            Box::leak(Box::new([0u8; 4].map(|_| empty_account(Pubkey::new_unique()).clone())))
+        };
+        assert_eq!(finalize_multi_with(None, three, unreadable).err(), Some(ErrorCode::SignerCountInvalid.into()));
+    }
+
+    /// msg_type is part of the signed blob: a signature for one type does not verify for another.
+    #[test]
+    fn msg_type_is_signed() {
+        let (sk, pk) = (crypto::test_key(0), crypto::test_pub(0));
+        let (cipher_kem, nonce, slot) = (b"cipher||kem", [9u8; 12], 42u64);
+        let sig = sk.sign(&signed_message(cipher_kem, &PAYER, &RECIPIENT, &nonce, slot, 3, SIG_ALG_SHA2_128S)).to_bytes();
+
+        let blob = signed_message(cipher_kem, &PAYER, &RECIPIENT, &nonce, slot, 3, SIG_ALG_SHA2_128S);
+        assert_eq!(blob[blob.len() - 3..blob.len() - 1], 3u16.to_le_bytes());
+        assert!(crypto::verify(&blob, &sig, &pk).is_ok());
+        assert!(crypto::verify(&signed_message(cipher_kem, &PAYER, &RECIPIENT, &nonce, slot, 4, SIG_ALG_SHA2_128S), &sig, &pk).is_err());
+        assert!(crypto::verify(&signed_message(cipher_kem, &PAYER, &RECIPIENT, &nonce, slot, 0, SIG_ALG_SHA2_128S), &sig, &pk).is_err());
+    }
+
+    #[derive(serde::Deserialize)]
+    struct MessageVector {
+        cipher_kem_hex: String,
+        sender_hex    : String,
+        recipient_hex : String,
+        nonce_hex     : String,
+        slot          : u64,
+        msg_type      : u16,
+        sig_alg       : u8,
+        message_hex   : String,
+    }
+
+    /// signed_message matches the vector slh-dsa-wasm checks its finalize_message against, byte for byte.
+    #[test]
+    fn signed_message_matches_shared_vector() {
+        let v: MessageVector =
+            serde_json::from_str(include_str!("../../../crates/slh-dsa-wasm/fixtures/finalize_message.json")).unwrap();
+        let h = |s: &str| hex::decode(s).unwrap();
+        let key = |s: &str| Pubkey::try_from(h(s)).unwrap();
+        let blob = signed_message(
+            &h(&v.cipher_kem_hex), &key(&v.sender_hex), &key(&v.recipient_hex), &h(&v.nonce_hex), v.slot, v.msg_type, v.sig_alg,
+        );
+        assert_eq!(hex::encode(blob), v.message_hex);
+    }
+
+    /// sig_alg round-trips through ChatMsg and is the signed blob's last byte: relabeling the set fails verification.
+    #[test]
+    fn sig_alg_stored_and_signed() {
+        let (sk, pk) = (crypto::test_key(0), crypto::test_pub(0));
+        let (cipher_kem, nonce, slot) = (b"cipher||kem".to_vec(), [9u8; 12], 42u64);
+        let blob = signed_message(&cipher_kem, &PAYER, &RECIPIENT, &nonce, slot, 0, SIG_ALG_SHA2_128S);
+        assert_eq!(blob.last(), Some(&SIG_ALG_SHA2_128S));
+        let sig = sk.sign(&blob).to_bytes();
+
+        let mut chat = ChatMsg { sender: PAYER, recipient: RECIPIENT, slot, ..test_chat(0, 0, cipher_kem.clone()) };
+        chat.set_nonce(&nonce);
+        let mut data = Vec::new();
+        chat.try_serialize(&mut data).unwrap();
+        // sig_alg is the last header byte, right before the payload's Vec prefix.
+        assert_eq!(data[DISC_SIZE + CHAT_HEAD - 5], SIG_ALG_SHA2_128S);
+        let back = ChatMsg::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(back.sig_alg, SIG_ALG_SHA2_128S);
+        let stored = signed_message(&back.payload, &back.sender, &back.recipient, back.aead_nonce(), back.slot, back.msg_type, back.sig_alg);
+        assert!(crypto::verify(&stored, &sig, &pk).is_ok());
+
+        // Relabeling the account as another set changes the signed blob, and finalize refuses unknown sets.
+        let mut relabeled = data.clone();
+        relabeled[DISC_SIZE + CHAT_HEAD - 5] = 2;
+        let back = ChatMsg::try_deserialize(&mut &relabeled[..]).unwrap();
+        let forged = signed_message(&back.payload, &back.sender, &back.recipient, back.aead_nonce(), back.slot, back.msg_type, back.sig_alg);
+        assert!(crypto::verify(&forged, &sig, &pk).is_err());
+        assert!(check_sig_alg(SIG_ALG_SHA2_128S).is_ok());
+        assert_eq!(check_sig_alg(2).unwrap_err(), ErrorCode::SigAlgUnsupported.into());
+        assert_eq!(check_sig_alg(0).unwrap_err(), ErrorCode::SigAlgUnsupported.into());
+    }
+
+    /// A segment of MAX_CHAT_PAYLOAD bytes derives the prover's plain SHA-256 inputs; a near-max cipher verifies in place.
+    #[test]
+    fn max_cipher_len_derivation() {
+        let full: Vec<u8> = (0..MAX_CHAT_PAYLOAD).map(|i| (i % 251) as u8).collect();
+        let (seed, inc) = derive_public_inputs(&full);
+        let (_params, proof_full) = stark_prover::generate_proof_for_segment(&full).unwrap();
+        assert!(crypto::verify_stark(&proof_full, seed, inc).is_ok());
+
+        // Proof sizes vary with how many Merkle paths the queries share, so leave 512 bytes of slack and pad the kem to fill the account.
+        let cipher = &full[..MAX_CHAT_PAYLOAD - proof_full.len() - 512];
+        let (_params, proof) = stark_prover::generate_proof_for_segment(cipher).unwrap();
+        let kem = vec![0u8; MAX_CHAT_PAYLOAD - cipher.len() - proof.len()];
+        let body = payload(cipher, &kem, &proof);
+        assert_eq!(body.len(), MAX_CHAT_PAYLOAD);
+        assert!(verify_payload(&body, cipher.len() as u32, kem.len() as u32, InputSource::Cipher).is_ok());
+
+        // Several slices in one hashv hash their concatenation; separate hashv calls do not chain.
+        let (a, b) = full.split_at(MAX_CHAT_PAYLOAD / 2);
+        assert_eq!(hashv(&[a, b]), hashv(&[&full]));
+        assert_ne!(hashv(&[&hashv(&[a]).to_bytes(), b]), hashv(&[&full]));
+    }
+
+    /// A 24-byte nonce is signed in full, with its length, and stored as-is.
+    #[test]
+    fn extended_nonce_signed_and_stored() {
+        use crate::state::{NONCE_LEN, XNONCE_LEN};
+        let (sk, pk) = (crypto::test_key(0), crypto::test_pub(0));
+        let nonce: Vec<u8> = (0..XNONCE_LEN as u8).collect();
+        let (cipher_kem, slot) = (b"cipher||kem".to_vec(), 42u64);
+        let blob = signed_message(&cipher_kem, &PAYER, &RECIPIENT, &nonce, slot, 1, SIG_ALG_SHA2_128S);
+        let sig = sk.sign(&blob).to_bytes();
+        assert!(check_nonce_len(&nonce).is_ok());
+        assert_eq!(blob[cipher_kem.len() + 64..cipher_kem.len() + 64 + XNONCE_LEN], nonce[..]);
+        assert_eq!(blob[blob.len() - 12], XNONCE_LEN as u8);
+        assert!(crypto::verify(&blob, &sig, &pk).is_ok());
+
+        // Every nonce byte is authenticated, and the tail cannot pass as a 12-byte nonce.
+        let mut flipped = nonce.clone();
+        flipped[XNONCE_LEN - 1] ^= 1;
+        assert!(crypto::verify(&signed_message(&cipher_kem, &PAYER, &RECIPIENT, &flipped, slot, 1, SIG_ALG_SHA2_128S), &sig, &pk).is_err());
+        let shifted = [&cipher_kem[..], &nonce[..NONCE_LEN]].concat();
+        assert!(crypto::verify(&signed_message(&shifted, &PAYER, &RECIPIENT, &nonce[NONCE_LEN..], slot, 1, SIG_ALG_SHA2_128S), &sig, &pk).is_err());
+
+        let mut chat = ChatMsg { sender: PAYER, recipient: RECIPIENT, slot, msg_type: 1, ..test_chat(0, 0, cipher_kem.clone()) };
+        chat.set_nonce(&nonce);
+        let mut data = Vec::new();
+        chat.try_serialize(&mut data).unwrap();
+        let back = ChatMsg::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(back.aead_nonce(), &nonce[..]);
+        assert_eq!(data.len(), chat_account_space(cipher_kem.len()));
+        let stored = signed_message(&back.payload, &back.sender, &back.recipient, back.aead_nonce(), back.slot, back.msg_type, back.sig_alg);
+        assert!(crypto::verify(&stored, &sig, &pk).is_ok());
+    }
+
+    /// A prehash signature passes only the prehash path; a pure signature only the pure path.
+    #[test]
+    fn prehash_path_separates_modes() {
+        use slh_dsa::onchain_sha2::SHA256_OID_DER;
+        let (sk, pk) = (crypto::test_key(0), crypto::test_pub(0));
+        let blob = signed_message(&[0x33u8; 4_000], &PAYER, &RECIPIENT, &[9u8; 12], 42, 0, SIG_ALG_SHA2_128S);
+        let digest = hashv(&[&blob]).to_bytes();
+        let pre = sk.slh_sign_internal(&[&[1u8, 0][..], &SHA256_OID_DER, &digest], None).to_bytes();
+        let pure = sk.sign(&blob).to_bytes();
+
+        let failed: Error = ErrorCode::SigFailed.into();
+        assert!(verify_signed(&blob, &pre, &pk, true).is_ok());
+        assert_eq!(verify_signed(&blob, &pure, &pk, true).unwrap_err(), failed);
+        assert_eq!(verify_signed(&blob, &pre, &pk, false).unwrap_err(), failed);
+        assert!(verify_signed(&blob, &pure, &pk, false).is_ok());
+        let other = signed_message(&[0x33u8; 4_000], &PAYER, &RECIPIENT, &[9u8; 12], 43, 0, SIG_ALG_SHA2_128S);
+        assert_eq!(verify_signed(&other, &pre, &pk, true).unwrap_err(), failed);
+    }
+
+    /// A sharded ChatMsg's signature covers shard_root, shard_count and both lengths.
+    #[test]
+    fn shard_commitment_is_signed() {
+        let (sk, pk) = (crypto::test_key(0), crypto::test_pub(0));
+        let (root, nonce) = ([7u8; 32], [9u8; 12]);
+        let blob = signed_message(&shard::shard_commitment(&root, 3, 16_000, 4_000), &PAYER, &RECIPIENT, &nonce, 42, 0, SIG_ALG_SHA2_128S);
+        let sig = sk.sign(&blob).to_bytes();
+        assert!(verify_signed(&blob, &sig, &pk, false).is_ok());
+
+        let failed: Error = ErrorCode::SigFailed.into();
+        for (r, count, cl, kl) in [([8u8; 32], 3, 16_000, 4_000), (root, 2, 16_000, 4_000), (root, 3, 15_999, 4_001)] {
+            let other = signed_message(&shard::shard_commitment(&r, count, cl, kl), &PAYER, &RECIPIENT, &nonce, 42, 0, SIG_ALG_SHA2_128S);
+            assert_eq!(verify_signed(&other, &sig, &pk, false).unwrap_err(), failed);
+        }
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! No .bss/.data; allocator metadata lives at heap start;
 //! default limit 32 KiB; in-place realloc and last-allocation free.
+//! The pure-bump feature turns both off (dealloc never reclaims, realloc always copies),
+//! so a failure that goes away with it points at memory reuse rather than the verifier.
 //!
 //! Runtime contract: set_heap_limit_bytes(n) must match 
 //! ComputeBudgetProgram.requestHeapFrame({ bytes: n }) (1024-byte multiple, max 256 KiB).
@@ -21,16 +23,27 @@ const DEFAULT_LIMIT_BYTES: usize = 32 * 1024;
 
 // Offsets for the in-heap allocator metadata.
 const USZ: usize = mem::size_of::<usize>();
-const OFF_HEAD:      usize = 0 * USZ; // Next alloc ptr
-const OFF_LIMIT:     usize = 1 * USZ; // Start + bytes
+const OFF_HEAD:      usize = 0;       // Next alloc ptr
+const OFF_LIMIT:     usize = USZ;     // Start + bytes
 const OFF_LAST_PTR:  usize = 2 * USZ; // Last allocation ptr
 const OFF_LAST_SIZE: usize = 3 * USZ; // Last allocation size
 const META_SIZE:     usize = 4 * USZ; // Reserved bytes at heap start
 
+// Host tests run the allocator over this buffer instead of the SBF heap region (sized for the largest frame).
+#[cfg(all(test, not(any(target_arch = "bpf", target_os = "solana"))))]
+const HOST_HEAP_BYTES: usize = 256 * 1024;
+#[cfg(all(test, not(any(target_arch = "bpf", target_os = "solana"))))]
+static mut HOST_HEAP: [usize; HOST_HEAP_BYTES / USZ] = [0; HOST_HEAP_BYTES / USZ];
+
+/// Serializes host tests that move HOST_HEAP's head or limit.
+#[cfg(all(test, not(any(target_arch = "bpf", target_os = "solana"))))]
+pub(crate) static HOST_HEAP_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
 #[inline(always)]
 fn heap_start() -> usize {
     #[cfg(any(target_arch = "bpf", target_os = "solana"))] { HEAP_START }
-    #[cfg(not(any(target_arch = "bpf", target_os = "solana")))] { 0x3000_0000_00 }
+    #[cfg(all(test, not(any(target_arch = "bpf", target_os = "solana"))))] { ptr::addr_of_mut!(HOST_HEAP) as usize }
+    #[cfg(not(any(test, target_arch = "bpf", target_os = "solana")))] { 0x3000_0000_00 }
 }
 
 #[inline(always)]
@@ -81,6 +94,23 @@ pub fn snapshot() -> (usize, usize, usize) {
     }
 }
 
+/// Debug helper: bytes handed out since the heap start (metadata included); the bump allocator's high-water mark
+/// unless the last allocation was freed.
+#[allow(unused)]
+pub fn used_bytes() -> usize {
+    let (start, head, _) = snapshot();
+    head - start
+}
+
+/// Host tests: empties HOST_HEAP (head back at the start, no last allocation); the limit is left alone.
+#[cfg(all(test, not(any(target_arch = "bpf", target_os = "solana"))))]
+pub(crate) unsafe fn reset_host_heap() {
+    let start = heap_start();
+    write_usize(start + OFF_HEAD, start + META_SIZE);
+    write_usize(start + OFF_LAST_PTR,  0);
+    write_usize(start + OFF_LAST_SIZE, 0);
+}
+
 /// Zero-sized global allocator (keeps no RW ELF sections).
 pub struct BpfBumpAlloc;
 
@@ -104,6 +134,7 @@ unsafe impl GlobalAlloc for BpfBumpAlloc {
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if cfg!(feature = "pure-bump") { return; } // Never reclaims: freed memory is not handed out again.
         let start = heap_start();
         let last_ptr = read_usize(start + OFF_LAST_PTR);
         let last_sz  = read_usize(start + OFF_LAST_SIZE);
@@ -121,7 +152,7 @@ unsafe impl GlobalAlloc for BpfBumpAlloc {
         let last_ptr = read_usize(start + OFF_LAST_PTR);
         let last_sz  = read_usize(start + OFF_LAST_SIZE);
 
-        if last_ptr == (ptr as usize) {
+        if !cfg!(feature = "pure-bump") && last_ptr == (ptr as usize) {
             let head  = read_usize(start + OFF_HEAD);
             let limit = read_usize(start + OFF_LIMIT);
             let old_end = last_ptr + last_sz;
@@ -149,3 +180,173 @@ unsafe impl GlobalAlloc for BpfBumpAlloc {
         new_ptr
     }
 }
+
+/// Host-test allocation meter: the test binary's global allocator forwards to System and counts,
+/// per thread, the bytes live (and their peak) and the bytes ever requested.
+/// Peak is what an allocator that reuses freed memory needs; total is what pure-bump would consume.
+/// Inside measure_bump the calling thread allocates from BpfBumpAlloc over HOST_HEAP instead, so the
+/// high-water mark comes from snapshot() exactly as on chain.
+/// It has to be the global allocator: Winterfell allocates through Vec and Box, with no allocator parameter to pass.
+/// Host figures approximate the SBF heap: layouts and alignment follow the host target, not sbf.
+#[cfg(all(test, not(any(target_arch = "bpf", target_os = "solana"))))]
+pub(crate) mod meter {
+    use super::{
+        heap_start, reset_host_heap, set_heap_limit_bytes, snapshot, BpfBumpAlloc, DEFAULT_LIMIT_BYTES, HOST_HEAP_BYTES,
+        HOST_HEAP_LOCK, META_SIZE,
+    };
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        ptr,
+    };
+
+    thread_local! {
+        static LIVE : Cell<usize> = const { Cell::new(0) };
+        static PEAK : Cell<usize> = const { Cell::new(0) };
+        static TOTAL: Cell<usize> = const { Cell::new(0) };
+        static BUMP : Cell<bool>  = const { Cell::new(false) }; // Route this thread's allocations to BpfBumpAlloc
+        static HIGH : Cell<usize> = const { Cell::new(0) };     // Highest snapshot() head - start seen
+    }
+
+    fn bump_on() -> bool {
+        BUMP.try_with(Cell::get).unwrap_or(false)
+    }
+
+    fn in_host_heap(p: *mut u8) -> bool {
+        (heap_start()..heap_start() + HOST_HEAP_BYTES).contains(&(p as usize))
+    }
+
+    fn record_high() {
+        let (start, head, _) = snapshot();
+        let _ = HIGH.try_with(|high| high.set(high.get().max(head - start)));
+    }
+
+    /// Bytes allocated while a closure ran on this thread.
+    #[derive(Clone, Copy, Debug)]
+    pub(crate) struct Usage {
+        pub peak : usize, // Highest live byte count
+        pub total: usize, // Sum of every allocation and realloc size
+    }
+
+    fn grow(n: usize) {
+        let _ = LIVE.try_with(|live| {
+            live.set(live.get() + n);
+            let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+        });
+        let _ = TOTAL.try_with(|total| total.set(total.get() + n));
+    }
+
+    fn shrink(n: usize) {
+        // Frees of memory allocated before measure() started would otherwise underflow.
+        let _ = LIVE.try_with(|live| live.set(live.get().saturating_sub(n)));
+    }
+
+    struct Meter;
+
+    unsafe impl GlobalAlloc for Meter {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let mut p = if bump_on() { BpfBumpAlloc.alloc(layout) } else { System.alloc(layout) };
+            if bump_on() {
+                if p.is_null() {
+                    // Out of heap on chain; record what was asked for and let the test report it instead of aborting.
+                    let (start, head, _) = snapshot();
+                    let _ = HIGH.try_with(|high| high.set(high.get().max(head - start + layout.size())));
+                    p = System.alloc(layout);
+                } else {
+                    record_high();
+                }
+            }
+            if !p.is_null() { grow(layout.size()); }
+            p
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            if in_host_heap(ptr) { BpfBumpAlloc.dealloc(ptr, layout) } else { System.dealloc(ptr, layout) }
+            shrink(layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let p = match (bump_on(), in_host_heap(ptr)) {
+                (true, true)   => BpfBumpAlloc.realloc(ptr, layout, new_size),
+                (false, false) => System.realloc(ptr, layout, new_size),
+                _ => ptr::null_mut(),
+            };
+            if p.is_null() {
+                // Moves between System and the bump heap, or out of a full bump heap; alloc and dealloc do the counting.
+                let p = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+                if !p.is_null() {
+                    ptr::copy_nonoverlapping(ptr, p, layout.size().min(new_size));
+                    self.dealloc(ptr, layout);
+                }
+                return p;
+            }
+            shrink(layout.size());
+            grow(new_size);
+            if bump_on() { record_high(); }
+            p
+        }
+    }
+
+    #[global_allocator]
+    static METER: Meter = Meter;
+
+    /// Runs f and reports what it allocated on the calling thread (other test threads are not counted).
+    pub(crate) fn measure<R>(f: impl FnOnce() -> R) -> (R, Usage) {
+        LIVE.with(|c| c.set(0));
+        PEAK.with(|c| c.set(0));
+        TOTAL.with(|c| c.set(0));
+        let out = f();
+        (out, Usage { peak: PEAK.with(Cell::get), total: TOTAL.with(Cell::get) })
+    }
+
+    /// Runs f with this thread's allocations served by BpfBumpAlloc from an empty heap limited to limit_bytes,
+    /// and returns the allocator's high-water mark (snapshot() head - start, metadata included).
+    /// An allocation past the limit falls back to System and pushes the mark over limit_bytes.
+    /// f should return plain values: memory it hands back lives in HOST_HEAP until the next reset.
+    pub(crate) fn measure_bump<R>(limit_bytes: usize, f: impl FnOnce() -> R) -> (R, usize) {
+        assert!(limit_bytes <= HOST_HEAP_BYTES);
+        let _guard = HOST_HEAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        unsafe { reset_host_heap() };
+        set_heap_limit_bytes(limit_bytes);
+        HIGH.with(|c| c.set(META_SIZE));
+        BUMP.with(|c| c.set(true));
+        let out = f();
+        BUMP.with(|c| c.set(false));
+        set_heap_limit_bytes(DEFAULT_LIMIT_BYTES);
+        (out, HIGH.with(Cell::get))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Freeing or growing the last allocation reuses its memory by default; pure-bump only ever moves head forward.
+    #[test]
+    fn pure_bump_is_monotonic() {
+        let _guard = HOST_HEAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let alloc = BpfBumpAlloc;
+        let (small, big) = (Layout::from_size_align(64, 8).unwrap(), Layout::from_size_align(128, 8).unwrap());
+        unsafe {
+            reset_host_heap();
+            set_heap_limit_bytes(DEFAULT_LIMIT_BYTES);
+            let (start, head0, limit) = snapshot();
+            assert_eq!((head0, limit), (start + META_SIZE, start + DEFAULT_LIMIT_BYTES));
+
+            let p = alloc.alloc(small);
+            assert_eq!(p as usize, head0);
+            assert_eq!(snapshot().1, head0 + 64);
+
+            let q = alloc.realloc(p, small, 128);
+            alloc.dealloc(q, big);
+            let head = snapshot().1;
+            if cfg!(feature = "pure-bump") {
+                assert_eq!(q as usize, head0 + 64);
+                assert_eq!(head, head0 + 64 + 128);
+            } else {
+                assert_eq!(q, p);
+                assert_eq!(head, head0);
+            }
+        }
+    }
+}
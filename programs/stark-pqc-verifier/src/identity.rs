@@ -0,0 +1,58 @@
+//! Registered PQ identities (PqIdentity PDA, seeds ["pqid", owner]).
+//!
+//! RegisterPqKey / handle_register_pq_key: creates the identity, or updates it idempotently (same key = no-op).
+//! RotatePqKey / handle_rotate_pq_key: replaces the key of an existing identity and records rotated_at_slot.
+//! Every finalize passes the sender's identity address; once it is registered, slh_pub must be its current key.
+
+use anchor_lang::prelude::*;
+use crate::state::{PqIdentity, PQ_IDENTITY_SPACE};
+
+/// Accounts for registering (or re-registering) the owner's verifying key.
+#[derive(Accounts)]
+pub struct RegisterPqKey<'info> {
+    #[account(
+        init_if_needed, payer=owner, space=PQ_IDENTITY_SPACE,
+        seeds=[b"pqid", owner.key().as_ref()], bump
+    )]
+    pub identity: Account<'info, PqIdentity>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Registers vk for the signer; calling again with a new key rotates it.
+pub fn handle_register_pq_key(ctx: Context<RegisterPqKey>, vk: [u8; 32]) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    ctx.accounts.identity.register_or_update(ctx.accounts.owner.key(), vk, slot);
+    Ok(())
+}
+
+/// Accounts for rotating an existing identity; only its owner may sign.
+#[derive(Accounts)]
+pub struct RotatePqKey<'info> {
+    #[account(
+        mut, has_one = owner,
+        seeds=[b"pqid", owner.key().as_ref()], bump
+    )]
+    pub identity: Account<'info, PqIdentity>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Replaces the registered key and stores the current slot as rotated_at_slot.
+pub fn handle_rotate_pq_key(ctx: Context<RotatePqKey>, new_vk: [u8; 32]) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    ctx.accounts.identity.rotate(new_vk, slot);
+    Ok(())
+}
+
+/// Checks slh_pub against the sender's identity account. An address nobody registered holds no data and
+/// passes; a registered one must deserialize as PqIdentity and name slh_pub as its current key.
+pub(crate) fn check_registered_key(identity: &AccountInfo, slh_pub: &[u8; 32]) -> Result<()> {
+    if identity.data_is_empty() {
+        return Ok(());
+    }
+    require_keys_eq!(*identity.owner, crate::ID, anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram);
+    PqIdentity::try_deserialize(&mut &identity.data.borrow()[..])?.check_key(slh_pub)
+}
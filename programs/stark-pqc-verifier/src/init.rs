@@ -2,9 +2,10 @@
 //!
 //! InitBuffer / handle_init_buffer: prepares the body buffer at a fixed size.
 //! InitSignature / handle_init_signature: prepares the signature buffer keyed by (sender, recipient, slot).
+//! ResetMessage / handle_reset_message: empties an existing body buffer so a changed message can be re-uploaded.
 
 use anchor_lang::prelude::*;
-use crate::state::{BufferPda, BUF_ACCOUNT_SPACE};
+use crate::state::{check_recipient, BufferPda, ErrorCode, BUF_ACCOUNT_SPACE};
 
 /// Accounts for initializing the body buffer PDA.
 #[derive(Accounts)]
@@ -24,9 +25,34 @@ pub struct InitBuffer<'info> {
 pub fn handle_init_buffer(ctx: Context<InitBuffer>) -> Result<()> {
     let buf = &mut ctx.accounts.buffer;
     buf.sender    = ctx.accounts.payer.key();
+    reset_buffer(buf);
+    Ok(())
+}
+
+/// Accounts for resetting an existing body buffer; only its stored sender may do so.
+#[derive(Accounts)]
+pub struct ResetMessage<'info> {
+    #[account(
+        mut,
+        seeds=[b"buf", sender.key().as_ref()], bump,
+        constraint = buffer.sender == sender.key() @ ErrorCode::NotBufferSender
+    )]
+    pub buffer: Account<'info, BufferPda>,
+
+    pub sender: Signer<'info>,
+}
+
+/// Drops an in-flight upload: length and sha_chain go back to zero and the account stays allocated,
+/// so the next upload_body starts at offset 0 without paying to close and recreate the buffer.
+pub fn handle_reset_message(ctx: Context<ResetMessage>) -> Result<()> {
+    reset_buffer(&mut ctx.accounts.buffer);
+    Ok(())
+}
+
+/// Empties a buffer; stale bytes past the new length are never read and get overwritten by the next upload.
+pub(crate) fn reset_buffer(buf: &mut BufferPda) {
     buf.length    = 0;
     buf.sha_chain = [0u8; 32];
-    Ok(())
 }
 
 /// Accounts for initializing the signature buffer PDA.
@@ -50,9 +76,10 @@ pub struct InitSignature<'info> {
 /// Resets length and sha_chain of the signature buffer.
 pub fn handle_init_signature(
     ctx: Context<InitSignature>,
-    _recipient: Pubkey,
+    recipient: Pubkey,
     _slot: u64,
 ) -> Result<()> {
+    check_recipient(&recipient, &ctx.accounts.recipient.key())?;
     let buf = &mut ctx.accounts.buffer;
     buf.sender    = ctx.accounts.payer.key();
     buf.length    = 0;
@@ -7,14 +7,25 @@
 //! init_buffer / init_signature: initialize PDA buffers used for streaming uploads.
 //! upload_body / upload_signature: chunked upload with hash-chaining to mitigate DoS.
 //! finalize_sig: verify SLH-DSA and persist a ChatMsg account (cipher|kem|proof).
-//! verify_stark: verify the STARK proof against SHA-256(cipher)-derived public inputs.
+//! finalize_sig_prehash: same, with a HashSLH-DSA (SHA-256 prehash) signature.
+//! finalize_sig_multi: same, with up to two co-signers over the same message.
+//! finalize_sig_sharded / store_shard: same, for payloads split across ChatShard accounts under a signed Merkle root.
+//! verify_stark: verify the STARK proof against SHA-256(cipher|kem)-derived public inputs.
+//! register_pq_key / rotate_pq_key: manage the sender's registered SLH-DSA key (PqIdentity).
+//! audit_sig: re-check the signature buffer against the ChatMsg's sig_hash.
+//! audit_chatmsg: read-only check of a ChatMsg's internal length invariants, returned as a ChatMsgAudit.
+//! set_stark_config: upgrade-authority switch for the trusted-relayer mode (finalize_sig records relayer_attested).
+//! message_exists: read-only check whether a (sender, recipient, slot) ChatMsg was finalized.
 
 #![allow(unexpected_cfgs)] // Keep until Anchor's cfg layout is simplified
 #![allow(deprecated)] // Remove once Anchor moves to AccountInfo::resize()
 
 use anchor_lang::prelude::*;
 
-#[cfg(all(feature = "custom-heap", any(target_arch = "bpf", target_os = "solana")))]
+#[macro_use]
+mod log;
+
+#[cfg(any(test, all(feature = "custom-heap", any(target_arch = "bpf", target_os = "solana"))))]
 mod heap;
 
 #[cfg(all(feature = "custom-heap", any(target_arch = "bpf", target_os = "solana")))]
@@ -22,27 +33,51 @@ mod heap;
 #[global_allocator]
 static GLOBAL_ALLOC: heap::BpfBumpAlloc = heap::BpfBumpAlloc;
 
-mod state;
-mod crypto;
+pub mod state;
+pub mod crypto;
 mod init;
 mod upload;
 mod finalize;
+mod audit;
+mod identity;
+mod query;
+mod config;
+mod shard;
+#[cfg(test)]
+mod test_accounts;
 
 // Program ID
 declare_id!("CECNRbDxFQVfWiQwvG8qcSGPGSk8eLWraBCERcdL5DKT");
 
 // Re-exports
-pub use init   ::{ InitBuffer, InitSignature };
+pub use init   ::{ InitBuffer, InitSignature, ResetMessage };
 pub use upload ::{ UploadBody, UploadSignature };
-pub use finalize::{ FinalizeSig, VerifyStark };
+pub use finalize::{ FinalizeSig, FinalizeSigMulti, FinalizeSigSharded, VerifyStark, derive_public_inputs, signed_message };
+pub use audit::{audit_chat_msg, AuditChatMsg, AuditSig, ChatMsgAudit, ChatMsgIssue};
+pub use identity::{ RegisterPqKey, RotatePqKey };
+pub use query::MessageExists;
+pub use config::SetStarkConfig;
+pub use shard::{ StoreShard, SHARD_COMMITMENT_TAG, shard_commitment, shard_leaf, shard_path, shards_root, verify_shard_path };
+pub use state::InputSource;
+pub use upload::{fold_chain, next_chain, UploadProgress};
 
 // Anchor idl-build client account module names
 pub mod __client_accounts_init_buffer      { pub use crate::InitBuffer; }
 pub mod __client_accounts_init_signature   { pub use crate::InitSignature; }
+pub mod __client_accounts_reset_message    { pub use crate::ResetMessage; }
 pub mod __client_accounts_upload_body      { pub use crate::UploadBody; }
 pub mod __client_accounts_upload_signature { pub use crate::UploadSignature; }
 pub mod __client_accounts_finalize_sig     { pub use crate::FinalizeSig; }
+pub mod __client_accounts_finalize_sig_multi { pub use crate::FinalizeSigMulti; }
+pub mod __client_accounts_finalize_sig_sharded { pub use crate::FinalizeSigSharded; }
+pub mod __client_accounts_store_shard      { pub use crate::StoreShard; }
 pub mod __client_accounts_verify_stark     { pub use crate::VerifyStark; }
+pub mod __client_accounts_audit_sig        { pub use crate::AuditSig; }
+pub mod __client_accounts_audit_chat_msg   { pub use crate::AuditChatMsg; }
+pub mod __client_accounts_register_pq_key  { pub use crate::RegisterPqKey; }
+pub mod __client_accounts_rotate_pq_key    { pub use crate::RotatePqKey; }
+pub mod __client_accounts_message_exists   { pub use crate::MessageExists; }
+pub mod __client_accounts_set_stark_config { pub use crate::SetStarkConfig; }
 
 #[program]
 pub mod stark_pqc_verifier {
@@ -58,8 +93,14 @@ pub mod stark_pqc_verifier {
         init::handle_init_signature(ctx, recipient, slot)
     }
 
+    /// Empties the caller's existing body buffer (length 0, zero sha_chain) so a changed message can be re-uploaded.
+    pub fn reset_message(ctx: Context<ResetMessage>) -> Result<()> {
+        init::handle_reset_message(ctx)
+    }
+
     /// Appends a body chunk to the body buffer (cipher || kem || proof) with hash-chaining.
-    pub fn upload_body(ctx: Context<UploadBody>, off: u32, data: Vec<u8>, hash: [u8; 32]) -> Result<()> {
+    /// Both uploads return the new (length, sha_chain) as return data for the next chunk.
+    pub fn upload_body(ctx: Context<UploadBody>, off: u32, data: Vec<u8>, hash: [u8; 32]) -> Result<UploadProgress> {
         upload::handle_upload_body(ctx, off, data, hash)
     }
 
@@ -71,24 +112,128 @@ pub mod stark_pqc_verifier {
         off: u32,
         data: Vec<u8>,
         hash: [u8; 32],
-    ) -> Result<()> {
+    ) -> Result<UploadProgress> {
         upload::handle_upload_signature(ctx, recipient, slot, off, data, hash)
     }
 
     /// Step 1: Verifies SLH-DSA and persists a ChatMsg.
+    /// `msg_type` is an application-defined kind stored in ChatMsg and covered by the signature.
+    /// `sig_alg` names the SLH-DSA parameter set (only SIG_ALG_SHA2_128S today); it is stored and signed too.
+    #[allow(clippy::too_many_arguments)]
     pub fn finalize_sig(
         ctx       : Context<FinalizeSig>,
         cipher_len: u32,
         kem_len   : u32,
-        nonce     : [u8; 12],
+        nonce     : Vec<u8>,
         slot      : u64,
         slh_pub   : [u8; 32],
+        msg_type  : u16,
+        sig_alg   : u8,
+    ) -> Result<()> {
+        finalize::handle_finalize_sig(ctx, cipher_len, kem_len, nonce, slot, slh_pub, msg_type, sig_alg)
+    }
+
+    /// Step 1 with a HashSLH-DSA signature: slh_pub signed SHA-256 of the finalize_sig message (FIPS 205 prehash).
+    /// Same accounts, arguments and ChatMsg as finalize_sig.
+    #[allow(clippy::too_many_arguments)]
+    pub fn finalize_sig_prehash(
+        ctx       : Context<FinalizeSig>,
+        cipher_len: u32,
+        kem_len   : u32,
+        nonce     : Vec<u8>,
+        slot      : u64,
+        slh_pub   : [u8; 32],
+        msg_type  : u16,
+        sig_alg   : u8,
+    ) -> Result<()> {
+        finalize::handle_finalize_sig_prehash(ctx, cipher_len, kem_len, nonce, slot, slh_pub, msg_type, sig_alg)
+    }
+
+    /// Step 1 with co-signers: every key in `slh_pubs` (at most 2) must sign the same message.
+    /// The payer's sig buffer holds slh_pubs[0]'s signature; each other key follows as remaining accounts
+    /// (its owner's PqIdentity, then that owner's sig PDA), so a co-signer must be a registered identity.
+    /// Every signer is recorded in the CoSigners account.
+    #[allow(clippy::too_many_arguments)]
+    pub fn finalize_sig_multi<'info>(
+        ctx       : Context<'_, '_, 'info, 'info, FinalizeSigMulti<'info>>,
+        cipher_len: u32,
+        kem_len   : u32,
+        nonce     : Vec<u8>,
+        slot      : u64,
+        slh_pubs  : Vec<[u8; 32]>,
+        msg_type  : u16,
+        sig_alg   : u8,
     ) -> Result<()> {
-        finalize::handle_finalize_sig(ctx, cipher_len, kem_len, nonce, slot, slh_pub)
+        finalize::handle_finalize_sig_multi(ctx, cipher_len, kem_len, nonce, slot, slh_pubs, msg_type, sig_alg)
+    }
+
+    /// Step 1 for a sharded payload: slh_pub signs shard_commitment(shard_root, shard_count, cipher_len, kem_len)
+    /// (prefixed with SHARD_COMMITMENT_TAG) in place of cipher || kem; cipher_len + kem_len must fit the shards.
+    /// The ChatMsg stores no payload; store_shard adds each shard afterwards.
+    #[allow(clippy::too_many_arguments)]
+    pub fn finalize_sig_sharded(
+        ctx        : Context<FinalizeSigSharded>,
+        cipher_len : u32,
+        kem_len    : u32,
+        nonce      : Vec<u8>,
+        slot       : u64,
+        slh_pub    : [u8; 32],
+        msg_type   : u16,
+        sig_alg    : u8,
+        shard_count: u16,
+        shard_root : [u8; 32],
+    ) -> Result<()> {
+        finalize::handle_finalize_sig_sharded(ctx, cipher_len, kem_len, nonce, slot, slh_pub, msg_type, sig_alg, shard_count, shard_root)
+    }
+
+    /// Moves the uploaded body buffer into ChatShard `index` of a sharded ChatMsg.
+    /// `path` is the Merkle path of the shard under ChatMsg.shard_root.
+    pub fn store_shard(ctx: Context<StoreShard>, recipient: Pubkey, slot: u64, index: u16, path: Vec<[u8; 32]>) -> Result<()> {
+        shard::handle_store_shard(ctx, recipient, slot, index, path)
     }
 
     /// Step 2: Verifies the STARK proof for the affine-counter AIR.
-    pub fn verify_stark(ctx: Context<VerifyStark>) -> Result<()> {
-        finalize::handle_verify_stark(ctx)
+    /// `source` selects which payload segment (cipher or kem) derives (seed, inc).
+    /// `heap_bytes` must equal the transaction's requestHeapFrame (clamped to 256 KiB); a larger value fails with HeapFrameExceeded.
+    /// For a sharded ChatMsg, pass its ChatShards in index order as remaining accounts.
+    pub fn verify_stark<'info>(
+        ctx       : Context<'_, '_, 'info, 'info, VerifyStark<'info>>,
+        source    : InputSource,
+        heap_bytes: u32,
+    ) -> Result<()> {
+        finalize::handle_verify_stark(ctx, source, heap_bytes)
+    }
+
+    /// Re-hashes the ChatMsg's signature buffer and fails unless it matches sig_hash and `expected_sig_hash`.
+    pub fn audit_sig(ctx: Context<AuditSig>, expected_sig_hash: [u8; 32]) -> Result<()> {
+        audit::handle_audit_sig(ctx, expected_sig_hash)
+    }
+
+    /// Returns (as return data) a ChatMsgAudit: sig_len, nonce_len, sig_alg, cipher_len + kem_len against the payload,
+    /// proof size and shard layout, with the first inconsistency found. Read-only; simulate it.
+    pub fn audit_chatmsg(ctx: Context<AuditChatMsg>) -> Result<ChatMsgAudit> {
+        audit::handle_audit_chatmsg(ctx)
+    }
+
+    /// Registers the signer's SLH-DSA verifying key; idempotent, and a different key rotates it.
+    pub fn register_pq_key(ctx: Context<RegisterPqKey>, vk: [u8; 32]) -> Result<()> {
+        identity::handle_register_pq_key(ctx, vk)
+    }
+
+    /// Replaces the owner's registered key and records rotated_at_slot.
+    pub fn rotate_pq_key(ctx: Context<RotatePqKey>, new_vk: [u8; 32]) -> Result<()> {
+        identity::handle_rotate_pq_key(ctx, new_vk)
+    }
+
+    /// Upgrade authority only: names the trusted relayer and enables or disables skip_stark.
+    /// While enabled, finalize_sig co-signed by that relayer stores relayer_attested = true instead of needing verify_stark.
+    pub fn set_stark_config(ctx: Context<SetStarkConfig>, relayer: Pubkey, skip_stark: bool) -> Result<()> {
+        config::handle_set_stark_config(ctx, relayer, skip_stark)
+    }
+
+    /// Returns (as return data) whether the ChatMsg for (sender, recipient, slot) has been finalized.
+    /// Read-only: lets clients skip a duplicate upload without racing a second finalize.
+    pub fn message_exists(ctx: Context<MessageExists>, sender: Pubkey, recipient: Pubkey, slot: u64) -> Result<bool> {
+        query::handle_message_exists(ctx, sender, recipient, slot)
     }
 }
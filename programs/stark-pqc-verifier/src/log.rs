@@ -0,0 +1,26 @@
+//! Logging levels for program output.
+//!
+//! msg!: essential lines (failures a client must see), always emitted.
+//! dbg_msg!: verbose DBG traces, compiled out unless the `verbose-logs` feature is enabled.
+//! Arguments to dbg_msg! are not evaluated in quiet builds, so they cost no CU.
+
+/// Emits a DBG trace via msg! only when built with `verbose-logs`.
+macro_rules! dbg_msg {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "verbose-logs")]
+        { anchor_lang::prelude::msg!($($arg)*); }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    /// Quiet builds must not even evaluate the log arguments.
+    #[test]
+    fn dbg_msg_gated_by_feature() {
+        let hits = Cell::new(0);
+        dbg_msg!("DBG hit={}", { hits.set(hits.get() + 1); hits.get() });
+        assert_eq!(hits.get(), if cfg!(feature = "verbose-logs") { 1 } else { 0 });
+    }
+}
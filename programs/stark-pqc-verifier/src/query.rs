@@ -0,0 +1,52 @@
+//! Read-only queries.
+//!
+//! handle_message_exists: derives the ChatMsg PDA for (sender, recipient, slot) and returns whether it holds data.
+//! Anchor writes the bool with set_return_data; nothing is written and no signer or writable account is needed.
+//! "Exists" uses the same test as upload_signature's freeze check (account data non-empty).
+
+use anchor_lang::prelude::*;
+
+/// Accounts for message_exists; the ChatMsg address is fixed by the seeds.
+#[derive(Accounts)]
+#[instruction(sender: Pubkey, recipient: Pubkey, slot: u64)]
+pub struct MessageExists<'info> {
+    /// CHECK: read-only; only its data length is inspected, and it may not exist yet.
+    #[account(seeds=[b"msg", sender.as_ref(), recipient.as_ref(), &slot.to_le_bytes()], bump)]
+    pub chat_msg: UncheckedAccount<'info>,
+}
+
+/// Returns true once finalize_sig has written the (sender, recipient, slot) ChatMsg.
+pub fn handle_message_exists(ctx: Context<MessageExists>, _sender: Pubkey, _recipient: Pubkey, _slot: u64) -> Result<bool> {
+    Ok(message_exists(&ctx.accounts.chat_msg))
+}
+
+/// True when the account holds data, i.e. finalize has run for its seeds.
+pub(crate) fn message_exists(chat_ai: &AccountInfo) -> bool {
+    !chat_ai.data_is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{test_chat, ChatMsg, CHAT_HEAD};
+    use crate::test_accounts::{empty_account, leak_info};
+
+    /// The PDA reads false while unallocated and true once a finalized ChatMsg is stored there.
+    #[test]
+    fn exists_only_after_finalize() {
+        let key = Pubkey::new_unique();
+
+        // Before finalize: no data, owned by the system program.
+        assert!(!message_exists(empty_account(key)));
+
+        // After finalize: the account holds a serialized ChatMsg.
+        let mut chat = ChatMsg {
+            sender: Pubkey::new_unique(), recipient: Pubkey::new_unique(), slot: 7, ..test_chat(1, 1, vec![1, 2])
+        };
+        chat.set_nonce(&[3u8; 12]);
+        let mut data = Vec::new();
+        chat.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), 8 + CHAT_HEAD + 2);
+        assert!(message_exists(leak_info(key, false, crate::ID, data)));
+    }
+}
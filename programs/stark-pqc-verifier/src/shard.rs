@@ -0,0 +1,241 @@
+//! Sharded payloads for messages larger than one ChatMsg account.
+//!
+//! finalize_sig_sharded commits shard_count and a Merkle root over the shards instead of storing the payload.
+//! handle_store_shard: moves one uploaded body buffer into ChatShard ["shard", chat_msg, index_le] after
+//! checking its Merkle path against ChatMsg.shard_root, so only the committed bytes can ever be stored.
+//! verify_stark passes the shards (in index order) to reassemble, which re-checks the root before verifying.
+//!
+//! Tree: leaf = H(0x00 || index_le || data), node = H(0x01 || left || right); an odd last node moves up unchanged.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::state::{shard_account_space, BufferPda, ChatMsg, ChatShard, ErrorCode, BUF_HEAD, MAX_SHARD_DATA};
+
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+/// Prefixes shard_commitment so a signed commitment can never also read as an inline cipher || kem.
+pub const SHARD_COMMITMENT_TAG: &[u8] = b"stark-pqc/shard-commitment/v1";
+
+/// Merkle leaf of shard `index`.
+pub fn shard_leaf(index: u16, data: &[u8]) -> [u8; 32] {
+    hashv(&[&[LEAF_TAG], &index.to_le_bytes(), data]).to_bytes()
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&[NODE_TAG], left, right]).to_bytes()
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level.chunks(2).map(|pair| if pair.len() == 2 { node(&pair[0], &pair[1]) } else { pair[0] }).collect()
+}
+
+/// Merkle root over shard leaves in index order; the sender signs it through finalize_sig_sharded.
+pub fn shards_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied().unwrap_or_default()
+}
+
+/// Sibling hashes from leaf `index` up to the root, the `path` argument of store_shard.
+pub fn shard_path(leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+    let (mut level, mut i, mut path) = (leaves.to_vec(), index, Vec::new());
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(i ^ 1) {
+            path.push(*sibling);
+        }
+        level = next_level(&level);
+        i /= 2;
+    }
+    path
+}
+
+/// Checks that `leaf` sits at `index` of a `count`-leaf tree with this root; the path must be consumed exactly.
+pub fn verify_shard_path(root: &[u8; 32], count: usize, index: usize, leaf: [u8; 32], path: &[[u8; 32]]) -> bool {
+    if index >= count {
+        return false;
+    }
+    let (mut acc, mut i, mut n, mut siblings) = (leaf, index, count, path.iter());
+    while n > 1 {
+        if (i ^ 1) < n {
+            let Some(sibling) = siblings.next() else { return false };
+            acc = if i % 2 == 0 { node(&acc, sibling) } else { node(sibling, &acc) };
+        }
+        i /= 2;
+        n = n.div_ceil(2);
+    }
+    siblings.next().is_none() && acc == *root
+}
+
+/// Stands in for cipher || kem in the signed message of a sharded ChatMsg:
+/// SHARD_COMMITMENT_TAG || shard_root || shard_count_le || cipher_len_le || kem_len_le.
+pub fn shard_commitment(shard_root: &[u8; 32], shard_count: u16, cipher_len: u32, kem_len: u32) -> Vec<u8> {
+    [SHARD_COMMITMENT_TAG, &shard_root[..], &shard_count.to_le_bytes(), &cipher_len.to_le_bytes(), &kem_len.to_le_bytes()].concat()
+}
+
+/// Requires cipher || kem to fit the shard_count shards it is committed to, each at most MAX_SHARD_DATA
+/// (= MAX_CHAT_PAYLOAD) bytes; the sum is checked so a wrapped cipher_len + kem_len cannot pass.
+pub(crate) fn check_sharded_lens(cipher_len: u32, kem_len: u32, shard_count: u16) -> Result<()> {
+    let total = (cipher_len as usize).checked_add(kem_len as usize).ok_or(ErrorCode::LenMismatch)?;
+    require!(total <= shard_count as usize * MAX_SHARD_DATA, ErrorCode::LenMismatch);
+    Ok(())
+}
+
+/// Accounts for store_shard; the body buffer holds the shard bytes and is closed to the payer.
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey, slot: u64, index: u16)]
+pub struct StoreShard<'info> {
+    #[account(
+        mut,
+        seeds=[b"buf", payer.key().as_ref()],
+        bump,
+        close = payer
+    )]
+    pub buffer: Account<'info, BufferPda>,
+
+    #[account(seeds=[b"msg", payer.key().as_ref(), recipient.as_ref(), &slot.to_le_bytes()], bump)]
+    pub chat_msg: Account<'info, ChatMsg>,
+
+    #[account(
+        init, payer=payer,
+        space=shard_account_space(buffer.length as usize),
+        seeds=[b"shard", chat_msg.key().as_ref(), &index.to_le_bytes()],
+        bump
+    )]
+    pub chat_shard: Account<'info, ChatShard>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Stores the uploaded body buffer as shard `index` of the payer's sharded ChatMsg.
+/// Fails with ShardInvalid unless `path` proves the bytes are leaf `index` under shard_root.
+pub fn handle_store_shard(ctx: Context<StoreShard>, _recipient: Pubkey, _slot: u64, index: u16, path: Vec<[u8; 32]>) -> Result<()> {
+    let len = ctx.accounts.buffer.length as usize;
+    require!(len <= MAX_SHARD_DATA, ErrorCode::LenMismatch);
+    let data = {
+        let buf_ai = ctx.accounts.buffer.to_account_info();
+        let raw    = buf_ai.data.borrow();
+        raw[BUF_HEAD..BUF_HEAD + len].to_vec()
+    };
+    check_shard(&ctx.accounts.chat_msg, index, &data, &path)?;
+
+    let shard = &mut ctx.accounts.chat_shard;
+    shard.chat_msg = ctx.accounts.chat_msg.key();
+    shard.index    = index;
+    shard.data     = data;
+    Ok(())
+}
+
+/// Accepts shard bytes only for a sharded ChatMsg, at an index below shard_count, with a valid Merkle path.
+pub(crate) fn check_shard(chat: &ChatMsg, index: u16, data: &[u8], path: &[[u8; 32]]) -> Result<()> {
+    let ok = chat.is_sharded()
+        && verify_shard_path(&chat.shard_root, chat.shard_count as usize, index as usize, shard_leaf(index, data), path);
+    require!(ok, ErrorCode::ShardInvalid);
+    Ok(())
+}
+
+/// Concatenates shards 0..shard_count of the ChatMsg at `chat_key` back into cipher || kem || proof.
+/// Fails with ShardInvalid on a missing, extra, foreign or out-of-order shard, or when the root differs.
+pub(crate) fn reassemble(chat_key: &Pubkey, chat: &ChatMsg, shards: &[ChatShard]) -> Result<Vec<u8>> {
+    require!(chat.is_sharded() && shards.len() == chat.shard_count as usize, ErrorCode::ShardInvalid);
+    let mut leaves = Vec::with_capacity(shards.len());
+    for (i, shard) in shards.iter().enumerate() {
+        require!(shard.chat_msg == *chat_key && shard.index as usize == i, ErrorCode::ShardInvalid);
+        leaves.push(shard_leaf(shard.index, &shard.data));
+    }
+    require!(shards_root(&leaves) == chat.shard_root, ErrorCode::ShardInvalid);
+    Ok(shards.iter().flat_map(|s| s.data.iter().copied()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{finalize::verify_payload, state::{test_chat, InputSource}};
+
+    fn sharded_chat(cipher_len: u32, kem_len: u32, shard_count: u16, shard_root: [u8; 32]) -> ChatMsg {
+        ChatMsg { shard_count, shard_root, ..test_chat(cipher_len, kem_len, vec![]) }
+    }
+
+    /// A 25 KB cipher || kem || proof is stored across three ChatShards and reassembles to a verifying payload.
+    #[test]
+    fn reassemble_25kb_from_three_shards() {
+        let cipher: Vec<u8> = (0..16_000u32).map(|i| (i % 251) as u8).collect();
+        let (_params, proof) = stark_prover::generate_proof_for_segment(&cipher).unwrap();
+        let kem = vec![0x42u8; 25_000 - cipher.len() - proof.len()]; // Pads the payload to exactly 25,000 bytes
+        let payload = [&cipher[..], &kem[..], &proof[..]].concat();
+        assert!(payload.len() > 2 * MAX_SHARD_DATA && payload.len() <= 3 * MAX_SHARD_DATA);
+
+        let parts: Vec<&[u8]> = payload.chunks(payload.len().div_ceil(3)).collect();
+        let leaves: Vec<[u8; 32]> = parts.iter().enumerate().map(|(i, d)| shard_leaf(i as u16, d)).collect();
+        let chat_key = Pubkey::new_unique();
+        let chat = sharded_chat(cipher.len() as u32, kem.len() as u32, 3, shards_root(&leaves));
+
+        // store_shard: each part passes its path check and round-trips through ChatShard account data.
+        let mut stored = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            assert!(part.len() <= MAX_SHARD_DATA);
+            check_shard(&chat, i as u16, part, &shard_path(&leaves, i)).unwrap();
+            let shard = ChatShard { chat_msg: chat_key, index: i as u16, data: part.to_vec() };
+            let mut data = Vec::new();
+            shard.try_serialize(&mut data).unwrap();
+            assert_eq!(data.len(), shard_account_space(part.len()));
+            stored.push(ChatShard::try_deserialize(&mut &data[..]).unwrap());
+        }
+
+        let back = reassemble(&chat_key, &chat, &stored).unwrap();
+        assert_eq!(back, payload);
+        assert!(verify_payload(&back, chat.cipher_len, chat.kem_len, InputSource::Cipher).is_ok());
+
+        // Wrong order, a missing shard, a foreign shard or one flipped byte are all refused.
+        let invalid: Error = ErrorCode::ShardInvalid.into();
+        let swapped = [stored[1].clone(), stored[0].clone(), stored[2].clone()];
+        assert_eq!(reassemble(&chat_key, &chat, &swapped).unwrap_err(), invalid);
+        assert_eq!(reassemble(&chat_key, &chat, &stored[..2]).unwrap_err(), invalid);
+        assert_eq!(reassemble(&Pubkey::new_unique(), &chat, &stored).unwrap_err(), invalid);
+        let mut tampered = stored.clone();
+        tampered[2].data[0] ^= 1;
+        assert_eq!(reassemble(&chat_key, &chat, &tampered).unwrap_err(), invalid);
+        assert_eq!(check_shard(&chat, 2, &tampered[2].data, &shard_path(&leaves, 2)).unwrap_err(), invalid);
+        assert_eq!(check_shard(&chat, 1, parts[0], &shard_path(&leaves, 0)).unwrap_err(), invalid);
+        assert_eq!(check_shard(&chat, 3, parts[2], &shard_path(&leaves, 2)).unwrap_err(), invalid);
+    }
+
+    /// cipher || kem must fit the committed shards; the tag keeps a commitment distinct from the same bytes untagged.
+    #[test]
+    fn sharded_lens_and_commitment_tag() {
+        let cap = (3 * MAX_SHARD_DATA) as u32;
+        assert!(check_sharded_lens(16_000, cap - 16_000, 3).is_ok());
+        let mismatch: Error = ErrorCode::LenMismatch.into();
+        assert_eq!(check_sharded_lens(16_000, cap - 15_999, 3).unwrap_err(), mismatch);
+        assert_eq!(check_sharded_lens(MAX_SHARD_DATA as u32 + 1, 0, 1).unwrap_err(), mismatch);
+        assert_eq!(check_sharded_lens(u32::MAX, u32::MAX, 8).unwrap_err(), mismatch);
+
+        let c = shard_commitment(&[7u8; 32], 3, 16_000, 4_000);
+        assert_eq!(c.len(), SHARD_COMMITMENT_TAG.len() + 32 + 2 + 4 + 4);
+        assert!(c.starts_with(SHARD_COMMITMENT_TAG));
+        assert_eq!(&c[SHARD_COMMITMENT_TAG.len()..][..32], &[7u8; 32]);
+    }
+
+    /// Every leaf's path verifies for every tree size, including odd ones; a truncated or padded path does not.
+    #[test]
+    fn shard_paths_verify_for_all_sizes() {
+        for count in 1..=8usize {
+            let leaves: Vec<[u8; 32]> = (0..count).map(|i| shard_leaf(i as u16, &[i as u8; 5])).collect();
+            let root = shards_root(&leaves);
+            for (i, leaf) in leaves.iter().enumerate() {
+                let path = shard_path(&leaves, i);
+                assert!(verify_shard_path(&root, count, i, *leaf, &path));
+                assert!(!verify_shard_path(&root, count, i, shard_leaf(i as u16, b"other"), &path));
+                assert!(!verify_shard_path(&root, count, i, *leaf, &[path.clone(), vec![[0u8; 32]]].concat()));
+                if let Some((_, shorter)) = path.split_last() {
+                    assert!(!verify_shard_path(&root, count, i, *leaf, shorter));
+                }
+            }
+            assert!(!verify_shard_path(&root, count, count, leaves[0], &shard_path(&leaves, 0)));
+        }
+    }
+}
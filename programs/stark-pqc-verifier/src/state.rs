@@ -2,6 +2,11 @@
 //!
 //! BufferPda: streaming buffer (body/signature uploads).
 //! ChatMsg: finalized message (cipher|kem|proof + metadata). Max 10,240B to keep CU predictable.
+//! ChatShard: one slice of a payload too large for ChatMsg, committed by ChatMsg.shard_root.
+//! NonceRegistry: per-sender rolling window of recently used AEAD nonces (replay/reuse guard).
+//! PqIdentity: per-owner registered SLH-DSA verifying key with rotation slot.
+//! StarkConfig: program-wide trusted-relayer switch (skip_stark), set by the upgrade authority.
+//! CoSigners: keys, sig buffers and signature hashes of a finalize_sig_multi ChatMsg.
 
 use anchor_lang::prelude::*;
 
@@ -10,14 +15,54 @@ pub const MAX_ACCOUNT_BYTES: usize = 10_240; // Hard upper bound for allocation
 pub const DISC_SIZE: usize = 8;
 pub const META_HEAD: usize = 32 + 4 + 32; // sender(32) + len(4) + sha(32)
 pub const BUF_HEAD : usize = DISC_SIZE + META_HEAD; // = 76
-pub const CHAT_HEAD: usize = 164;
-pub const MAX_CHAT_PAYLOAD: usize = MAX_ACCOUNT_BYTES - DISC_SIZE - CHAT_HEAD; // = 10,068
+pub const CHAT_HEAD: usize = 215; // Fixed ChatMsg fields (nonce_len + 24-byte nonce, msg_type, shard_count + shard_root, relayer_attested, sig_alg included) + payload Vec prefix(4)
 pub const BUF_ACCOUNT_SPACE: usize = META_HEAD + (MAX_ACCOUNT_BYTES - BUF_HEAD); // = 10,232
-pub const MAX_SIG_PAYLOAD: usize = BUF_ACCOUNT_SPACE - BUF_HEAD; // = 10,156
+// Upload and payload caps live in stark-air so the client and the WASM crate share them
+pub use stark_air::limits::{MAX_CHAT_PAYLOAD, MAX_CHUNK, MAX_PROOF_BYTES, MAX_SIG_PAYLOAD};
+const _: () = assert!(MAX_CHAT_PAYLOAD == MAX_ACCOUNT_BYTES - DISC_SIZE - CHAT_HEAD);
+const _: () = assert!(MAX_SIG_PAYLOAD == BUF_ACCOUNT_SPACE - BUF_HEAD);
 
 // Signature and hashing
 pub const CHAINED_HASH_LEN: usize = 32;
 pub const SIG_BYTES: usize = crate::crypto::SIG_LEN;
+pub const MAX_COSIGNERS: usize = 2; // finalize_sig_multi; each signature costs ≈ 500k CU of the 1.4M limit
+pub const SIG_ALG_SHA2_128S: u8 = 1; // ChatMsg.sig_alg; same id as the signature envelope's alg_id, the only set finalize accepts
+
+// Heap frame bounds accepted by ComputeBudget requestHeapFrame
+pub const MIN_HEAP_BYTES: usize = 32 * 1024;  // Default SBF heap
+pub const MAX_HEAP_BYTES: usize = 256 * 1024; // Allocator contract upper bound
+
+// AEAD nonces: 12 bytes (ChaCha20-Poly1305, AES-GCM) or 24 bytes (XChaCha20-Poly1305)
+pub const NONCE_LEN: usize = 12;  // Also the registry entry size
+pub const XNONCE_LEN: usize = 24; // Largest nonce ChatMsg stores
+
+// Sharded payloads: each shard is moved from one full body buffer
+pub const SHARD_HEAD: usize = 32 + 2 + 4; // chat_msg(32) + index(2) + data Vec prefix(4)
+pub const MAX_SHARD_DATA: usize = MAX_CHAT_PAYLOAD; // upload_body's cap; = 10,017
+pub const MAX_SHARDS: usize = 8; // ≈ 80 KB; verify_stark copies every shard into the heap frame
+
+// Nonce reuse window
+pub const NONCE_WINDOW: usize = 64; // Last 64 nonces per sender
+pub const NONCE_REGISTRY_SPACE: usize = DISC_SIZE + 32 + 1 + 1 + NONCE_LEN * NONCE_WINDOW; // = 810
+
+// Registered PQ identity
+pub const PQ_IDENTITY_SPACE: usize = DISC_SIZE + 32 + 32 + 8 + 8; // = 88
+
+// Trusted-relayer config
+pub const STARK_CONFIG_SPACE: usize = DISC_SIZE + 32 + 32 + 1; // = 73
+
+// Co-signer record: chat_msg + count + (key, sig_pda, sig_hash) per signer
+pub const CO_SIGNERS_SPACE: usize = DISC_SIZE + 32 + 1 + MAX_COSIGNERS * (32 + 32 + 32); // = 233
+
+/// Account space finalize_sig allocates for a ChatMsg holding payload_len bytes of cipher || kem || proof.
+pub const fn chat_account_space(payload_len: usize) -> usize {
+    DISC_SIZE + CHAT_HEAD + payload_len
+}
+
+/// Account space store_shard allocates for a ChatShard holding data_len bytes.
+pub const fn shard_account_space(data_len: usize) -> usize {
+    DISC_SIZE + SHARD_HEAD + data_len
+}
 
 #[account]
 pub struct BufferPda {
@@ -29,21 +74,456 @@ pub struct BufferPda {
 /// Finalized chat message: payload = cipher || kem || proof (STARK proof).
 #[account]
 pub struct ChatMsg {
-    pub sender    : Pubkey,
-    pub recipient : Pubkey,
-    pub cipher_len: u32,
-    pub kem_len   : u32,
-    pub nonce     : [u8; 12],
-    pub slot      : u64,
-    pub sig_pda   : Pubkey,
-    pub sig_len   : u32,
-    pub sig_hash  : [u8; 32],
-    pub payload   : Vec<u8>, // cipher || kem || proof
-}
-
-#[error_code(offset = 7000)]
+    pub sender          : Pubkey,
+    pub recipient       : Pubkey,
+    pub cipher_len      : u32,
+    pub kem_len         : u32,
+    pub nonce_len       : u8,               // NONCE_LEN or XNONCE_LEN
+    pub nonce           : [u8; XNONCE_LEN], // First nonce_len bytes used, rest zero
+    pub slot            : u64,
+    pub sig_pda         : Pubkey,
+    pub sig_len         : u32,
+    pub sig_hash        : [u8; 32],
+    pub msg_type        : u16,              // Application-defined kind (text, file, reaction, ...); signed
+    pub shard_count     : u16,              // 0: payload inline; otherwise the payload lives in this many ChatShards
+    pub shard_root      : [u8; 32],         // Merkle root over the shards (zero when inline); signed
+    pub relayer_attested: bool,             // STARK checked off chain by the config's relayer, not by verify_stark
+    pub sig_alg         : u8,               // SLH-DSA parameter set (SIG_ALG_*) that signed the message; signed
+    pub payload         : Vec<u8>,          // cipher || kem || proof; empty when sharded
+}
+
+/// One slice of a sharded payload (seeds: "shard", chat_msg, index_le).
+/// Concatenating shards 0..shard_count gives cipher || kem || proof.
+#[account]
+pub struct ChatShard {
+    pub chat_msg: Pubkey,
+    pub index   : u16,
+    pub data    : Vec<u8>,
+}
+
+impl ChatMsg {
+    /// True when the payload is stored in ChatShards rather than inline.
+    pub fn is_sharded(&self) -> bool {
+        self.shard_count != 0
+    }
+
+    /// Stores a nonce already checked by check_nonce_len, zero-filling the unused tail.
+    pub fn set_nonce(&mut self, nonce: &[u8]) {
+        self.nonce = [0u8; XNONCE_LEN];
+        self.nonce[..nonce.len()].copy_from_slice(nonce);
+        self.nonce_len = nonce.len() as u8;
+    }
+
+    /// The AEAD nonce as passed to finalize_sig (12 or 24 bytes).
+    pub fn aead_nonce(&self) -> &[u8] {
+        &self.nonce[..(self.nonce_len as usize).min(XNONCE_LEN)]
+    }
+
+    /// Cipher segment of the payload.
+    pub fn cipher(&self) -> Result<&[u8]> {
+        Ok(split_payload(&self.payload, self.cipher_len, self.kem_len)?.0)
+    }
+
+    /// KEM ciphertext segment of the payload.
+    pub fn kem(&self) -> Result<&[u8]> {
+        Ok(split_payload(&self.payload, self.cipher_len, self.kem_len)?.1)
+    }
+
+    /// STARK proof blob (hash-id || proof), everything after cipher || kem.
+    pub fn proof(&self) -> Result<&[u8]> {
+        Ok(split_payload(&self.payload, self.cipher_len, self.kem_len)?.2)
+    }
+}
+
+/// Splits cipher || kem || proof by the recorded lengths; LenMismatch if they overrun the payload.
+pub fn split_payload(payload: &[u8], cipher_len: u32, kem_len: u32) -> Result<(&[u8], &[u8], &[u8])> {
+    let cipher_end = cipher_len as usize;
+    let kem_end = cipher_end
+        .checked_add(kem_len as usize)
+        .ok_or(ErrorCode::LenMismatch)?;
+    require!(kem_end <= payload.len(), ErrorCode::LenMismatch);
+    Ok((&payload[..cipher_end], &payload[cipher_end..kem_end], &payload[kem_end..]))
+}
+
+/// Accepts only the nonce sizes ChatMsg can store: NONCE_LEN or XNONCE_LEN.
+pub fn check_nonce_len(nonce: &[u8]) -> Result<()> {
+    require!(nonce.len() == NONCE_LEN || nonce.len() == XNONCE_LEN, ErrorCode::NonceLengthInvalid);
+    Ok(())
+}
+
+/// Accepts only the SLH-DSA parameter sets this build verifies (SIG_ALG_SHA2_128S).
+pub fn check_sig_alg(sig_alg: u8) -> Result<()> {
+    require!(sig_alg == SIG_ALG_SHA2_128S, ErrorCode::SigAlgUnsupported);
+    Ok(())
+}
+
+/// Requires an instruction's `recipient` argument to be the recipient account its PDA seeds use.
+/// The handlers key every buffer by the account, so a differing argument would be silently ignored.
+pub fn check_recipient(recipient: &Pubkey, account: &Pubkey) -> Result<()> {
+    require_keys_eq!(*recipient, *account, ErrorCode::RecipientMismatch);
+    Ok(())
+}
+
+/// Payload segment hashed into the STARK public inputs (seed, inc); prover and verifier must agree.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputSource {
+    Cipher, // SHA-256(cipher), the default binding
+    Kem,    // SHA-256(kem_ciphertext), binds the encapsulation into the statement
+}
+
+/// Per-sender ring buffer of the last NONCE_WINDOW nonces accepted by finalize_sig.
+#[account]
+pub struct NonceRegistry {
+    pub sender: Pubkey,
+    pub len   : u8, // Filled entries (≤ NONCE_WINDOW)
+    pub head  : u8, // Next entry to overwrite
+    pub recent: [[u8; NONCE_LEN]; NONCE_WINDOW],
+}
+
+impl NonceRegistry {
+    /// Window entry for a nonce: 12-byte nonces as-is, longer ones as SHA-256(nonce)[..12].
+    /// Keeps existing 810-byte registries valid; a 96-bit digest collision inside 64 entries is negligible.
+    pub fn entry(nonce: &[u8]) -> [u8; NONCE_LEN] {
+        let mut out = [0u8; NONCE_LEN];
+        if nonce.len() == NONCE_LEN {
+            out.copy_from_slice(nonce);
+        } else {
+            out.copy_from_slice(&anchor_lang::solana_program::hash::hashv(&[nonce]).to_bytes()[..NONCE_LEN]);
+        }
+        out
+    }
+
+    /// Returns true if the nonce is inside the current window.
+    pub fn contains(&self, nonce: impl AsRef<[u8]>) -> bool {
+        let entry = Self::entry(nonce.as_ref());
+        self.recent[..self.len as usize].contains(&entry)
+    }
+
+    /// Rejects a nonce already in the window, otherwise records it (evicting the oldest).
+    pub fn check_and_record(&mut self, nonce: impl AsRef<[u8]>) -> Result<()> {
+        let nonce = nonce.as_ref();
+        require!(!self.contains(nonce), ErrorCode::NonceReused);
+        self.recent[self.head as usize] = Self::entry(nonce);
+        self.head = ((self.head as usize + 1) % NONCE_WINDOW) as u8;
+        if (self.len as usize) < NONCE_WINDOW { self.len += 1; }
+        Ok(())
+    }
+}
+
+/// Trusted-relayer switch (seeds: "config"); only the upgrade authority can write it.
+#[account]
+pub struct StarkConfig {
+    pub admin     : Pubkey, // Upgrade authority that last set it
+    pub relayer   : Pubkey, // Relayer whose attestation finalize_sig accepts
+    pub skip_stark: bool,   // Off: relayer attestations are refused
+}
+
+/// Signers of a finalize_sig_multi ChatMsg (seeds: "cosig", chat_msg), in slh_pubs order.
+/// Entries past count are zero; entry 0 repeats ChatMsg.sig_pda and sig_hash.
+#[account]
+pub struct CoSigners {
+    pub chat_msg  : Pubkey,
+    pub count     : u8,
+    pub keys      : [[u8; 32]; MAX_COSIGNERS],
+    pub sig_pdas  : [Pubkey; MAX_COSIGNERS],
+    pub sig_hashes: [[u8; 32]; MAX_COSIGNERS], // SHA-256 of each verified signature, as ChatMsg.sig_hash
+}
+
+impl CoSigners {
+    /// Replaces the record with `signers` (key, sig buffer, signature hash); at most MAX_COSIGNERS.
+    pub fn record(&mut self, chat_msg: Pubkey, signers: &[([u8; 32], Pubkey, [u8; 32])]) {
+        self.chat_msg = chat_msg;
+        self.count = signers.len() as u8;
+        self.keys = [[0u8; 32]; MAX_COSIGNERS];
+        self.sig_pdas = [Pubkey::default(); MAX_COSIGNERS];
+        self.sig_hashes = [[0u8; 32]; MAX_COSIGNERS];
+        for (i, (key, sig_pda, sig_hash)) in signers.iter().enumerate() {
+            self.keys[i] = *key;
+            self.sig_pdas[i] = *sig_pda;
+            self.sig_hashes[i] = *sig_hash;
+        }
+    }
+}
+
+/// Owner's registered SLH-DSA verifying key (seeds: "pqid", owner).
+#[account]
+pub struct PqIdentity {
+    pub owner          : Pubkey,
+    pub vk             : [u8; 32],
+    pub registered_slot: u64,
+    pub rotated_at_slot: u64, // 0 until the first rotation
+}
+
+impl PqIdentity {
+    /// Registers vk on a fresh account; re-registering the current key is a no-op,
+    /// and a different key is a rotation.
+    pub fn register_or_update(&mut self, owner: Pubkey, vk: [u8; 32], slot: u64) {
+        if self.owner == Pubkey::default() {
+            self.owner = owner;
+            self.vk = vk;
+            self.registered_slot = slot;
+        } else {
+            self.rotate(vk, slot);
+        }
+    }
+
+    /// Replaces the key and records the slot; rotating to the current key changes nothing.
+    pub fn rotate(&mut self, vk: [u8; 32], slot: u64) {
+        if self.vk != vk {
+            self.vk = vk;
+            self.rotated_at_slot = slot;
+        }
+    }
+
+    /// Accepts only the currently registered key.
+    pub fn check_key(&self, vk: &[u8; 32]) -> Result<()> {
+        require!(&self.vk == vk, ErrorCode::IdentityKeyMismatch);
+        Ok(())
+    }
+}
+
+/// Every error the program returns, in one enum.
+/// Two stable code ranges: upload errors at 6000 + position (the former UploadError codes), and every
+/// other error at 7000 + position (the former offset-7000 enum). New variants are appended to the second range.
+#[error_code]
 pub enum ErrorCode {
-    #[msg("length mismatch")]           LenMismatch,
+    #[msg("chunk > 900 bytes")] ChunkTooLarge,
+    #[msg("offset mismatch")]   OffsetMismatch,
+    #[msg("buffer overflow")]   MsgTooBig,
+    #[msg("hash mismatch")]     HashMismatch,
+    #[msg("signature buffer is frozen (finalized)")] AlreadyFinalized,
+    #[msg("empty chunk")]       EmptyChunk,
+    #[msg("signature upload would exceed 7,856 bytes")] SigTooLong,
+    #[msg("signer is not the buffer's sender")] NotBufferSender,
+    #[msg("length mismatch")]           LenMismatch = 1000,
     #[msg("signature verify failed")]   SigFailed,
     #[msg("STARK proof verify failed")] ProofFailed,
+    #[msg("proof hash-id not supported by this build")] UnsupportedHashId,
+    #[msg("nonce already used by this sender")] NonceReused,
+    #[msg("heap limit must be a 1024-byte multiple of at least 32 KiB")] HeapLimitInvalid,
+    #[msg("signature buffer does not match sig_hash")] SigHashMismatch,
+    #[msg("key is not the owner's registered PQ identity")] IdentityKeyMismatch,
+    #[msg("signer list must hold 1..=2 distinct keys, one per signature buffer")] SignerCountInvalid,
+    #[msg("signature buffer missing or empty: run init_signature and upload_signature first")] SigBufferMissing,
+    #[msg("AEAD nonce must be 12 or 24 bytes")] NonceLengthInvalid,
+    #[msg("shard count, index, account or Merkle path does not match ChatMsg.shard_root")] ShardInvalid,
+    #[msg("signer is not the program upgrade authority")] ConfigAuthorityMismatch,
+    #[msg("relayer attestation needs config.skip_stark on and this relayer configured")] RelayerNotAuthorized,
+    #[msg("SLH-DSA parameter set not supported by this build")] SigAlgUnsupported,
+    #[msg("recipient argument does not match the recipient account used for PDA seeds")] RecipientMismatch,
+    #[msg("heap_bytes exceeds the transaction's requestHeapFrame")] HeapFrameExceeded,
+    #[msg("co-signer needs a registered PqIdentity for its key and its own sig PDA")] CoSignerUnbound,
+}
+
+/// An unsharded, zeroed ChatMsg over `payload` shared by the tests; they override fields with struct update syntax.
+#[cfg(test)]
+pub(crate) fn test_chat(cipher_len: u32, kem_len: u32, payload: Vec<u8>) -> ChatMsg {
+    ChatMsg {
+        sender: Pubkey::default(), recipient: Pubkey::default(), cipher_len, kem_len,
+        nonce_len: NONCE_LEN as u8, nonce: [0u8; XNONCE_LEN], slot: 0, sig_pda: Pubkey::default(), sig_len: 0, sig_hash: [0u8; 32], msg_type: 0,
+        shard_count: 0, shard_root: [0u8; 32], relayer_attested: false, sig_alg: SIG_ALG_SHA2_128S, payload,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_registry() -> NonceRegistry {
+        NonceRegistry { sender: Pubkey::default(), len: 0, head: 0, recent: [[0u8; NONCE_LEN]; NONCE_WINDOW] }
+    }
+
+    /// Pins the derived size constants to their documented values and to what Anchor actually serializes,
+    /// so a layout change that breaks the 10,240-byte cap fails here rather than on-chain.
+    #[test]
+    fn account_layout_invariants() {
+        assert_eq!((BUF_HEAD, BUF_ACCOUNT_SPACE, MAX_SIG_PAYLOAD), (76, 10_232, 10_156));
+        assert_eq!((CHAT_HEAD, MAX_CHAT_PAYLOAD, MAX_SHARD_DATA), (215, 10_017, 10_017));
+        assert_eq!((NONCE_REGISTRY_SPACE, PQ_IDENTITY_SPACE, STARK_CONFIG_SPACE, CO_SIGNERS_SPACE), (810, 88, 73, 233));
+        assert_eq!(BUF_HEAD + MAX_SIG_PAYLOAD, BUF_ACCOUNT_SPACE);
+        assert_eq!(DISC_SIZE + CHAT_HEAD + MAX_CHAT_PAYLOAD, MAX_ACCOUNT_BYTES);
+        assert_eq!(chat_account_space(MAX_CHAT_PAYLOAD), MAX_ACCOUNT_BYTES);
+        const { assert!(BUF_ACCOUNT_SPACE <= MAX_ACCOUNT_BYTES) };
+        assert!(shard_account_space(MAX_SHARD_DATA) <= MAX_ACCOUNT_BYTES);
+        const { assert!(SIG_BYTES <= MAX_SIG_PAYLOAD && MAX_PROOF_BYTES <= MAX_CHAT_PAYLOAD) };
+
+        // The header constants match the serialized sizes of empty accounts.
+        let mut data = Vec::new();
+        BufferPda { sender: Pubkey::default(), length: 0, sha_chain: [0u8; CHAINED_HASH_LEN] }.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), BUF_HEAD);
+        let mut data = Vec::new();
+        test_chat(0, 0, vec![]).try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), DISC_SIZE + CHAT_HEAD);
+        let mut data = Vec::new();
+        ChatShard { chat_msg: Pubkey::default(), index: 0, data: vec![] }.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), shard_account_space(0));
+        let mut data = Vec::new();
+        empty_registry().try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), NONCE_REGISTRY_SPACE);
+        let mut data = Vec::new();
+        PqIdentity { owner: Pubkey::default(), vk: [0u8; 32], registered_slot: 0, rotated_at_slot: 0 }.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), PQ_IDENTITY_SPACE);
+        let mut data = Vec::new();
+        StarkConfig { admin: Pubkey::default(), relayer: Pubkey::default(), skip_stark: false }.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), STARK_CONFIG_SPACE);
+        let mut data = Vec::new();
+        let cosigners = CoSigners {
+            chat_msg: Pubkey::default(), count: 0, keys: [[0u8; 32]; MAX_COSIGNERS],
+            sig_pdas: [Pubkey::default(); MAX_COSIGNERS], sig_hashes: [[0u8; 32]; MAX_COSIGNERS],
+        };
+        cosigners.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), CO_SIGNERS_SPACE);
+    }
+
+    /// A second finalize with the same nonce is refused.
+    #[test]
+    fn nonce_reuse_rejected() {
+        let mut reg = empty_registry();
+        assert!(reg.check_and_record([1u8; NONCE_LEN]).is_ok());
+        assert!(reg.check_and_record([2u8; NONCE_LEN]).is_ok());
+        assert!(reg.check_and_record([1u8; NONCE_LEN]).is_err());
+        // All-zero nonce is not mistaken for an unfilled entry.
+        assert!(reg.check_and_record([0u8; NONCE_LEN]).is_ok());
+    }
+
+    /// A recipient argument naming another key than the seeds' recipient account is refused.
+    #[test]
+    fn recipient_mismatch_rejected() {
+        let (bob, carol) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(check_recipient(&bob, &bob).is_ok());
+        assert_eq!(check_recipient(&bob, &carol).unwrap_err(), ErrorCode::RecipientMismatch.into());
+        assert_eq!(check_recipient(&Pubkey::default(), &carol).unwrap_err(), ErrorCode::RecipientMismatch.into());
+    }
+
+    /// Every error keeps its published code (uploads from 6000, the rest from 7000) and has a row in the README's error table.
+    #[test]
+    fn error_codes_distinct_and_documented() {
+        let readme = include_str!("../README.md");
+        let all = [
+            (ErrorCode::ChunkTooLarge, "ChunkTooLarge"),
+            (ErrorCode::OffsetMismatch, "OffsetMismatch"),
+            (ErrorCode::MsgTooBig, "MsgTooBig"),
+            (ErrorCode::HashMismatch, "HashMismatch"),
+            (ErrorCode::AlreadyFinalized, "AlreadyFinalized"),
+            (ErrorCode::EmptyChunk, "EmptyChunk"),
+            (ErrorCode::SigTooLong, "SigTooLong"),
+            (ErrorCode::NotBufferSender, "NotBufferSender"),
+            (ErrorCode::LenMismatch, "LenMismatch"),
+            (ErrorCode::SigFailed, "SigFailed"),
+            (ErrorCode::ProofFailed, "ProofFailed"),
+            (ErrorCode::UnsupportedHashId, "UnsupportedHashId"),
+            (ErrorCode::NonceReused, "NonceReused"),
+            (ErrorCode::HeapLimitInvalid, "HeapLimitInvalid"),
+            (ErrorCode::SigHashMismatch, "SigHashMismatch"),
+            (ErrorCode::IdentityKeyMismatch, "IdentityKeyMismatch"),
+            (ErrorCode::SignerCountInvalid, "SignerCountInvalid"),
+            (ErrorCode::SigBufferMissing, "SigBufferMissing"),
+            (ErrorCode::NonceLengthInvalid, "NonceLengthInvalid"),
+            (ErrorCode::ShardInvalid, "ShardInvalid"),
+            (ErrorCode::ConfigAuthorityMismatch, "ConfigAuthorityMismatch"),
+            (ErrorCode::RelayerNotAuthorized, "RelayerNotAuthorized"),
+            (ErrorCode::SigAlgUnsupported, "SigAlgUnsupported"),
+            (ErrorCode::RecipientMismatch, "RecipientMismatch"),
+            (ErrorCode::HeapFrameExceeded, "HeapFrameExceeded"),
+            (ErrorCode::CoSignerUnbound, "CoSignerUnbound"),
+        ];
+        let mut seen = std::collections::HashSet::new();
+        for (i, (err, name)) in all.into_iter().enumerate() {
+            let code = u32::from(err);
+            let expected = if i < 8 { 6000 + i as u32 } else { 7000 + (i - 8) as u32 };
+            assert_eq!(code, expected, "{name}");
+            assert!(seen.insert(code));
+            assert!(readme.contains(&format!("| {code} | {name} |")), "{name} missing from README");
+        }
+    }
+
+    /// Getters slice cipher || kem || proof by the recorded lengths.
+    #[test]
+    fn chat_getters_well_formed() {
+        let msg = test_chat(2, 3, vec![1, 1, 2, 2, 2, 3]);
+        assert_eq!(msg.cipher().unwrap(), &[1, 1]);
+        assert_eq!(msg.kem().unwrap(), &[2, 2, 2]);
+        assert_eq!(msg.proof().unwrap(), &[3]);
+
+        // Exactly cipher || kem leaves an empty proof; zero lengths are allowed.
+        assert!(test_chat(2, 4, vec![0; 6]).proof().unwrap().is_empty());
+        assert_eq!(test_chat(0, 0, vec![9]).proof().unwrap(), &[9]);
+    }
+
+    /// Lengths that overrun the payload are refused instead of panicking.
+    #[test]
+    fn chat_getters_malformed() {
+        for (cl, kl, len) in [(7, 0, 6), (2, 5, 6), (u32::MAX, u32::MAX, 6), (0, 1, 0)] {
+            let msg = test_chat(cl, kl, vec![0; len]);
+            assert_eq!(msg.cipher().unwrap_err(), ErrorCode::LenMismatch.into());
+            assert_eq!(msg.kem().unwrap_err(), ErrorCode::LenMismatch.into());
+            assert_eq!(msg.proof().unwrap_err(), ErrorCode::LenMismatch.into());
+        }
+    }
+
+    /// 24-byte nonces share the window with 12-byte ones and are stored back in full.
+    #[test]
+    fn extended_nonce_recorded() {
+        let mut reg = empty_registry();
+        let (short, long) = ([5u8; NONCE_LEN], [5u8; XNONCE_LEN]);
+        let mut other = long;
+        other[XNONCE_LEN - 1] ^= 1;
+        reg.check_and_record(short).unwrap();
+        reg.check_and_record(long).unwrap();
+        assert!(reg.check_and_record(other).is_ok());
+        assert_eq!(reg.check_and_record(long).unwrap_err(), ErrorCode::NonceReused.into());
+        assert_eq!(reg.check_and_record(short).unwrap_err(), ErrorCode::NonceReused.into());
+
+        let mut msg = test_chat(0, 0, vec![]);
+        msg.set_nonce(&long);
+        assert_eq!((msg.nonce_len as usize, msg.aead_nonce()), (XNONCE_LEN, &long[..]));
+        msg.set_nonce(&short);
+        assert_eq!(msg.aead_nonce(), &short[..]);
+        assert_eq!(msg.nonce[NONCE_LEN..], [0u8; XNONCE_LEN - NONCE_LEN]);
+
+        for len in [0, 11, 13, 23, 25, 32] {
+            assert_eq!(check_nonce_len(&vec![0u8; len]).unwrap_err(), ErrorCode::NonceLengthInvalid.into());
+        }
+        assert!(check_nonce_len(&short).is_ok() && check_nonce_len(&long).is_ok());
+    }
+
+    /// The oldest nonce falls out of the window once it wraps.
+    #[test]
+    fn nonce_window_evicts_oldest() {
+        let mut reg = empty_registry();
+        for i in 0..NONCE_WINDOW as u16 {
+            let mut n = [0u8; NONCE_LEN];
+            n[..2].copy_from_slice(&i.to_le_bytes());
+            reg.check_and_record(n).unwrap();
+        }
+        let first = [0u8; NONCE_LEN];
+        assert!(reg.contains(first));
+        reg.check_and_record([0xff; NONCE_LEN]).unwrap();
+        assert!(!reg.contains(first));
+        assert_eq!(reg.len as usize, NONCE_WINDOW);
+    }
+
+    /// Register is idempotent; after a rotation only the new key passes check_key.
+    #[test]
+    fn identity_rotation_switches_key() {
+        let owner = Pubkey::new_unique();
+        let (old, new) = ([1u8; 32], [2u8; 32]);
+        let mut id = PqIdentity { owner: Pubkey::default(), vk: [0u8; 32], registered_slot: 0, rotated_at_slot: 0 };
+
+        id.register_or_update(owner, old, 10);
+        id.register_or_update(owner, old, 11);
+        assert_eq!((id.owner, id.registered_slot, id.rotated_at_slot), (owner, 10, 0));
+        assert!(id.check_key(&old).is_ok());
+
+        id.rotate(new, 20);
+        assert_eq!(id.rotated_at_slot, 20);
+        assert!(id.check_key(&new).is_ok());
+        assert_eq!(id.check_key(&old).unwrap_err(), ErrorCode::IdentityKeyMismatch.into());
+
+        // Re-registering the same key does not move the rotation slot; a new key through register does.
+        id.register_or_update(owner, new, 30);
+        assert_eq!(id.rotated_at_slot, 20);
+        id.register_or_update(owner, old, 40);
+        assert_eq!((id.vk, id.rotated_at_slot, id.registered_slot), (old, 40, 10));
+    }
 }
@@ -0,0 +1,34 @@
+//! AccountInfo fixtures shared by the handler tests.
+//!
+//! Storage is leaked so a test Account, Signer or Context can borrow it for 'static.
+
+use anchor_lang::prelude::*;
+
+/// An AccountInfo for `key` owned by `owner` and holding `data`; the system program's own account is executable.
+pub(crate) fn leak_info(key: Pubkey, signer: bool, owner: Pubkey, data: Vec<u8>) -> &'static AccountInfo<'static> {
+    let executable = key == anchor_lang::system_program::ID;
+    let (key, owner, lamports) = (Box::leak(Box::new(key)), Box::leak(Box::new(owner)), Box::leak(Box::new(1_000_000_000u64)));
+    Box::leak(Box::new(AccountInfo::new(key, signer, true, lamports, data.leak(), owner, executable, 0)))
+}
+
+/// A program-owned account at a fresh address holding `data`.
+pub(crate) fn program_account(data: Vec<u8>) -> &'static AccountInfo<'static> {
+    leak_info(Pubkey::new_unique(), false, crate::ID, data)
+}
+
+/// An address nobody created: no data, owned by the system program.
+pub(crate) fn empty_account(key: Pubkey) -> &'static AccountInfo<'static> {
+    leak_info(key, false, anchor_lang::system_program::ID, vec![])
+}
+
+/// A signing wallet at a fresh address.
+pub(crate) fn wallet() -> &'static AccountInfo<'static> {
+    leak_info(Pubkey::new_unique(), true, anchor_lang::system_program::ID, vec![])
+}
+
+/// Serialized account data (discriminator first) for `acc`, followed by `tail`.
+pub(crate) fn account_data<T: AccountSerialize>(acc: &T, tail: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    acc.try_serialize(&mut data).unwrap();
+    [data, tail.to_vec()].concat()
+}
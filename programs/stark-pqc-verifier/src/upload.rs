@@ -1,14 +1,76 @@
 //! Chunked upload for (A) message body and (B) signature buffers.
 //!
 //! DoS control via hash-chaining: each chunk provides SHA256(prev_chain || data).
+//! The blake3-chain feature swaps in BLAKE3. SHA-256 stays the default until upload_body CU is measured for both
+//! (README, Upload chain hash); BLAKE3's syscall is not enabled on mainnet-beta.
 //! Stable offsets and bounded chunk size to keep CU predictable under SBF.
+//! Re-sending the last accepted chunk unchanged is an idempotent no-op (safe RPC retries).
+//! Both uploads return the buffer's (length, sha_chain) as return data, so the next chunk needs no account read.
 
 use anchor_lang::prelude::*;
+#[cfg(not(feature = "blake3-chain"))]
 use anchor_lang::solana_program::hash::hashv;
 
-use crate::state::{BufferPda, MAX_CHAT_PAYLOAD, CHAINED_HASH_LEN, BUF_HEAD, MAX_SIG_PAYLOAD};
+use crate::state::{check_recipient, BufferPda, ErrorCode, MAX_CHAT_PAYLOAD, CHAINED_HASH_LEN, BUF_HEAD, MAX_SIG_PAYLOAD, MAX_CHUNK, SIG_BYTES};
+
+/// Computes the next chain value H(prev || data) that write_chunk expects.
+/// SHA-256 by default, BLAKE3 with the blake3-chain feature (clients must match).
+pub fn next_chain(prev: &[u8; CHAINED_HASH_LEN], data: &[u8]) -> [u8; CHAINED_HASH_LEN] {
+    #[cfg(not(feature = "blake3-chain"))]
+    { hashv(&[prev, data]).to_bytes() }
+    #[cfg(feature = "blake3-chain")]
+    { anchor_lang::solana_program::blake3::hashv(&[prev, data]).to_bytes() }
+}
+
+/// Folds next_chain over chunks from the zero seed: the sha_chain a buffer holds after uploading them in order.
+pub fn fold_chain(chunks: &[&[u8]]) -> [u8; CHAINED_HASH_LEN] {
+    chunks.iter().fold([0u8; CHAINED_HASH_LEN], |prev, chunk| next_chain(&prev, chunk))
+}
+
+// Account-data byte range [start, end) of a chunk at offset, with overflow-checked arithmetic.
+// MsgTooBig if the chunk would pass hard_max or the end of the account data.
+fn chunk_span(offset: u32, len: usize, hard_max: usize, account_len: usize) -> Result<(usize, usize)> {
+    let end = (offset as usize).checked_add(len).ok_or(ErrorCode::MsgTooBig)?;
+    require!(end <= hard_max, ErrorCode::MsgTooBig);
+    let start = BUF_HEAD.checked_add(offset as usize).ok_or(ErrorCode::MsgTooBig)?;
+    let stop = BUF_HEAD.checked_add(end).ok_or(ErrorCode::MsgTooBig)?;
+    require!(stop <= account_len, ErrorCode::MsgTooBig);
+    Ok((start, stop))
+}
+
+// True when (offset, data, next_hash) is exactly the last accepted chunk, e.g. an RPC retry.
+fn is_resent_last_chunk(
+    buf: &Account<BufferPda>,
+    offset: u32,
+    data: &[u8],
+    next_hash: &[u8; CHAINED_HASH_LEN],
+) -> bool {
+    match (offset as usize).checked_add(data.len()) {
+        Some(end) if end == buf.length as usize && *next_hash == buf.sha_chain => {}
+        _ => return false,
+    }
+    let acc_info = buf.to_account_info();
+    let stored = acc_info.data.borrow();
+    let span = chunk_span(offset, data.len(), usize::MAX, stored.len());
+    span.is_ok_and(|(start, stop)| stored[start..stop] == *data)
+}
+
+/// Buffer state after an upload, returned via set_return_data (4 + 32 = 36 bytes).
+/// `length` is the next chunk's offset and `sha_chain` its prev value.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UploadProgress {
+    pub length   : u32,
+    pub sha_chain: [u8; CHAINED_HASH_LEN],
+}
+
+impl UploadProgress {
+    fn of(buf: &BufferPda) -> Self {
+        UploadProgress { length: buf.length, sha_chain: buf.sha_chain }
+    }
+}
 
 /// Common helper: write one chunk into a BufferPda with hash-chaining.
+/// Returns the buffer's state afterwards (unchanged for a resent last chunk).
 fn write_chunk(
     buf: &mut Account<BufferPda>,
     _who: &Signer,
@@ -16,31 +78,33 @@ fn write_chunk(
     data:   Vec<u8>,
     next_hash: [u8; CHAINED_HASH_LEN],
     hard_max: usize,
-) -> Result<()> {
-    require!(data.len() <= 900, UploadError::ChunkTooLarge);
-    require!(offset as usize == buf.length as usize, UploadError::OffsetMismatch);
-    require!((offset as usize + data.len()) <= hard_max, UploadError::MsgTooBig);
-
-    let calc = hashv(&[&buf.sha_chain, &data[..]]).to_bytes();
-    require!(calc[..] == next_hash[..], UploadError::HashMismatch);
+) -> Result<UploadProgress> {
+    require!(!data.is_empty(), ErrorCode::EmptyChunk);
+    require!(data.len() <= MAX_CHUNK, ErrorCode::ChunkTooLarge);
+    if is_resent_last_chunk(buf, offset, &data, &next_hash) {
+        return Ok(UploadProgress::of(buf)); // Retry of the chunk that produced the current chain: no-op
+    }
+    require!(offset as usize == buf.length as usize, ErrorCode::OffsetMismatch);
 
     let acc_info = buf.to_account_info();
-    let mut dst  = acc_info.data.borrow_mut();
-    let start = BUF_HEAD + offset as usize;
-    dst[start .. start + data.len()].copy_from_slice(&data);
+    let (start, stop) = chunk_span(offset, data.len(), hard_max, acc_info.data_len())?;
+
+    let calc = next_chain(&buf.sha_chain, &data);
+    require!(calc[..] == next_hash[..], ErrorCode::HashMismatch);
+
+    let mut dst = acc_info.data.borrow_mut();
+    dst[start..stop].copy_from_slice(&data);
 
     buf.length    += data.len() as u32;
     buf.sha_chain  = next_hash;
-    Ok(())
+    Ok(UploadProgress::of(buf))
 }
 
-#[error_code]
-pub enum UploadError {
-    #[msg("chunk > 900 bytes")] ChunkTooLarge,
-    #[msg("offset mismatch")]   OffsetMismatch,
-    #[msg("buffer overflow")]   MsgTooBig,
-    #[msg("hash mismatch")]     HashMismatch,
-    #[msg("signature buffer is frozen (finalized)")] AlreadyFinalized,
+// A signature is exactly SIG_BYTES long, so a chunk ending past it is refused at upload, not at finalize.
+fn check_sig_end(offset: u32, len: usize) -> Result<()> {
+    let end = (offset as usize).checked_add(len).ok_or(ErrorCode::SigTooLong)?;
+    require!(end <= SIG_BYTES, ErrorCode::SigTooLong);
+    Ok(())
 }
 
 /// Accounts for appending to the body buffer (buf).
@@ -57,7 +121,7 @@ pub fn handle_upload_body(
     offset: u32,
     data:   Vec<u8>,
     next_hash: [u8; CHAINED_HASH_LEN],
-) -> Result<()> {
+) -> Result<UploadProgress> {
     write_chunk(&mut ctx.accounts.buffer, &ctx.accounts.sender, offset, data, next_hash, MAX_CHAT_PAYLOAD)
 }
 
@@ -83,14 +147,16 @@ pub struct UploadSignature<'info> {
 /// Appends a signature chunk with hash-chaining.
 pub fn handle_upload_signature(
     ctx: Context<UploadSignature>,
-    _recipient: Pubkey,
+    recipient: Pubkey,
     _slot: u64,
     offset: u32,
     data:   Vec<u8>,
     next_hash: [u8; CHAINED_HASH_LEN],
-) -> Result<()> {
+) -> Result<UploadProgress> {
+    check_recipient(&recipient, &ctx.accounts.recipient.key())?;
     let chat_ai = ctx.accounts.chat_msg.to_account_info();
-    require!(chat_ai.data_is_empty(), UploadError::AlreadyFinalized);
+    require!(chat_ai.data_is_empty(), ErrorCode::AlreadyFinalized);
+    check_sig_end(offset, data.len())?;
     // Enforces the signature PDA payload cap (10,156 bytes).
     write_chunk(
         &mut ctx.accounts.buffer,
@@ -101,3 +167,187 @@ pub fn handle_upload_signature(
         MAX_SIG_PAYLOAD,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::BUF_ACCOUNT_SPACE;
+    use crate::test_accounts::{program_account, wallet};
+
+    /// Runs f against a fresh, empty BufferPda owned by the program and a signer.
+    fn with_buffer(f: impl FnOnce(&mut Account<BufferPda>, &Signer)) {
+        let mut data = vec![0u8; BUF_ACCOUNT_SPACE];
+        data[..8].copy_from_slice(&BufferPda::DISCRIMINATOR[..8]);
+        let mut buf = Account::<BufferPda>::try_from(program_account(data)).unwrap();
+        let signer = Signer::try_from(wallet()).unwrap();
+        f(&mut buf, &signer);
+    }
+
+    #[test]
+    fn empty_chunk_rejected() {
+        with_buffer(|buf, who| {
+            let next = next_chain(&buf.sha_chain, &[]);
+            let err = write_chunk(buf, who, 0, vec![], next, MAX_CHAT_PAYLOAD).unwrap_err();
+            assert_eq!(err, ErrorCode::EmptyChunk.into());
+            assert_eq!(buf.length, 0);
+            assert_eq!(buf.sha_chain, [0u8; 32]);
+        });
+    }
+
+    /// Consecutive chunks chained with next_chain are accepted; a stale hash is refused.
+    #[test]
+    fn chained_chunks_accepted() {
+        with_buffer(|buf, who| {
+            let (a, b) = (vec![1u8; MAX_CHUNK], vec![2u8; 10]);
+            let h1 = next_chain(&buf.sha_chain, &a);
+            write_chunk(buf, who, 0, a, h1, MAX_CHAT_PAYLOAD).unwrap();
+            let err = write_chunk(buf, who, MAX_CHUNK as u32, b.clone(), h1, MAX_CHAT_PAYLOAD).unwrap_err();
+            assert_eq!(err, ErrorCode::HashMismatch.into());
+            let h2 = next_chain(&h1, &b);
+            write_chunk(buf, who, MAX_CHUNK as u32, b, h2, MAX_CHAT_PAYLOAD).unwrap();
+            assert_eq!(buf.sha_chain, h2);
+        });
+    }
+
+    /// The returned progress is the buffer's new length and chain, ready for the next chunk.
+    #[test]
+    fn progress_returned_after_chunk() {
+        with_buffer(|buf, who| {
+            let (a, b) = (vec![6u8; MAX_CHUNK], vec![7u8; 33]);
+            let h1 = next_chain(&buf.sha_chain, &a);
+            let p1 = write_chunk(buf, who, 0, a, h1, MAX_CHAT_PAYLOAD).unwrap();
+            assert_eq!(p1, UploadProgress { length: buf.length, sha_chain: buf.sha_chain });
+            assert_eq!((p1.length as usize, p1.sha_chain), (MAX_CHUNK, h1));
+
+            let h2 = next_chain(&p1.sha_chain, &b);
+            let p2 = write_chunk(buf, who, p1.length, b.clone(), h2, MAX_CHAT_PAYLOAD).unwrap();
+            assert_eq!((p2.length, p2.sha_chain), (buf.length, buf.sha_chain));
+            assert_eq!(p2.length as usize, MAX_CHUNK + b.len());
+
+            // A resent chunk reports the same state.
+            assert_eq!(write_chunk(buf, who, p1.length, b, h2, MAX_CHAT_PAYLOAD).unwrap(), p2);
+            assert_eq!(p2.try_to_vec().unwrap().len(), 36);
+        });
+    }
+
+    /// A full signature uploads; one byte past SIG_BYTES is refused with SigTooLong before anything is written.
+    #[test]
+    fn sig_overrun_rejected_early() {
+        with_buffer(|buf, who| {
+            let sig: Vec<u8> = (0..SIG_BYTES).map(|i| i as u8).collect();
+            let mut off = 0u32;
+            for chunk in sig.chunks(MAX_CHUNK) {
+                check_sig_end(off, chunk.len()).unwrap();
+                let next = next_chain(&buf.sha_chain, chunk);
+                off = write_chunk(buf, who, off, chunk.to_vec(), next, MAX_SIG_PAYLOAD).unwrap().length;
+            }
+            assert_eq!(off as usize, SIG_BYTES);
+
+            let too_long: Error = ErrorCode::SigTooLong.into();
+            assert_eq!(check_sig_end(off, 1).unwrap_err(), too_long);
+            assert_eq!(check_sig_end(off - 10, 11).unwrap_err(), too_long);
+            assert_eq!(check_sig_end(u32::MAX, MAX_CHUNK).unwrap_err(), too_long);
+            // The last chunk resent as a retry still ends at SIG_BYTES and passes.
+            assert!(check_sig_end(off - (SIG_BYTES % MAX_CHUNK) as u32, SIG_BYTES % MAX_CHUNK).is_ok());
+            assert_eq!(buf.length as usize, SIG_BYTES);
+        });
+    }
+
+    /// Default builds chain with plain SHA-256 over prev || data.
+    #[cfg(not(feature = "blake3-chain"))]
+    #[test]
+    fn chain_is_sha256_by_default() {
+        let prev = [7u8; 32];
+        let expected = anchor_lang::solana_program::hash::hash(&[&prev[..], b"abc"].concat()).to_bytes();
+        assert_eq!(next_chain(&prev, b"abc"), expected);
+    }
+
+    /// fold_chain pins the zero seed and chunk order and matches the sha_chain write_chunk leaves behind.
+    #[cfg(not(feature = "blake3-chain"))]
+    #[test]
+    fn fold_chain_known_value() {
+        let tail = b"world".repeat(100);
+        let chunks: [&[u8]; 2] = [b"hello", &tail];
+        let expected = [
+            0x42, 0x9e, 0x97, 0x63, 0xd9, 0x67, 0x6b, 0xf0, 0x13, 0x1a, 0xd7, 0xd3, 0x87, 0x0b, 0xc0, 0x93,
+            0x99, 0x03, 0x7c, 0x51, 0x33, 0xa9, 0x0d, 0x28, 0xd3, 0x75, 0xd8, 0x1a, 0x76, 0x23, 0xe3, 0x41,
+        ];
+        assert_eq!(fold_chain(&chunks), expected);
+        assert_eq!(fold_chain(&[]), [0u8; 32]);
+        assert_ne!(fold_chain(&[&tail, b"hello"]), expected);
+
+        with_buffer(|buf, who| {
+            let mut off = 0u32;
+            for c in chunks {
+                let next = next_chain(&buf.sha_chain, c);
+                write_chunk(buf, who, off, c.to_vec(), next, MAX_CHAT_PAYLOAD).unwrap();
+                off += c.len() as u32;
+            }
+            assert_eq!(buf.sha_chain, expected);
+        });
+    }
+
+    /// reset_message empties a half-uploaded buffer; a different body then uploads from offset 0 on the zero seed.
+    #[test]
+    fn reset_then_reupload() {
+        with_buffer(|buf, who| {
+            let first = vec![3u8; MAX_CHUNK];
+            write_chunk(buf, who, 0, first.clone(), next_chain(&[0u8; 32], &first), MAX_CHAT_PAYLOAD).unwrap();
+            assert_eq!(buf.length as usize, MAX_CHUNK);
+
+            crate::init::reset_buffer(buf);
+            assert_eq!((buf.length, buf.sha_chain), (0, [0u8; 32]));
+            let stale = next_chain(&fold_chain(&[&first]), &[4u8; 10]);
+            let err = write_chunk(buf, who, MAX_CHUNK as u32, vec![4u8; 10], stale, MAX_CHAT_PAYLOAD).unwrap_err();
+            assert_eq!(err, ErrorCode::OffsetMismatch.into());
+
+            let (a, b) = (vec![5u8; MAX_CHUNK], vec![6u8; 40]);
+            write_chunk(buf, who, 0, a.clone(), fold_chain(&[&a]), MAX_CHAT_PAYLOAD).unwrap();
+            write_chunk(buf, who, MAX_CHUNK as u32, b.clone(), fold_chain(&[&a, &b]), MAX_CHAT_PAYLOAD).unwrap();
+            assert_eq!(buf.length as usize, MAX_CHUNK + 40);
+            assert_eq!(buf.sha_chain, fold_chain(&[&a, &b]));
+        });
+    }
+
+    /// Re-sending the last chunk returns Ok and leaves the buffer untouched.
+    #[test]
+    fn resent_last_chunk_is_noop() {
+        with_buffer(|buf, who| {
+            let (a, b) = (vec![3u8; MAX_CHUNK], vec![4u8; 20]);
+            let h1 = next_chain(&buf.sha_chain, &a);
+            write_chunk(buf, who, 0, a, h1, MAX_CHAT_PAYLOAD).unwrap();
+            let h2 = next_chain(&h1, &b);
+            write_chunk(buf, who, MAX_CHUNK as u32, b.clone(), h2, MAX_CHAT_PAYLOAD).unwrap();
+            let before = (buf.length, buf.sha_chain);
+
+            write_chunk(buf, who, MAX_CHUNK as u32, b.clone(), h2, MAX_CHAT_PAYLOAD).unwrap();
+            assert_eq!((buf.length, buf.sha_chain), before);
+
+            // Same offset with different bytes is still an offset mismatch.
+            let mut other = b.clone();
+            other[0] ^= 1;
+            let err = write_chunk(buf, who, MAX_CHUNK as u32, other, h2, MAX_CHAT_PAYLOAD).unwrap_err();
+            assert_eq!(err, ErrorCode::OffsetMismatch.into());
+        });
+    }
+
+    /// Offsets near u32::MAX never wrap: the span check reports MsgTooBig and the buffer is untouched.
+    #[test]
+    fn large_offset_rejected() {
+        for offset in [u32::MAX, u32::MAX - MAX_CHUNK as u32, MAX_CHAT_PAYLOAD as u32] {
+            let err = chunk_span(offset, MAX_CHUNK, MAX_CHAT_PAYLOAD, BUF_ACCOUNT_SPACE).unwrap_err();
+            assert_eq!(err, ErrorCode::MsgTooBig.into());
+        }
+        // Within hard_max but past the account data is refused as well.
+        let err = chunk_span(0, 10, usize::MAX, BUF_HEAD + 9).unwrap_err();
+        assert_eq!(err, ErrorCode::MsgTooBig.into());
+        assert_eq!(chunk_span(900, 100, MAX_CHAT_PAYLOAD, BUF_ACCOUNT_SPACE).unwrap(), (BUF_HEAD + 900, BUF_HEAD + 1_000));
+
+        with_buffer(|buf, who| {
+            let data = vec![5u8; 10];
+            let next = next_chain(&buf.sha_chain, &data);
+            assert!(write_chunk(buf, who, u32::MAX, data, next, MAX_CHAT_PAYLOAD).is_err());
+            assert_eq!((buf.length, buf.sha_chain), (0, [0u8; 32]));
+        });
+    }
+}
@@ -23,11 +23,29 @@ use hybrid_array::{Array, ArraySize};
 use sha2::{Sha256, Sha512};
 use typenum::{Diff, Sum, U, U128, U16, U24, U30, U32, U34, U39, U42, U47, U49, U64};
 
+/// Declares the most slices one `sol_sha256` call accepts (the runtime's `sha256_max_slices`)
+/// Callers with more parts are hashed over their concatenation instead, which yields the same digest
+pub(crate) const SHA256_MAX_PARTS: usize = 20_000;
+
+/// Joins the parts into one buffer when there are more than SHA256_MAX_PARTS, otherwise returns None
+fn coalesce_parts(parts: &[&[u8]]) -> Option<Vec<u8>> {
+    (parts.len() > SHA256_MAX_PARTS).then(|| parts.concat())
+}
+
 /// Computes a single SHA 256 digest
 /// Uses `solana_program::hash::hashv` on chain and falls back to `Sha256` off chain
-/// Accepts multiple parts as concatenated inputs
+/// Accepts multiple parts as concatenated inputs; never passes more than SHA256_MAX_PARTS slices to hashv
 #[inline]
 fn sha256_syscall(parts: &[&[u8]]) -> [u8; 32] {
+    let joined = coalesce_parts(parts);
+    let single;
+    let parts = match &joined {
+        Some(buf) => {
+            single = [buf.as_slice()];
+            &single[..]
+        }
+        None => parts,
+    };
     #[cfg(target_os = "solana")]
     {
         use solana_program::hash::hashv;
@@ -103,6 +121,7 @@ where
     }
 
     /// Computes H_msg as MGF1 SHA256 over rand || pk_seed || SHA256(rand || pk_seed || pk_root || msg)
+    /// A msg split into more than SHA256_MAX_PARTS - 3 slices is joined before hashing (see sha256_syscall)
     fn h_msg(
         rand: &Array<u8, Self::N>,
         pk_seed: &PkSeed<Self::N>,
@@ -143,7 +162,7 @@ where
     }
 
     /// Computes T_l as SHA256(pk_seed || zeroPad(64-N) || ADRSc || M[0] .. M[L-1])
-    /// Builds parts with Vec to avoid generic const expressions
+    /// Builds parts with Vec to avoid generic const expressions; L is at most 35 here (WOTS len), far below SHA256_MAX_PARTS
     fn t<L: ArraySize>(
         pk_seed: &PkSeed<Self::N>,
         adrs: &impl Address,
@@ -474,3 +493,27 @@ impl ParameterSet for Sha2_256f {
     const ALGORITHM_OID: pkcs8::ObjectIdentifier =
         pkcs8::ObjectIdentifier::new_unwrap("1.3.6.1.4.1.99999.1.6");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// H_msg over a message split into more slices than hashv accepts equals H_msg over the joined message
+    #[test]
+    fn h_msg_many_parts() {
+        let rand = Array::<u8, U16>::from_fn(|i| i as u8);
+        let pk_seed = PkSeed(Array::<u8, U16>::from_fn(|i| 0x40 | i as u8));
+        let pk_root = Array::<u8, U16>::from_fn(|i| 0x80 | i as u8);
+        let msg: Vec<u8> = (0..SHA256_MAX_PARTS + 7).map(|i| i as u8).collect();
+        let parts: Vec<&[u8]> = msg.chunks(1).collect();
+
+        let joined = Sha2_128s::h_msg(&rand, &pk_seed, &pk_root, &[&msg]);
+        assert_eq!(Sha2_128s::h_msg(&rand, &pk_seed, &pk_root, &parts), joined);
+        let few = Sha2_128s::h_msg(&rand, &pk_seed, &pk_root, &parts[..4]);
+        assert_eq!(few, Sha2_128s::h_msg(&rand, &pk_seed, &pk_root, &[&msg[..4]]));
+
+        assert!(coalesce_parts(&parts[..SHA256_MAX_PARTS]).is_none());
+        assert_eq!(coalesce_parts(&parts).unwrap(), msg);
+        assert_eq!(sha256_syscall(&parts), <[u8; 32]>::from(Sha256::digest(&msg)));
+    }
+}
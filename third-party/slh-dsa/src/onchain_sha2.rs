@@ -2,6 +2,7 @@
 //! Uses Solana hashv for SHA 256 on chain and uses Sha256 off chain
 //! Parses signature and XMSS layers directly from slices and avoids large temporary objects
 //! Keeps steps aligned with FIPS 205 and returns signature errors on any mismatch
+//! Supports pure SLH DSA and HashSLH DSA with SHA 256 prehash, both with an empty context
 
 #![cfg_attr(target_arch = "bpf", no_std)]
 
@@ -18,21 +19,52 @@ use crate::{
     fors::ForsParams,
     hashes::{HashSuite, Sha2_128s},
     hypertree::HypertreeParams,
-    util::{base_2b, split_digest},
+    util::{base_2b, pure_prefix, split_digest},
     verifying_key::VerifyingKey,
+    PkSeed,
     xmss::{XmssParams, XmssSig},
 };
 
 type P = Sha2_128s;                         // L1 / 128s
 type N = <P as HashSuite>::N;               // 16 bytes
 type BytesN = Array<u8, N>;
+type M = <P as HashSuite>::M;               // 30 bytes
+
+/// Declares the H_msg output length for 128s (m = 30 bytes)
+pub const H_MSG_LEN: usize = 30;
 
 /// Declares the SLH DSA SHA2 128s signature length from FIPS 205
 pub const SIG_LEN_128S: usize = 7_856;
 
+/// Declares the length of the randomizer R that opens every signature (N bytes)
+pub const RAND_LEN: usize = 16;
+
+/// Declares the FORS signature length for 128s: K (14) trees of sk plus A (12) auth nodes, N bytes each
+pub const FORS_SIG_LEN: usize = 14 * (12 + 1) * 16;
+
+/// Declares the hypertree depth for 128s: D (7) XMSS layers follow the FORS signature
+pub const HT_LAYERS: usize = 7;
+
+/// Declares one XMSS layer signature length for 128s: len (35) WOTS chains plus H' (9) auth nodes, N bytes each
+pub const XMSS_SIG_LEN: usize = (35 + 9) * 16;
+
 /// Declares the XMSS height per layer for 128s, where H' equals 9
 const H_PRIME: usize = 9;
 
+/// Declares the DER encoded OID of SHA 256 (2.16.840.1.101.3.4.2.1) used in the HashSLH DSA framing
+pub const SHA256_OID_DER: [u8; 11] = [0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+/// Declares the SHA 256 prehash digest length
+pub const PREHASH_LEN: usize = 32;
+
+/// Declares the M' prefix for pure mode with an empty context: 0 || |ctx| = 0
+const PURE_PREFIX: [u8; 2] = pure_prefix(0);
+
+/// Declares the M' prefix for HashSLH DSA with SHA 256 and an empty context: 1 || |ctx| = 0 || OID
+const PREHASH_PREFIX: [u8; 13] = [
+    1, 0, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01,
+];
+
 /* SHA 256 helper that uses hashv on chain and falls back off chain */
 #[inline]
 fn sha256_syscall(parts: &[&[u8]]) -> [u8; 32] {
@@ -71,29 +103,15 @@ fn mgf1_sha256<L: ArraySize>(seed: &[u8]) -> Array<u8, L> {
     out
 }
 
-/// Verifies SLH DSA SHA2 128s in a streaming manner
-/// Accepts raw message, raw signature and raw verifying key that equals pk_seed || pk_root
-/// Returns Ok on success and returns Error on failure
-#[inline(never)]
-pub fn verify_sha2_128s(msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), SigErr> {
-    // Check input length first to avoid work
-    if sig.len() != SIG_LEN_128S {
-        return Err(SigErr::new());
-    }
-
-    // Parse verifying key without allocation
-    let vk = VerifyingKey::<P>::try_from(vk_raw).map_err(|_| SigErr::new())?;
-
-    // Compute h_msg with empty context prefix [0, 0]
-    let rand: BytesN = (&sig[..N::USIZE]).try_into().map_err(|_| SigErr::new())?;
-    let ctx_prefix = [0u8; 2];
-
-    // inner = SHA256(rand || pk_seed || pk_root || 0 || 0 || msg)
+/// Computes H_msg as MGF1 SHA256 over rand || pk_seed || SHA256(rand || pk_seed || pk_root || M')
+/// M' = prefix || msg, where prefix is PURE_PREFIX (pure mode) or PREHASH_PREFIX (msg is then the digest)
+fn h_msg_digest(rand: &BytesN, vk: &VerifyingKey<P>, prefix: &[u8], msg: &[u8]) -> Array<u8, M> {
+    // inner = SHA256(rand || pk_seed || pk_root || prefix || msg)
     let inner = sha256_syscall(&[
         rand.as_slice(),
         vk.pk_seed.as_ref(),
         vk.pk_root.as_slice(),
-        &ctx_prefix,
+        prefix,
         msg,
     ]);
 
@@ -104,25 +122,51 @@ pub fn verify_sha2_128s(msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), Sig
         .concat(Array::<u8, typenum::U32>(inner));
 
     // digest = MGF1 SHA256 over seed
-    let digest = mgf1_sha256::<<P as HashSuite>::M>(seed.as_slice());
+    mgf1_sha256::<M>(seed.as_slice())
+}
 
-    // Split digest into md, idx_tree and idx_leaf
-    let (md, mut idx_tree, idx_leaf0) = split_digest::<P>(&digest);
+/// Exposes H_msg for 128s so callers can compare the digest signer and verifier derive
+/// Accepts raw message, the 16 byte randomizer R from the signature and the raw verifying key
+/// Returns Error on any length mismatch
+pub fn h_msg_sha2_128s(msg: &[u8], rand: &[u8], vk_raw: &[u8]) -> Result<[u8; H_MSG_LEN], SigErr> {
+    let rand: BytesN = rand.try_into().map_err(|_| SigErr::new())?;
+    let vk = VerifyingKey::<P>::try_from(vk_raw).map_err(|_| SigErr::new())?;
+    let digest = h_msg_digest(&rand, &vk, &PURE_PREFIX, msg);
+    let mut out = [0u8; H_MSG_LEN];
+    out.copy_from_slice(digest.as_slice());
+    Ok(out)
+}
+
+/// Computes the FORS public key (the root the first XMSS layer signs) from a FORS signature slice
+/// Accepts `md`, `idx_tree` and `idx_leaf` as split from `H_msg`, and the FORS part of the signature after R
+/// Lets callers inspect the intermediate root without running the hypertree
+///
+/// # Errors
+/// Returns Error when `fors_sig` is not `FORS_SIG_LEN` bytes, `md` is too short for K indices or `pk_seed` is not N bytes
+pub fn fors_root(
+    fors_sig: &[u8],
+    md: &[u8],
+    pk_seed: &[u8],
+    idx_tree: u64,
+    idx_leaf: u32,
+) -> Result<[u8; RAND_LEN], SigErr> {
+    if fors_sig.len() != FORS_SIG_LEN || md.len() < <P as ForsParams>::MD::USIZE {
+        return Err(SigErr::new());
+    }
+    let pk_seed = &PkSeed::<N>(pk_seed.try_into().map_err(|_| SigErr::new())?);
 
     // Derive K indices from md
-    let msg_idx =
-        base_2b::<<P as ForsParams>::K, <P as ForsParams>::A>(md.as_slice());
+    let msg_idx = base_2b::<<P as ForsParams>::K, <P as ForsParams>::A>(md);
 
-    // Process FORS in a streaming way
-    let mut cursor = N::USIZE; // skip rand
+    let mut cursor = 0;
     let mut roots = Array::<BytesN, U14>::default(); // K equals 14 for 128s
 
-    let mut adrs = ForsTree::new(idx_tree, idx_leaf0);
+    let mut adrs = ForsTree::new(idx_tree, idx_leaf);
     let adrs_roots = adrs.clone();
 
     for i in 0..<P as ForsParams>::K::USIZE {
         // Borrow sk_i as a slice view
-        let sk: &BytesN = (&sig[cursor..cursor + N::USIZE])
+        let sk: &BytesN = (&fors_sig[cursor..cursor + N::USIZE])
             .try_into()
             .map_err(|_| SigErr::new())?;
         cursor += N::USIZE;
@@ -132,12 +176,12 @@ pub fn verify_sha2_128s(msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), Sig
         adrs.tree_index.set(leaf_idx);
 
         // Compute leaf
-        let mut node = P::f(&vk.pk_seed, &adrs, sk);
+        let mut node = P::f(pk_seed, &adrs, sk);
 
         // Consume authentication path of A levels
         let mut idx = leaf_idx;
         for h in 0..<P as ForsParams>::A::USIZE {
-            let sib: &BytesN = (&sig[cursor..cursor + N::USIZE])
+            let sib: &BytesN = (&fors_sig[cursor..cursor + N::USIZE])
                 .try_into()
                 .map_err(|_| SigErr::new())?;
             cursor += N::USIZE;
@@ -146,9 +190,9 @@ pub fn verify_sha2_128s(msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), Sig
             adrs.tree_index.set(idx >> 1);
 
             node = if idx & 1 == 0 {
-                P::h(&vk.pk_seed, &adrs, &node, sib)
+                P::h(pk_seed, &adrs, &node, sib)
             } else {
-                P::h(&vk.pk_seed, &adrs, sib, &node)
+                P::h(pk_seed, &adrs, sib, &node)
             };
             idx >>= 1;
         }
@@ -156,12 +200,66 @@ pub fn verify_sha2_128s(msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), Sig
     }
 
     // Hash FORS roots into a single root
-    let mut root = P::t(&vk.pk_seed, &adrs_roots.fors_roots(), &roots);
+    Ok(P::t(pk_seed, &adrs_roots.fors_roots(), &roots).0)
+}
+
+/// Points the hypertree WOTS address at one layer; only these three fields change between layers
+/// `xmss_pk_from_sig` works on a clone, so `chain_adrs` and `hash_adrs` stay 0 across iterations
+#[inline]
+fn set_layer_adrs(adrs: &mut WotsHash, layer: u32, tree_addr: u64, leaf: u32) {
+    adrs.layer_adrs.set(layer);
+    adrs.tree_adrs_low.set(tree_addr);
+    adrs.key_pair_adrs.set(leaf);
+}
+
+/// Verifies SLH DSA SHA2 128s in a streaming manner
+/// Accepts raw message, raw signature and raw verifying key that equals pk_seed || pk_root
+/// Returns Ok on success and returns Error on failure
+#[inline(never)]
+pub fn verify_sha2_128s(msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), SigErr> {
+    verify_framed(&PURE_PREFIX, msg, sig, vk_raw)
+}
 
-    // Process hypertree layer by layer
+/// Verifies a HashSLH DSA SHA2 128s signature with SHA 256 prehash over a 32 byte digest
+/// The signer signed M' = 1 || 0 || SHA256_OID_DER || digest (FIPS 205 Algorithm 23, empty context)
+/// Only the digest reaches H_msg, so the message itself is hashed once by the caller
+/// Pure signatures never verify here and prehash signatures never verify in verify_sha2_128s
+#[inline(never)]
+pub fn verify_sha2_128s_prehash(digest: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), SigErr> {
+    if digest.len() != PREHASH_LEN {
+        return Err(SigErr::new());
+    }
+    verify_framed(&PREHASH_PREFIX, digest, sig, vk_raw)
+}
+
+/// Runs the streaming verification for M' = prefix || msg
+fn verify_framed(prefix: &[u8], msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), SigErr> {
+    // Check input length first to avoid work
+    if sig.len() != SIG_LEN_128S {
+        return Err(SigErr::new());
+    }
+
+    // Parse verifying key without allocation
+    let vk = VerifyingKey::<P>::try_from(vk_raw).map_err(|_| SigErr::new())?;
+
+    // Compute h_msg over M' = prefix || msg
+    let rand: BytesN = (&sig[..N::USIZE]).try_into().map_err(|_| SigErr::new())?;
+    let digest = h_msg_digest(&rand, &vk, prefix, msg);
+
+    // Split digest into md, idx_tree and idx_leaf
+    let (md, mut idx_tree, idx_leaf0) = split_digest::<P>(&digest);
+
+    // Process FORS in a streaming way and hash its K roots into a single root
+    let mut cursor = N::USIZE; // skip rand
+    let fors_sig = &sig[cursor..cursor + FORS_SIG_LEN];
+    let mut root = Array(fors_root(fors_sig, md.as_slice(), vk.pk_seed.as_ref(), idx_tree, idx_leaf0)?);
+    cursor += FORS_SIG_LEN;
+
+    // Process hypertree layer by layer, reusing one WOTS address (tree_adrs_high stays 0 for 128s)
     const MASK: u64 = (1u64 << H_PRIME) - 1;
+    let mut adrs_w = WotsHash::default();
 
-    for layer in 0..<P as HypertreeParams>::D::USIZE {
+    for layer in 0..<P as HypertreeParams>::D::U32 {
         let (leaf, tree_addr) = if layer == 0 {
             (idx_leaf0, idx_tree)
         } else {
@@ -180,11 +278,7 @@ pub fn verify_sha2_128s(msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), Sig
         cursor += xmss_len;
 
         // Configure address and derive next root
-        let mut adrs_w = WotsHash::default();
-        adrs_w.layer_adrs.set(layer as u32);
-        adrs_w.tree_adrs_low.set(tree_addr);
-        adrs_w.tree_adrs_high.set(0);
-        adrs_w.key_pair_adrs.set(leaf);
+        set_layer_adrs(&mut adrs_w, layer, tree_addr, leaf);
 
         root = <P as XmssParams>::xmss_pk_from_sig(leaf, &xmss_sig, &root, &vk.pk_seed, &adrs_w);
     }
@@ -196,3 +290,171 @@ pub fn verify_sha2_128s(msg: &[u8], sig: &[u8], vk_raw: &[u8]) -> Result<(), Sig
         Err(SigErr::new())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+    use signature::{Keypair, Signer};
+
+    /// Checks that the exposed H_msg matches the generic suite fed with the empty context framing
+    #[test]
+    fn h_msg_matches_hash_suite() {
+        let sk = SigningKey::<Sha2_128s>::slh_keygen_internal(&[1u8; 16], &[2u8; 16], &[3u8; 16]);
+        let vk = sk.verifying_key();
+        let msg = b"h_msg framing";
+        let sig = sk.sign(msg).to_bytes();
+        let rand: BytesN = (&sig[..N::USIZE]).try_into().unwrap();
+
+        let got = h_msg_sha2_128s(msg, &sig[..N::USIZE], &vk.to_bytes()).unwrap();
+        let want = <P as HashSuite>::h_msg(&rand, &vk.pk_seed, &vk.pk_root, &[&[0u8, 0][..], &msg[..]]);
+        assert_eq!(&got[..], want.as_slice());
+        assert!(h_msg_sha2_128s(msg, &sig[..8], &vk.to_bytes()).is_err());
+    }
+
+    /// The streaming and generic paths hash the same M' for an empty context, so a signature from either verifies in both
+    #[test]
+    fn pure_framing_matches_generic() {
+        use signature::Verifier;
+        let sk = SigningKey::<Sha2_128s>::slh_keygen_internal(&[3u8; 16], &[1u8; 16], &[4u8; 16]);
+        let vk = sk.verifying_key();
+        let vk_raw = vk.to_bytes();
+        let blob: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+        for msg in [&b""[..], &b"\0"[..], &b"framing"[..], &blob[..]] {
+            // Generic path: try_verify_with_context feeds [0 || |ctx|, ctx, msg] to h_msg
+            let generic = sk.sign(msg);
+            let rand: BytesN = (&generic.to_bytes()[..N::USIZE]).try_into().unwrap();
+            let generic_input = <P as HashSuite>::h_msg(&rand, &vk.pk_seed, &vk.pk_root, &[&[0u8, 0][..], &[][..], msg]);
+            let streaming_input = h_msg_digest(&rand, &vk, &PURE_PREFIX, msg);
+            assert_eq!(generic_input, streaming_input);
+            assert_eq!(h_msg_sha2_128s(msg, rand.as_slice(), &vk_raw).unwrap()[..], streaming_input[..]);
+            assert!(verify_sha2_128s(msg, &generic.to_bytes(), &vk_raw).is_ok());
+
+            // Streaming framing signed directly still verifies through the generic verifier
+            let direct = sk.slh_sign_internal(&[&PURE_PREFIX[..], msg], None);
+            assert!(vk.verify(msg, &direct).is_ok());
+            assert!(verify_sha2_128s(msg, &direct.to_bytes(), &vk_raw).is_ok());
+
+            // A non-empty context frames differently and verifies on neither pure path
+            let with_ctx = sk.try_sign_with_context(msg, b"ctx", None).unwrap();
+            assert!(vk.try_verify_with_context(msg, b"ctx", &with_ctx).is_ok());
+            assert!(vk.verify(msg, &with_ctx).is_err());
+            assert!(verify_sha2_128s(msg, &with_ctx.to_bytes(), &vk_raw).is_err());
+        }
+        assert_eq!(PURE_PREFIX, [0, 0]);
+    }
+
+    /// An empty message signs and verifies; the streaming verifier agrees with the generic one
+    #[test]
+    fn empty_message_verifies() {
+        let sk = SigningKey::<Sha2_128s>::slh_keygen_internal(&[4u8; 16], &[5u8; 16], &[6u8; 16]);
+        let vk = sk.verifying_key();
+        let sig = sk.sign(b"");
+        assert!(signature::Verifier::verify(&vk, b"", &sig).is_ok());
+        assert!(verify_sha2_128s(b"", &sig.to_bytes(), &vk.to_bytes()).is_ok());
+        assert!(verify_sha2_128s(b"\0", &sig.to_bytes(), &vk.to_bytes()).is_err());
+    }
+
+    /// A prehash signature verifies only on the prehash path, and a pure one only on the pure path
+    #[test]
+    fn prehash_signature_verifies() {
+        use sha2::{Digest, Sha256};
+        let sk = SigningKey::<Sha2_128s>::slh_keygen_internal(&[1u8; 16], &[2u8; 16], &[3u8; 16]);
+        let vk = sk.verifying_key().to_bytes();
+        let msg = vec![0x5au8; 20_000];
+        let digest: [u8; 32] = Sha256::digest(&msg).into();
+        let pre = sk.slh_sign_internal(&[&[1u8, 0][..], &SHA256_OID_DER, &digest], None).to_bytes();
+        let pure = sk.sign(&msg).to_bytes();
+
+        assert_eq!(PREHASH_PREFIX[2..], SHA256_OID_DER);
+        assert!(verify_sha2_128s_prehash(&digest, &pre, &vk).is_ok());
+        assert!(verify_sha2_128s_prehash(&digest, &pure, &vk).is_err());
+        assert!(verify_sha2_128s(&msg, &pre, &vk).is_err());
+        assert!(verify_sha2_128s(&digest, &pre, &vk).is_err());
+        assert!(verify_sha2_128s(&msg, &pure, &vk).is_ok());
+
+        let mut other = digest;
+        other[31] ^= 1;
+        assert!(verify_sha2_128s_prehash(&other, &pre, &vk).is_err());
+        assert!(verify_sha2_128s_prehash(&digest[..31], &pre, &vk).is_err());
+    }
+
+    /// The factored FORS root equals the one the generic verifier derives, and moves with any change to its inputs
+    #[test]
+    fn fors_root_matches_generic() {
+        use crate::fors::ForsSignature;
+        let sk = SigningKey::<Sha2_128s>::slh_keygen_internal(&[7u8; 16], &[8u8; 16], &[9u8; 16]);
+        let vk = sk.verifying_key();
+        let sig = sk.sign(b"fors root").to_bytes();
+        let rand: BytesN = (&sig[..N::USIZE]).try_into().unwrap();
+        let digest = h_msg_digest(&rand, &vk, &PURE_PREFIX, b"fors root");
+        let (md, idx_tree, idx_leaf) = split_digest::<P>(&digest);
+        let fors_sig = &sig[N::USIZE..N::USIZE + FORS_SIG_LEN];
+
+        assert_eq!(FORS_SIG_LEN, ForsSignature::<P>::SIZE);
+        assert_eq!((RAND_LEN, HT_LAYERS, XMSS_SIG_LEN), (N::USIZE, <P as HypertreeParams>::D::USIZE, XmssSig::<P>::SIZE));
+        assert_eq!(RAND_LEN + FORS_SIG_LEN + HT_LAYERS * XMSS_SIG_LEN, SIG_LEN_128S);
+        let generic = ForsSignature::<P>::try_from(fors_sig).unwrap();
+        let want = P::fors_pk_from_sig(&generic, md, &vk.pk_seed, &ForsTree::new(idx_tree, idx_leaf));
+        let got = fors_root(fors_sig, md.as_slice(), vk.pk_seed.as_ref(), idx_tree, idx_leaf).unwrap();
+        assert_eq!(got, want.0);
+
+        let mut flipped = fors_sig.to_vec();
+        flipped[0] ^= 1;
+        assert_ne!(fors_root(&flipped, md.as_slice(), vk.pk_seed.as_ref(), idx_tree, idx_leaf).unwrap(), want.0);
+        assert_ne!(fors_root(fors_sig, md.as_slice(), vk.pk_seed.as_ref(), idx_tree ^ 1, idx_leaf).unwrap(), want.0);
+        assert!(fors_root(&fors_sig[1..], md.as_slice(), vk.pk_seed.as_ref(), idx_tree, idx_leaf).is_err());
+        assert!(fors_root(fors_sig, &md[1..], vk.pk_seed.as_ref(), idx_tree, idx_leaf).is_err());
+        assert!(fors_root(fors_sig, md.as_slice(), &vk.pk_seed.as_ref()[1..], idx_tree, idx_leaf).is_err());
+    }
+
+    /// A WOTS address reset layer by layer encodes the same bytes as one built fresh for each layer
+    #[test]
+    fn reset_wots_adrs_matches_fresh() {
+        use crate::address::Address;
+        use zerocopy::AsBytes;
+        let mut reused = WotsHash::default();
+        let layers = [(0u32, u64::MAX >> 9, 511u32), (1, 0x0123_4567_89ab, 0), (6, 0, 7), (3, 42, 300)];
+        for (layer, tree_addr, leaf) in layers {
+            let mut fresh = WotsHash::default();
+            fresh.layer_adrs.set(layer);
+            fresh.tree_adrs_low.set(tree_addr);
+            fresh.tree_adrs_high.set(0);
+            fresh.key_pair_adrs.set(leaf);
+
+            set_layer_adrs(&mut reused, layer, tree_addr, leaf);
+            assert_eq!(reused.as_bytes(), fresh.as_bytes());
+            assert_eq!(reused.compressed(), fresh.compressed());
+        }
+    }
+
+    /// An empty trailing part hashes the same through hashv (the on-chain path) and sha2 (off chain)
+    #[test]
+    fn empty_trailing_part_cross_target() {
+        let head = [7u8; 66];
+        for parts in [&[&head[..], &[][..]][..], &[&[][..]][..], &[&head[..], &[][..], &[][..]][..]] {
+            let joined: Vec<u8> = parts.concat();
+            let soft = sha256_syscall(parts);
+            assert_eq!(soft, solana_program::hash::hashv(parts).to_bytes());
+            assert_eq!(soft, solana_program::hash::hash(&joined).to_bytes());
+        }
+    }
+
+    /// The off-chain branch equals plain sha2 and hashv for inputs around the 64-byte block, whole or split
+    #[test]
+    fn sha256_syscall_matches_reference() {
+        use sha2::{Digest, Sha256};
+        for len in [0usize, 1, 55, 56, 63, 64, 65, 128, 1000] {
+            let input: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+            let want: [u8; 32] = Sha256::digest(&input).into();
+            let (half, one) = (len / 2, len.min(1));
+            let whole: [&[u8]; 1] = [&input];
+            let halves: [&[u8]; 2] = [&input[..half], &input[half..]];
+            let ragged: [&[u8]; 3] = [&input[..one], &input[one..], &[]];
+            for parts in [&whole[..], &halves[..], &ragged[..]] {
+                assert_eq!(sha256_syscall(parts), want, "len {len}, {} parts", parts.len());
+                assert_eq!(solana_program::hash::hashv(parts).to_bytes(), want, "len {len}, {} parts", parts.len());
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::address::{ForsTree, WotsHash};
 use crate::signature_encoding::Signature;
-use crate::util::split_digest;
+use crate::util::{pure_prefix, split_digest};
 use crate::verifying_key::VerifyingKey;
 use crate::{ParameterSet, PkSeed, Sha2L1, Sha2L35, Shake, VerifyingKeyLen};
 use ::signature::{Error, KeypairRef, RandomizedSigner, Signer, rand_core::CryptoRng};
@@ -148,9 +148,9 @@ impl<P: ParameterSet> SigningKey<P> {
         opt_rand: Option<&[u8]>,
     ) -> Result<Signature<P>, Error> {
         let ctx_len = u8::try_from(ctx.len()).map_err(|_| Error::new())?;
-        let ctx_len_bytes = ctx_len.to_be_bytes();
+        let prefix = pure_prefix(ctx_len);
 
-        let ctx_msg = [&[0], &ctx_len_bytes, ctx, msg];
+        let ctx_msg = [&prefix[..], ctx, msg];
         Ok(self.slh_sign_internal(&ctx_msg, opt_rand))
     }
 
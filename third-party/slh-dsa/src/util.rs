@@ -1,6 +1,12 @@
 use crate::fors::ForsParams;
 use hybrid_array::{Array, ArraySize, typenum::Unsigned};
 
+/// The M' prefix of pure SLH-DSA (FIPS 205 Algorithms 22 and 24): 0 || |ctx|, ahead of ctx and the message
+/// Shared by the generic signer and verifier and by onchain_sha2, so every path frames messages the same way
+pub(crate) const fn pure_prefix(ctx_len: u8) -> [u8; 2] {
+    [0, ctx_len]
+}
+
 // Algorithm 3
 pub fn base_2b<OutLen: ArraySize, B: Unsigned>(x: &[u8]) -> Array<u16, OutLen> {
     debug_assert!(x.len() >= (OutLen::USIZE * B::USIZE + 7) / 8);
@@ -4,7 +4,7 @@ use crate::Sha2L35;
 use crate::Shake;
 use crate::address::ForsTree;
 use crate::signature_encoding::Signature;
-use crate::util::split_digest;
+use crate::util::{pure_prefix, split_digest};
 use ::signature::{Error, Verifier};
 use hybrid_array::{Array, ArraySize};
 use pkcs8::{der, spki};
@@ -86,9 +86,9 @@ impl<P: ParameterSet + VerifyingKeyLen> VerifyingKey<P> {
         signature: &Signature<P>,
     ) -> Result<(), Error> {
         let ctx_len = u8::try_from(ctx.len()).map_err(|_| Error::new())?;
-        let ctx_len_bytes = ctx_len.to_be_bytes();
+        let prefix = pure_prefix(ctx_len);
 
-        let ctx_msg = [&[0], &ctx_len_bytes, ctx, msg];
+        let ctx_msg = [&prefix[..], ctx, msg];
         self.slh_verify_internal(&ctx_msg, signature) // TODO - context processing
     }
 
@@ -10,6 +10,9 @@ use utils::ByteWriter;
 use super::{ByteDigest, ElementHasher, Hasher};
 use super::Digest as _;
 
+#[cfg(test)]
+mod tests;
+
 /// Implements SHA256 with 256 bit output.
 /// On Solana BPF it uses solana_program::hash::hashv.
 /// Off chain it uses sha2::Sha256.
@@ -29,6 +32,8 @@ impl<B: StarkField> Hasher for Sha2_256<B> {
         ByteDigest(sha256_once(ByteDigest::digests_as_bytes(values)))
     }
 
+    /// Hashes the concatenation of all digests; equals merge for two values and
+    /// SHA256 of zero bytes for an empty slice.
     #[inline]
     fn merge_many(values: &[Self::Digest]) -> Self::Digest {
         ByteDigest(sha256_once(ByteDigest::digests_as_bytes(values)))
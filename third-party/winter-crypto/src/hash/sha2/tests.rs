@@ -0,0 +1,126 @@
+use alloc::vec::Vec;
+
+use math::{
+    fields::{f128::BaseElement, f62::BaseElement as F62, f64::BaseElement as F64, QuadExtension},
+    FieldElement, StarkField,
+};
+use sha2::{Digest as _, Sha256};
+use utils::ByteWriter;
+
+use super::{sha256_once, ByteDigest, ElementHasher, Hasher, Sha2_256, Sha256Writer};
+
+type H = Sha2_256<BaseElement>;
+
+fn digests(n: usize) -> Vec<ByteDigest<32>> {
+    (0..n).map(|i| ByteDigest::new([i as u8 + 1; 32])).collect()
+}
+
+fn reference(values: &[ByteDigest<32>]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    for v in values {
+        h.update(v.0);
+    }
+    h.finalize().into()
+}
+
+#[test]
+fn merge_many_two_equals_merge() {
+    let d = digests(2);
+    assert_eq!(H::merge_many(&d), H::merge(&[d[0], d[1]]));
+}
+
+#[test]
+fn merge_many_hashes_concatenation() {
+    for n in [1, 3, 8] {
+        let d = digests(n);
+        assert_eq!(H::merge_many(&d).0, reference(&d), "n = {n}");
+    }
+    // A single digest is re-hashed, not passed through.
+    let one = digests(1);
+    assert_ne!(H::merge_many(&one), one[0]);
+    assert_eq!(H::merge_many(&one), H::hash(&one[0].0));
+}
+
+#[test]
+fn merge_many_empty_is_sha256_of_empty_input() {
+    // Empty slice hashes zero bytes: SHA-256("") = e3b0c442...b855.
+    let empty = H::merge_many(&[]);
+    assert_eq!(empty, H::hash(&[]));
+    assert_eq!(empty.0[..4], [0xe3, 0xb0, 0xc4, 0x42]);
+}
+
+/// Bytes write_many produces for the elements, collected in a Vec as the BPF writer buffers them.
+fn written<E: FieldElement>(elements: &[E]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.write_many(elements);
+    buf
+}
+
+#[test]
+fn hash_elements_canonical_field_hashes_raw_bytes() {
+    assert!(BaseElement::IS_CANONICAL);
+    let e: Vec<BaseElement> = (1..=5u128).map(BaseElement::new).collect();
+    let raw = BaseElement::elements_as_bytes(&e);
+    assert_eq!(H::hash_elements(&e).0, sha256_once(raw));
+    // For a canonical field the fast path and the writer path agree.
+    assert_eq!(raw, &written(&e)[..]);
+}
+
+#[test]
+fn hash_elements_non_canonical_field_uses_writer() {
+    // f62 and f64 keep elements in Montgomery form, so elements_as_bytes is not the canonical encoding.
+    assert!(!F62::IS_CANONICAL && !F64::IS_CANONICAL);
+
+    let e: Vec<F64> = (1..=5u64).map(F64::new).collect();
+    let canonical: Vec<u8> = e.iter().flat_map(|x| x.as_int().to_le_bytes()).collect();
+    assert_eq!(written(&e), canonical);
+    assert_eq!(Sha2_256::<F64>::hash_elements(&e).0, sha256_once(&canonical));
+    assert_ne!(Sha2_256::<F64>::hash_elements(&e).0, sha256_once(F64::elements_as_bytes(&e)));
+
+    let e: Vec<F62> = (1..=5u64).map(F62::new).collect();
+    assert_eq!(Sha2_256::<F62>::hash_elements(&e).0, sha256_once(&written(&e)));
+
+    // Extension elements are written as their base coordinates.
+    let q: Vec<QuadExtension<F64>> =
+        (1..=3u64).map(|i| QuadExtension::new(F64::new(i), F64::new(i + 7))).collect();
+    let base = QuadExtension::<F64>::as_base_elements(&q);
+    assert_eq!(Sha2_256::<F64>::hash_elements(&q), Sha2_256::<F64>::hash_elements(base));
+}
+
+#[test]
+fn writer_streaming_matches_buffered_hash() {
+    // The host writer streams into sha2; the BPF writer buffers and hashes once. Both must equal sha256_once.
+    let parts: [&[u8]; 4] = [b"", b"a", &[0u8; 64], &[7u8; 129]];
+    let mut w = Sha256Writer::new();
+    let mut buf = Vec::new();
+    for p in parts {
+        w.write_bytes(p);
+        buf.write_bytes(p);
+    }
+    w.write_u8(9);
+    buf.write_u8(9);
+    assert_eq!(w.finalize(), sha256_once(&buf));
+    assert_eq!(Sha256Writer::new().finalize(), sha256_once(&[]));
+}
+
+/// Inputs around SHA-256's 64-byte block and its 55/56-byte padding edge.
+fn block_edge_inputs() -> Vec<Vec<u8>> {
+    [0usize, 1, 55, 56, 63, 64, 65, 127, 128, 1000].iter().map(|&n| (0..n).map(|i| i as u8).collect()).collect()
+}
+
+#[test]
+fn sha256_once_matches_reference_and_hashv() {
+    // The host branch must equal plain sha2 and the hashv API the BPF branch calls (single part, as sha256_once does).
+    for input in block_edge_inputs() {
+        let want: [u8; 32] = Sha256::digest(&input).into();
+        assert_eq!(sha256_once(&input), want, "len {}", input.len());
+        assert_eq!(solana_program::hash::hashv(&[&input]).to_bytes(), want, "len {}", input.len());
+    }
+    assert_eq!(
+        sha256_once(&[]),
+        [
+            0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9, 0x24,
+            0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52, 0xb8, 0x55,
+        ]
+    );
+}